@@ -6,8 +6,21 @@ pub mod voxel_grid {
 	pub mod analyze;
 	pub mod surface_area;
 	pub mod mrc_output;
+	pub mod mrc_input;
+	pub mod ezd_output;
+	pub mod vtk_output;
 	pub mod raster;
+	pub mod parallel;
 	pub mod pdb;
 	pub mod geometry;
 	pub mod pdb_output;
+	pub mod ply_output;
+	pub mod mesh;
+	pub mod float_grid;
+	pub mod distance;
+	pub mod config;
+	pub mod atom_index;
+	pub mod raycast;
+	pub mod hull;
+	pub(crate) mod storage;
 }
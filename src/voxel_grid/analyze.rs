@@ -1,6 +1,485 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::thread;
+
+use crate::voxel_grid::geometry::GridParams;
 use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::parallel::worker_threads;
+use crate::voxel_grid::pdb::{load_models_from_pdb_path, PdbOptions};
+use crate::voxel_grid::raster::Atom;
+use crate::voxel_grid::surface_area::classify_edge_point;
 
 impl Grid3D {
+	/// Label 6-connected regions of voxels whose occupancy equals `value`.
+	/// Returns one label per voxel (0 = not in a region, regions numbered from 1) and the region count.
+	pub fn connected_components(&self, value: bool) -> (Vec<u32>, usize) {
+		let mut labels = vec![0u32; self.total_voxels];
+		let mut count = 0usize;
+		let mut queue: VecDeque<usize> = VecDeque::new();
+
+		for start in 0..self.total_voxels {
+			if labels[start] != 0 || self.get_voxel_index(start) != value {
+				continue;
+			}
+			count += 1;
+			let label = count as u32;
+			labels[start] = label;
+			queue.push_back(start);
+			while let Some(idx) = queue.pop_front() {
+				for neighbor in face_neighbors(self, idx).into_iter().flatten() {
+					if labels[neighbor] == 0 && self.get_voxel_index(neighbor) == value {
+						labels[neighbor] = label;
+						queue.push_back(neighbor);
+					}
+				}
+			}
+		}
+		(labels, count)
+	}
+
+	/// Parallel `connected_components`; labels and count are identical to the serial version.
+	/// Thread count follows `parallel::worker_threads()`.
+	pub fn connected_components_parallel(&self, value: bool) -> (Vec<u32>, usize) {
+		self.connected_components_with_threads(value, worker_threads())
+	}
+
+	/// Same as `connected_components_parallel` with an explicit thread count.
+	/// Each thread labels a run of k-slabs on its own; labels touching across run boundaries are merged
+	/// with union-find, then renumbered in order of each region's lowest voxel index, as the serial BFS does.
+	pub fn connected_components_with_threads(&self, value: bool, threads: usize) -> (Vec<u32>, usize) {
+		let total_voxels = self.total_voxels;
+		let mut labels = vec![0u32; total_voxels];
+		if total_voxels == 0 {
+			return (labels, 0);
+		}
+		let slab = self.len_i * self.len_j;
+		let threads = threads.max(1).min(self.len_k);
+		let block = self.len_k.div_ceil(threads) * slab;
+
+		// Phase 1: label each block independently (local labels from 1).
+		let counts: Vec<usize> = thread::scope(|scope| {
+			let handles: Vec<_> = labels
+				.chunks_mut(block)
+				.enumerate()
+				.map(|(b, chunk)| scope.spawn(move || label_block(self, value, b * block, chunk)))
+				.collect();
+			handles.into_iter().map(|h| h.join().expect("labeling thread panicked")).collect()
+		});
+		let mut offsets = vec![0usize; counts.len()];
+		for b in 1..counts.len() {
+			offsets[b] = offsets[b - 1] + counts[b - 1];
+		}
+		let provisional = offsets[counts.len() - 1] + counts[counts.len() - 1];
+
+		// Phase 2: merge labels that meet across the face between consecutive blocks.
+		let mut parent: Vec<usize> = (0..=provisional).collect();
+		for b in 1..counts.len() {
+			let start = b * block;
+			for idx in start..start + slab {
+				let (lower, upper) = (labels[idx - slab], labels[idx]);
+				if lower != 0 && upper != 0 {
+					union(&mut parent, offsets[b - 1] + lower as usize, offsets[b] + upper as usize);
+				}
+			}
+		}
+
+		// Provisional labels already run in order of first appearance, so the smallest label of each
+		// merged region is its lowest voxel and numbering roots in that order matches the serial labels.
+		let mut final_label = vec![0u32; provisional + 1];
+		let mut root_label = vec![0u32; provisional + 1];
+		let mut count = 0usize;
+		for (p, label) in final_label.iter_mut().enumerate().skip(1) {
+			let root = find(&mut parent, p);
+			if root_label[root] == 0 {
+				count += 1;
+				root_label[root] = count as u32;
+			}
+			*label = root_label[root];
+		}
+
+		thread::scope(|scope| {
+			for (b, chunk) in labels.chunks_mut(block).enumerate() {
+				let final_label = &final_label;
+				let offset = offsets[b];
+				scope.spawn(move || {
+					for label in chunk.iter_mut().filter(|l| **l != 0) {
+						*label = final_label[offset + *label as usize];
+					}
+				});
+			}
+		});
+		(labels, count)
+	}
+
+	/// Label 6-connected patches of surface voxels (filled voxels with `classify_edge_point != 0`), e.g.
+	/// distinct exposed faces or protrusions. Same return shape as `connected_components`: one label per
+	/// voxel (0 = not a surface voxel) and the patch count. Patches only join through surface voxels.
+	pub fn surface_patches(&self) -> (Vec<u32>, usize) {
+		let mut surface = self.empty_like();
+		for idx in self.iter_filled() {
+			if classify_edge_point(self, idx) != 0 {
+				surface.fill_voxel_index(idx);
+			}
+		}
+		surface.connected_components(true)
+	}
+
+	/// Empty voxels that are not connected to the grid boundary (buried cavities), keeping only cavities of
+	/// at least `min_voxels` voxels to drop the 1-2 voxel specks left by discretization (0 or 1 keeps all).
+	/// See `min_cavity_voxels` to set the threshold as a volume.
+	pub fn interior_cavities(&self, min_voxels: usize) -> Grid3D {
+		let (labels, sizes, buried) = self.buried_cavities();
+		let mut cavities = self.empty_like();
+		for (idx, &label) in labels.iter().enumerate() {
+			if label != 0 && buried[label as usize] && sizes[label as usize] >= min_voxels {
+				cavities.fill_voxel_index(idx);
+			}
+		}
+		cavities
+	}
+
+	/// Empty-region labels with the voxel count of each label and whether it is buried (does not touch
+	/// the grid boundary); label 0 is never buried.
+	fn buried_cavities(&self) -> (Vec<u32>, Vec<usize>, Vec<bool>) {
+		let (labels, count) = self.connected_components_parallel(false);
+		let exterior = self.boundary_flags(&labels, count);
+		let mut sizes = vec![0usize; count + 1];
+		for &label in labels.iter() {
+			sizes[label as usize] += 1;
+		}
+		let buried = (0..=count).map(|label| label != 0 && !exterior[label]).collect();
+		(labels, sizes, buried)
+	}
+
+	/// Smallest voxel count whose volume reaches `min_volume_a3` (cubic angstroms) on this grid,
+	/// for `interior_cavities` and `cavity_size_histogram` thresholds given as a physical volume. At least 1.
+	pub fn min_cavity_voxels(&self, min_volume_a3: f64) -> usize {
+		((min_volume_a3 / self.voxel_volume()).ceil() as usize).max(1)
+	}
+
+	/// Grid holding only the single largest buried cavity, or `None` if there are no buried cavities.
+	pub fn largest_cavity(&self) -> Option<Grid3D> {
+		let (labels, count) = self.connected_components_parallel(false);
+		let exterior = self.boundary_flags(&labels, count);
+
+		let mut sizes = vec![0usize; count + 1];
+		for &label in labels.iter() {
+			sizes[label as usize] += 1;
+		}
+
+		// Ties go to the lowest label, i.e. the cavity reached first in index order.
+		let mut best: Option<u32> = None;
+		for label in 1..=count {
+			if exterior[label] {
+				continue;
+			}
+			if best.is_none_or(|b| sizes[label] > sizes[b as usize]) {
+				best = Some(label as u32);
+			}
+		}
+		let best = best?;
+		Some(self.extract_component(&labels, best))
+	}
+
+	/// Grid with the same dimensions, spacing, and shifts where only voxels labeled `label` are filled.
+	/// `labels` comes from `connected_components` (or the parallel version) run on this grid.
+	pub fn extract_component(&self, labels: &[u32], label: u32) -> Grid3D {
+		assert_eq!(labels.len(), self.total_voxels, "one label per voxel expected");
+		let mut component = self.empty_like();
+		for (idx, &l) in labels.iter().enumerate() {
+			if l == label {
+				component.fill_voxel_index(idx);
+			}
+		}
+		component
+	}
+
+	/// Like `extract_component`, but cropped to the component's bounding box plus `pad` empty voxels
+	/// on every side (the padding may extend past this grid). Shifts are moved so every voxel keeps its
+	/// physical position. Returns `None` if no voxel carries `label`.
+	pub fn extract_component_cropped(&self, labels: &[u32], label: u32, pad: usize) -> Option<Grid3D> {
+		assert_eq!(labels.len(), self.total_voxels, "one label per voxel expected");
+		self.crop_where(pad, |idx| labels[idx] == label)
+	}
+
+	/// Count buried cavities by volume (cubic angstroms, voxel count times voxel volume).
+	/// Bin `b` covers `[edges[b], edges[b + 1])`, the last bin also includes its upper edge (as numpy does),
+	/// and cavities outside every bin are not counted.
+	/// Cavities below `min_voxels` are skipped, as in `interior_cavities`, so the histogram describes that grid.
+	/// Returns one count per bin (`edges.len() - 1`, or none for fewer than two edges); errors with
+	/// `InvalidInput` unless `bin_edges_a3` is finite and strictly increasing.
+	pub fn cavity_size_histogram(&self, bin_edges_a3: &[f64], min_voxels: usize) -> io::Result<Vec<usize>> {
+		if bin_edges_a3.iter().any(|edge| !edge.is_finite()) || bin_edges_a3.windows(2).any(|w| w[0] >= w[1]) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("cavity histogram bin edges must be finite and strictly increasing, got {:?}", bin_edges_a3),
+			));
+		}
+		let bins = bin_edges_a3.len().saturating_sub(1);
+		let mut counts = vec![0usize; bins];
+		if bins == 0 {
+			return Ok(counts);
+		}
+
+		let (_, sizes, buried) = self.buried_cavities();
+		let voxel_volume = self.voxel_volume();
+		let last = bin_edges_a3[bins];
+		for (label, &size) in sizes.iter().enumerate() {
+			if !buried[label] || size < min_voxels {
+				continue;
+			}
+			let volume = size as f64 * voxel_volume;
+			if volume < bin_edges_a3[0] || volume > last {
+				continue;
+			}
+			// Number of edges <= volume, less one, is the bin; the top edge folds into the last bin.
+			let bin = bin_edges_a3.partition_point(|&edge| edge <= volume) - 1;
+			counts[bin.min(bins - 1)] += 1;
+		}
+		Ok(counts)
+	}
+
+	/// Keep only the largest 6-connected filled region (e.g. bulk solvent in an accessible grid).
+	/// Ties go to the lowest label. Returns the number of filled voxels left.
+	pub fn keep_largest_component(&mut self) -> usize {
+		self.filter_largest_component(true)
+	}
+
+	/// Inverse of `keep_largest_component`: drop the largest filled region and keep the rest
+	/// (e.g. internal accessible pockets and channels). Returns the number of filled voxels left.
+	pub fn remove_largest_component(&mut self) -> usize {
+		self.filter_largest_component(false)
+	}
+
+	/// Keep (`keep_largest`) or drop the largest filled component.
+	fn filter_largest_component(&mut self, keep_largest: bool) -> usize {
+		let (labels, count) = self.connected_components_parallel(true);
+		if count == 0 {
+			return 0;
+		}
+		let mut sizes = vec![0usize; count + 1];
+		for &label in labels.iter() {
+			sizes[label as usize] += 1;
+		}
+		let mut largest = 1usize;
+		for label in 2..=count {
+			if sizes[label] > sizes[largest] {
+				largest = label;
+			}
+		}
+
+		let mut remaining = 0usize;
+		for (idx, &label) in labels.iter().enumerate() {
+			if label == 0 {
+				continue;
+			}
+			if (label as usize == largest) == keep_largest {
+				remaining += 1;
+			} else {
+				self.empty_voxel_index(idx);
+			}
+		}
+		remaining
+	}
+
+	/// Filled volume (cubic angstroms) within `distance` of any reference atom center.
+	/// Reference spheres are stamped into one mask first, so overlapping spheres are counted once.
+	pub fn volume_near_atoms(&self, atoms: &[Atom], distance: f32) -> f64 {
+		let centers: Vec<Atom> = atoms
+			.iter()
+			.map(|atom| Atom { radius: 0.0, ..*atom })
+			.collect();
+		let mut mask = self.empty_like();
+		mask.fill_accessible_parallel(&centers, distance);
+
+		let count = (0..self.total_voxels)
+			.filter(|&idx| self.get_voxel_index(idx) && mask.get_voxel_index(idx))
+			.count();
+		count as f64 * self.voxel_volume()
+	}
 
+	/// Volume (cubic angstroms) attributed to each residue, from labels returned by `fill_accessible_labeled`.
+	pub fn per_residue_volume(&self, labels: &[u32]) -> HashMap<u32, f64> {
+		let voxel_volume = self.voxel_volume();
+		let mut volumes: HashMap<u32, f64> = HashMap::new();
+		for (idx, &label) in labels.iter().enumerate() {
+			if label != 0 && self.get_voxel_index(idx) {
+				*volumes.entry(label).or_insert(0.0) += voxel_volume;
+			}
+		}
+		volumes
+	}
+
+	/// Labels of components with at least one voxel on a face of the grid, from `labels` returned by
+	/// `connected_components` on this grid. On an empty-space labeling these are bulk solvent; every
+	/// other label is a buried pocket (what `interior_cavities` keeps).
+	pub fn boundary_components(&self, labels: &[u32]) -> HashSet<u32> {
+		assert_eq!(labels.len(), self.total_voxels, "one label per voxel expected");
+		labels
+			.iter()
+			.enumerate()
+			.filter(|&(idx, &label)| label != 0 && self.is_boundary_index(idx))
+			.map(|(_, &label)| label)
+			.collect()
+	}
+
+	/// Flag (indexed by label) which components have at least one voxel on the grid boundary.
+	pub(crate) fn boundary_flags(&self, labels: &[u32], count: usize) -> Vec<bool> {
+		let mut touches = vec![false; count + 1];
+		for (idx, &label) in labels.iter().enumerate() {
+			if label != 0 && !touches[label as usize] && self.is_boundary_index(idx) {
+				touches[label as usize] = true;
+			}
+		}
+		touches
+	}
+
+	/// True if the voxel lies on any face of the grid.
+	fn is_boundary_index(&self, idx: usize) -> bool {
+		let (i, j, k) = self.index_to_ijk(idx);
+		i == 0 || j == 0 || k == 0 || i + 1 == self.len_i || j + 1 == self.len_j || k + 1 == self.len_k
+	}
+}
+
+/// Solvent fraction of a box: rasterize the van der Waals spheres (no probe) into a grid spanning
+/// `cell` (lengths in angstroms, corner at the origin) and return `1 - occupancy_fraction()`.
+/// Atoms are not wrapped periodically; the box is rounded up to whole voxels of size `grid`.
+pub fn solvent_content(atoms: &[Atom], cell: [f32; 3], grid: f32) -> f64 {
+	let len_i = ((cell[0] / grid).ceil() as usize).max(1);
+	let len_j = ((cell[1] / grid).ceil() as usize).max(1);
+	let len_k = ((cell[2] / grid).ceil() as usize).max(1);
+	let mut box_grid = Grid3D::new(len_i, len_j, len_k, grid);
+	box_grid.fill_accessible_parallel(atoms, 0.0);
+	1.0 - box_grid.occupancy_fraction()
+}
+
+/// Grid parameters enclosing every atom of `atoms` (any count, including one), or a single empty
+/// block at the origin when none has a usable radius. Panics if `grid` is not finite and positive.
+fn shared_grid_params(atoms: &[Atom], probe: f32, grid: f32) -> GridParams {
+	GridParams::from_atom_bounds(atoms, probe, grid)
+		.or_else(|| GridParams::from_box([0.0; 3], [grid; 3], grid, true).ok())
+		.expect("grid spacing must be finite and positive")
+}
+
+/// Compare two conformations on one shared grid sized to enclose both atom sets.
+/// Each set is rasterized as solvent-excluded volume; returns the symmetric-difference grid and the
+/// net volume change `volume(b) - volume(a)` in cubic angstroms. Any number of atoms works, including
+/// a single ion; panics if `grid` is not finite and positive.
+pub fn volume_difference(atoms_a: &[Atom], atoms_b: &[Atom], probe: f32, grid: f32) -> (Grid3D, f64) {
+	let combined: Vec<Atom> = atoms_a.iter().chain(atoms_b).copied().collect();
+	let params = shared_grid_params(&combined, probe, grid);
+
+	let mut grid_a = params.build_grid();
+	let filled_a = grid_a.fill_excluded(atoms_a, probe);
+	let mut grid_b = params.build_grid();
+	let filled_b = grid_b.fill_excluded(atoms_b, probe);
+
+	let change = (filled_b as f64 - filled_a as f64) * grid_a.voxel_volume();
+	(grid_a.symmetric_difference(&grid_b), change)
+}
+
+/// Empty space trapped between two molecules (e.g. two docked chains), in cubic angstroms.
+/// On one shared grid, a voxel counts if it is empty in the excluded volume of each set alone and not in
+/// a cavity either set already buries by itself, but is enclosed by the pair: filled in the excluded volume
+/// of the combined atoms (re-entrant gaps too narrow for the probe) or inside one of its buried cavities.
+/// Any number of atoms works, including a single ion; panics if `grid` is not finite and positive.
+pub fn interface_void(atoms_a: &[Atom], atoms_b: &[Atom], probe: f32, grid: f32) -> f64 {
+	let combined: Vec<Atom> = atoms_a.iter().chain(atoms_b).copied().collect();
+	let params = shared_grid_params(&combined, probe, grid);
+
+	let mut grid_a = params.build_grid();
+	grid_a.fill_excluded(atoms_a, probe);
+	let mut grid_b = params.build_grid();
+	grid_b.fill_excluded(atoms_b, probe);
+	let mut grid_ab = params.build_grid();
+	grid_ab.fill_excluded(&combined, probe);
+
+	let enclosed = grid_ab.union(&grid_ab.interior_cavities(1));
+	let alone = grid_a
+		.union(&grid_a.interior_cavities(1))
+		.union(&grid_b)
+		.union(&grid_b.interior_cavities(1));
+	enclosed.difference(&alone).filled_volume()
+}
+
+/// Solvent-excluded volume (cubic angstroms) of every MODEL in a multi-model PDB, in file order.
+/// All frames share one grid enclosing every frame, so the series is comparable; frames may differ
+/// in atom count. Errors with `InvalidData` if the file has too few atoms to size a grid.
+pub fn volume_trajectory(path: &str, opts: &PdbOptions, probe: f32, grid: f32) -> io::Result<Vec<f64>> {
+	let models = load_models_from_pdb_path(path, opts)?;
+	let combined: Vec<Atom> = models.iter().flatten().copied().collect();
+	let params = GridParams::from_atoms(&combined, probe, grid).ok_or_else(|| {
+		io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("{}: too few atoms to size a grid", path),
+		)
+	})?;
+
+	let mut frame_grid = params.build_grid();
+	let voxel_volume = frame_grid.voxel_volume();
+	let volumes = models
+		.iter()
+		.map(|atoms| frame_grid.fill_excluded(atoms, probe) as f64 * voxel_volume)
+		.collect();
+	Ok(volumes)
+}
+
+/// BFS-label the voxels equal to `value` inside one block (`labels` covers global indices from `start`),
+/// ignoring neighbors outside the block. Returns the number of local labels.
+fn label_block(grid: &Grid3D, value: bool, start: usize, labels: &mut [u32]) -> usize {
+	let end = start + labels.len();
+	let mut count = 0usize;
+	let mut queue: VecDeque<usize> = VecDeque::new();
+	for seed in start..end {
+		if labels[seed - start] != 0 || grid.get_voxel_index(seed) != value {
+			continue;
+		}
+		count += 1;
+		let label = count as u32;
+		labels[seed - start] = label;
+		queue.push_back(seed);
+		while let Some(idx) = queue.pop_front() {
+			for neighbor in face_neighbors(grid, idx).into_iter().flatten() {
+				if neighbor >= start && neighbor < end && labels[neighbor - start] == 0 && grid.get_voxel_index(neighbor) == value {
+					labels[neighbor - start] = label;
+					queue.push_back(neighbor);
+				}
+			}
+		}
+	}
+	count
+}
+
+/// Union-find root with path halving.
+fn find(parent: &mut [usize], mut x: usize) -> usize {
+	while parent[x] != x {
+		parent[x] = parent[parent[x]];
+		x = parent[x];
+	}
+	x
+}
+
+/// Merge the sets of `a` and `b`, keeping the smaller root.
+fn union(parent: &mut [usize], a: usize, b: usize) {
+	let (ra, rb) = (find(parent, a), find(parent, b));
+	if ra < rb {
+		parent[rb] = ra;
+	} else if rb < ra {
+		parent[ra] = rb;
+	}
+}
 
+/// Indices of the six face neighbors of `idx`; neighbors outside the grid are `None`.
+pub(crate) fn face_neighbors(grid: &Grid3D, idx: usize) -> [Option<usize>; 6] {
+	let (i, j, k) = grid.index_to_ijk(idx);
+	let stride_j = grid.len_i;
+	let stride_k = grid.len_i * grid.len_j;
+	[
+		if i > 0 { Some(idx - 1) } else { None },
+		if i + 1 < grid.len_i { Some(idx + 1) } else { None },
+		if j > 0 { Some(idx - stride_j) } else { None },
+		if j + 1 < grid.len_j { Some(idx + stride_j) } else { None },
+		if k > 0 { Some(idx - stride_k) } else { None },
+		if k + 1 < grid.len_k { Some(idx + stride_k) } else { None },
+	]
 }
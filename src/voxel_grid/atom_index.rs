@@ -0,0 +1,180 @@
+use std::io;
+
+use crate::voxel_grid::grid::check_grid_size;
+use crate::voxel_grid::raster::Atom;
+
+/// Spatial hash of atom centers into cubic cells, for nearest-atom and radius queries
+/// without scanning every atom. Built once from a slice; indices refer to that slice.
+#[derive(Debug, Clone)]
+pub struct AtomGridIndex {
+	cell: f32,
+	origin: [f32; 3],
+	dims: [usize; 3],
+	centers: Vec<[f32; 3]>,
+	cell_start: Vec<usize>,  // CSR offsets into `members`, one entry per cell plus a final end
+	members: Vec<usize>,  // Atom indices grouped by cell, ascending within each cell
+}
+
+impl AtomGridIndex {
+	/// Index `atoms` with cells `cell` angstroms wide (a few angstroms suits proteins).
+	/// Atoms with non-finite coordinates are left out. Panics unless `cell` is finite and positive;
+	/// use `try_new` to get an error instead.
+	pub fn new(atoms: &[Atom], cell: f32) -> Self {
+		if let Err(e) = check_grid_size(cell) {
+			panic!("{}", e);
+		}
+		let centers: Vec<[f32; 3]> = atoms.iter().map(|atom| [atom.x, atom.y, atom.z]).collect();
+		let finite = |c: &[f32; 3]| c.iter().all(|v| v.is_finite());
+
+		let mut lo = [f32::MAX; 3];
+		let mut hi = [f32::MIN; 3];
+		for c in centers.iter().filter(|c| finite(c)) {
+			for axis in 0..3 {
+				lo[axis] = lo[axis].min(c[axis]);
+				hi[axis] = hi[axis].max(c[axis]);
+			}
+		}
+		let (origin, dims) = if lo[0] > hi[0] {
+			([0.0; 3], [1, 1, 1])
+		} else {
+			(lo, [0, 1, 2].map(|axis| ((hi[axis] - lo[axis]) / cell) as usize + 1))
+		};
+
+		let mut index = Self { cell, origin, dims, centers, cell_start: Vec::new(), members: Vec::new() };
+		let cells: Vec<Option<usize>> = index
+			.centers
+			.iter()
+			.map(|c| if finite(c) { Some(index.cell_of(c)) } else { None })
+			.collect();
+		let total_cells = dims[0] * dims[1] * dims[2];
+		let mut cell_start = vec![0usize; total_cells + 1];
+		for &cell_idx in cells.iter().flatten() {
+			cell_start[cell_idx + 1] += 1;
+		}
+		for c in 0..total_cells {
+			cell_start[c + 1] += cell_start[c];
+		}
+		let mut fill = cell_start.clone();
+		let mut members = vec![0usize; cell_start[total_cells]];
+		for (atom_idx, cell_idx) in cells.iter().enumerate() {
+			if let Some(cell_idx) = *cell_idx {
+				members[fill[cell_idx]] = atom_idx;
+				fill[cell_idx] += 1;
+			}
+		}
+		index.cell_start = cell_start;
+		index.members = members;
+		index
+	}
+
+	/// Checked `new`: errors with `InvalidInput` unless `cell` is finite and positive
+	pub fn try_new(atoms: &[Atom], cell: f32) -> io::Result<Self> {
+		check_grid_size(cell)?;
+		Ok(Self::new(atoms, cell))
+	}
+
+	/// Index of the atom center closest to `point` (ties go to the lower index),
+	/// or `None` if no atom was indexed.
+	pub fn nearest_atom(&self, point: [f32; 3]) -> Option<usize> {
+		if self.members.is_empty() {
+			return None;
+		}
+		let center = self.clamped_cell(point);
+		let max_ring = self.dims.iter().max().copied().unwrap_or(1);
+		let mut best: Option<(f32, usize)> = None;
+		for ring in 0..=max_ring {
+			self.for_each_in_ring(center, ring, |atom_idx| {
+				let d2 = distance2(self.centers[atom_idx], point);
+				if best.is_none_or(|(b2, b_idx)| d2 < b2 || (d2 == b2 && atom_idx < b_idx)) {
+					best = Some((d2, atom_idx));
+				}
+			});
+			// Cells in ring + 1 are at least `ring * cell` away from the point.
+			let reach = ring as f32 * self.cell;
+			if let Some((b2, _)) = best
+				&& b2 < reach * reach
+			{
+				break;
+			}
+		}
+		best.map(|(_, atom_idx)| atom_idx)
+	}
+
+	/// Indices (ascending) of atoms whose centers lie within `radius` angstroms of `point`.
+	pub fn atoms_within(&self, point: [f32; 3], radius: f32) -> Vec<usize> {
+		let mut found = Vec::new();
+		if radius < 0.0 || self.members.is_empty() {
+			return found;
+		}
+		let r2 = radius * radius;
+		let lo = self.clamped_cell([point[0] - radius, point[1] - radius, point[2] - radius]);
+		let hi = self.clamped_cell([point[0] + radius, point[1] + radius, point[2] + radius]);
+		for k in lo[2]..=hi[2] {
+			for j in lo[1]..=hi[1] {
+				for i in lo[0]..=hi[0] {
+					for &atom_idx in self.cell_members([i, j, k]) {
+						if distance2(self.centers[atom_idx], point) <= r2 {
+							found.push(atom_idx);
+						}
+					}
+				}
+			}
+		}
+		found.sort_unstable();
+		found
+	}
+
+	/// Flat cell index of an in-range center.
+	fn cell_of(&self, c: &[f32; 3]) -> usize {
+		let [i, j, k] = self.clamped_cell(*c);
+		i + j * self.dims[0] + k * self.dims[0] * self.dims[1]
+	}
+
+	/// Cell coordinates of `point`, clamped into the indexed box.
+	fn clamped_cell(&self, point: [f32; 3]) -> [usize; 3] {
+		[0, 1, 2].map(|axis| {
+			let t = ((point[axis] - self.origin[axis]) / self.cell).floor();
+			if t.is_nan() || t < 0.0 {
+				0
+			} else {
+				(t as usize).min(self.dims[axis] - 1)
+			}
+		})
+	}
+
+	fn cell_members(&self, [i, j, k]: [usize; 3]) -> &[usize] {
+		let c = i + j * self.dims[0] + k * self.dims[0] * self.dims[1];
+		&self.members[self.cell_start[c]..self.cell_start[c + 1]]
+	}
+
+	/// Visit the atoms in cells at Chebyshev distance exactly `ring` from `center`.
+	fn for_each_in_ring<F: FnMut(usize)>(&self, center: [usize; 3], ring: usize, mut visit: F) {
+		let ring = ring as isize;
+		let range = |axis: usize| {
+			let c = center[axis] as isize;
+			(c - ring).max(0)..=(c + ring).min(self.dims[axis] as isize - 1)
+		};
+		for k in range(2) {
+			for j in range(1) {
+				for i in range(0) {
+					let on_ring = (i - center[0] as isize).abs() == ring
+						|| (j - center[1] as isize).abs() == ring
+						|| (k - center[2] as isize).abs() == ring;
+					if !on_ring {
+						continue;
+					}
+					for &atom_idx in self.cell_members([i as usize, j as usize, k as usize]) {
+						visit(atom_idx);
+					}
+				}
+			}
+		}
+	}
+}
+
+fn distance2(a: [f32; 3], b: [f32; 3]) -> f32 {
+	let dx = a[0] - b[0];
+	let dy = a[1] - b[1];
+	let dz = a[2] - b[2];
+	dx * dx + dy * dy + dz * dz
+}
@@ -0,0 +1,121 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::voxel_grid::geometry::GridParams;
+use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::pdb::{load_atoms_from_pdb_path, Filters, PdbOptions};
+
+/// `from_pdb_with_config` warns when more than this fraction of the grid is empty
+pub const SPARSITY_WARNING: f64 = 0.99;
+
+/// Knobs shared by the PDB-to-volume pipeline; missing fields in a config file take the defaults
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct VolumeConfig {
+	pub probe: f32,  // Probe radius in angstroms
+	pub grid: f32,  // Voxel size in angstroms
+	pub filters: Filters,
+	pub use_united: bool,  // United-atom radii (implicit hydrogens)
+}
+
+impl Default for VolumeConfig {
+	fn default() -> Self {
+		Self {
+			probe: 1.4,
+			grid: 1.0,
+			filters: Filters::default(),
+			use_united: true,
+		}
+	}
+}
+
+impl VolumeConfig {
+	/// PDB loading options derived from this config
+	pub fn pdb_options(&self) -> PdbOptions {
+		PdbOptions {
+			use_united: self.use_united,
+			filters: self.filters.clone(),
+			..PdbOptions::default()
+		}
+	}
+}
+
+/// Wall-clock time per pipeline phase, in the order the phases ran
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Timings {
+	pub phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+	/// Run `f` and record its duration under `phase` (e.g. to add "surface" or "write" after the pipeline)
+	pub fn time<T, F: FnOnce() -> T>(&mut self, phase: &'static str, f: F) -> T {
+		let start = Instant::now();
+		let out = f();
+		self.phases.push((phase, start.elapsed()));
+		out
+	}
+
+	/// Total time recorded under `phase`, or `None` if it never ran
+	pub fn get(&self, phase: &str) -> Option<Duration> {
+		let mut found = None;
+		for &(_, elapsed) in self.phases.iter().filter(|(name, _)| *name == phase) {
+			found = Some(found.unwrap_or(Duration::ZERO) + elapsed);
+		}
+		found
+	}
+
+	/// Sum of all recorded phases
+	pub fn total(&self) -> Duration {
+		self.phases.iter().map(|&(_, elapsed)| elapsed).sum()
+	}
+}
+
+impl Grid3D {
+	/// Load a PDB file and rasterize its solvent-excluded volume on a grid sized for the atoms.
+	/// Errors with `InvalidData` if the file has too few atoms, `config.grid` is not a valid spacing,
+	/// or an atom's expanded sphere would be clipped by the grid (e.g. a radius beyond the usual padding).
+	pub fn from_pdb_with_config(path: &str, config: &VolumeConfig) -> io::Result<Grid3D> {
+		Self::from_pdb_with_timings(path, config).map(|(grid, _)| grid)
+	}
+
+	/// Same as `from_pdb_with_config`, also returning how long each phase took:
+	/// "parse", "grid_build", "rasterize" (accessible fill), and "contract" (only when `config.probe > 0`).
+	/// Timing costs one `Instant::now()` per phase.
+	pub fn from_pdb_with_timings(path: &str, config: &VolumeConfig) -> io::Result<(Grid3D, Timings)> {
+		let mut timings = Timings::default();
+		let atoms = timings.time("parse", || load_atoms_from_pdb_path(path, &config.pdb_options()))?;
+		let start = Instant::now();
+		let params = GridParams::from_atoms(&atoms, config.probe, config.grid).ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("{}: cannot size a grid ({} atoms, grid {})", path, atoms.len(), config.grid),
+			)
+		})?;
+		if let Some(idx) = params.first_atom_outside(&atoms, config.probe) {
+			let atom = atoms[idx];
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!(
+					"{}: atom {} at ({:.3}, {:.3}, {:.3}) with radius {:.2} lies outside the grid",
+					path, idx + 1, atom.x, atom.y, atom.z, atom.radius
+				),
+			));
+		}
+		let mut grid = params.build_grid();
+		timings.phases.push(("grid_build", start.elapsed()));
+		timings.time("rasterize", || grid.fill_accessible_parallel(&atoms, config.probe));
+		if config.probe > 0.0 {
+			timings.time("contract", || grid.contract_exclusion_parallel(config.probe));
+		}
+		let sparsity = grid.sparsity();
+		if sparsity > SPARSITY_WARNING {
+			eprintln!(
+				"Warning: {}: {:.1}% of the {} voxels are empty; crop_to_content can shrink the grid before writing it",
+				path, 100.0 * sparsity, grid.total_voxels
+			);
+		}
+		Ok((grid, timings))
+	}
+}
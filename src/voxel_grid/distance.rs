@@ -0,0 +1,265 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::voxel_grid::analyze::face_neighbors;
+use crate::voxel_grid::float_grid::FloatGrid3D;
+use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::surface_area::classify_edge_point;
+
+/// Stand-in for "no feature voxel yet" in the squared-distance passes.
+const FAR: f64 = 1.0e20;
+
+impl Grid3D {
+	/// Euclidean distance (angstroms, center to center) from every voxel to the nearest filled voxel;
+	/// filled voxels are 0. Exact, via separable passes. Every voxel is infinite if nothing is filled.
+	pub fn distance_transform(&self) -> FloatGrid3D {
+		self.distance_to(true)
+	}
+
+	/// Euclidean distance (angstroms) from every voxel to the nearest voxel whose occupancy equals `value`.
+	/// `distance_to(false)` gives, inside a filled region or cavity mask, the depth from its boundary.
+	pub fn distance_to(&self, value: bool) -> FloatGrid3D {
+		let targets: Vec<bool> = (0..self.total_voxels).map(|idx| self.get_voxel_index(idx) == value).collect();
+		let dist2 = squared_distance_to(&targets, self.axis_scale(), self.len_i, self.len_j, self.len_k);
+		let mut out = FloatGrid3D::like_grid(self);
+		let g = self.grid_size as f64;
+		for (cell, &d2) in out.data.iter_mut().zip(dist2.iter()) {
+			*cell = if d2 >= FAR { f32::INFINITY } else { (d2.sqrt() * g) as f32 };
+		}
+		out
+	}
+
+	/// Largest sphere centered in `region` (a mask such as a cavity) that avoids this grid's filled voxels:
+	/// the region voxel farthest from the nearest filled voxel, as `(center, radius)` in physical coordinates.
+	/// Ties go to the lowest index. An empty region returns `([0.0; 3], 0.0)`.
+	pub fn largest_inscribed_sphere(&self, region: &Grid3D) -> ([f32; 3], f32) {
+		assert!(
+			self.len_i == region.len_i && self.len_j == region.len_j && self.len_k == region.len_k,
+			"region mask dimensions must match the grid"
+		);
+		let wall = self.distance_transform();
+		let mut best: Option<(usize, f32)> = None;
+		for idx in 0..region.total_voxels {
+			if region.get_voxel_index(idx) && best.is_none_or(|(_, r)| wall.data[idx] > r) {
+				best = Some((idx, wall.data[idx]));
+			}
+		}
+		match best {
+			Some((idx, radius)) => {
+				let (i, j, k) = self.index_to_ijk(idx);
+				let (x, y, z) = self.voxel_to_physical(i, j, k);
+				([x, y, z], radius)
+			}
+			None => ([0.0; 3], 0.0),
+		}
+	}
+}
+
+/// Directed surface distance from `a` to `b` (grids with matching dimensions), in angstroms.
+/// Surface voxels are filled voxels with an empty face neighbor (as in `write_surface_pdb`).
+/// Returns `(directed_max, mean)`: the largest and the mean distance from a surface voxel of `a` to the
+/// nearest surface voxel of `b`. Both are 0 when `a` has no surface and infinite when only `b` lacks one.
+/// See `surface_hausdorff_symmetric` for the two-way Hausdorff distance.
+pub fn surface_hausdorff(a: &Grid3D, b: &Grid3D) -> (f32, f32) {
+	check_matching_dims(a, b);
+	let surface_a = surface_flags(a);
+	let surface_b = surface_flags(b);
+	directed_surface_distance(a, &surface_a, &surface_b).map_or((0.0, 0.0), finish_distance(a))
+}
+
+/// Symmetric surface distance between two grids with matching dimensions, in angstroms.
+/// Returns `(hausdorff, mean)`: the largest nearest-surface distance in either direction, and the mean
+/// nearest-surface distance over the surface voxels of both grids. Both are 0 when neither grid has
+/// a surface and infinite when only one does.
+pub fn surface_hausdorff_symmetric(a: &Grid3D, b: &Grid3D) -> (f32, f32) {
+	check_matching_dims(a, b);
+	let surface_a = surface_flags(a);
+	let surface_b = surface_flags(b);
+	let forward = directed_surface_distance(a, &surface_a, &surface_b);
+	let backward = directed_surface_distance(a, &surface_b, &surface_a);
+	let combined = match (forward, backward) {
+		(Some(f), Some(b)) => Some((f.0.max(b.0), f.1 + b.1, f.2 + b.2)),
+		(one, other) => one.or(other),
+	};
+	combined.map_or((0.0, 0.0), finish_distance(a))
+}
+
+fn check_matching_dims(a: &Grid3D, b: &Grid3D) {
+	assert!(
+		a.len_i == b.len_i && a.len_j == b.len_j && a.len_k == b.len_k,
+		"grid dimensions must match"
+	);
+}
+
+/// `(max squared distance, sum of distances, count)` in voxel units from each `from` surface voxel to the
+/// nearest `to` surface voxel; `None` when `from` is empty, infinite distances when only `to` is.
+fn directed_surface_distance(grid: &Grid3D, from: &[bool], to: &[bool]) -> Option<(f64, f64, usize)> {
+	let count = from.iter().filter(|&&s| s).count();
+	if count == 0 {
+		return None;
+	}
+	if !to.iter().any(|&s| s) {
+		return Some((f64::INFINITY, f64::INFINITY, count));
+	}
+	let dist2 = squared_distance_to(to, grid.axis_scale(), grid.len_i, grid.len_j, grid.len_k);
+	let mut max_d2 = 0.0_f64;
+	let mut sum = 0.0_f64;
+	for (idx, &on_surface) in from.iter().enumerate() {
+		if on_surface {
+			max_d2 = max_d2.max(dist2[idx]);
+			sum += dist2[idx].sqrt();
+		}
+	}
+	Some((max_d2, sum, count))
+}
+
+/// Convert a `directed_surface_distance` result to `(max, mean)` in angstroms.
+fn finish_distance(grid: &Grid3D) -> impl Fn((f64, f64, usize)) -> (f32, f32) {
+	let g = grid.grid_size as f64;
+	move |(max_d2, sum, count)| ((max_d2.sqrt() * g) as f32, (sum / count as f64 * g) as f32)
+}
+
+/// Pocket depth: the largest distance (angstroms) from any `cavity` voxel to the nearest filled
+/// `molecule` voxel, i.e. the radius of the largest sphere centered in the pocket. 0 for an empty cavity.
+pub fn cavity_depth(cavity: &Grid3D, molecule: &Grid3D) -> f32 {
+	molecule.largest_inscribed_sphere(cavity).1
+}
+
+/// Channel bottleneck: the smallest distance (angstroms) to the nearest filled `molecule` voxel over the
+/// voxels containing the `path` points (physical coordinates). Points outside the grid are skipped;
+/// returns infinity if no point lies inside.
+pub fn channel_bottleneck(molecule: &Grid3D, path: &[[f32; 3]]) -> f32 {
+	let wall = molecule.distance_transform();
+	path.iter()
+		.filter_map(|p| molecule.physical_to_ijk(p[0], p[1], p[2]))
+		.map(|(i, j, k)| wall.get_ijk(i, j, k))
+		.fold(f32::INFINITY, f32::min)
+}
+
+/// Channel mouth radius: the widest constriction (angstroms) a probe must pass to get from the `cavity`
+/// mask to the `exterior` mask. Walls are the voxels in neither mask; the result is the largest, over
+/// face-connected paths through `cavity` or `exterior` voxels, of the smallest wall distance along the
+/// path (a maximin path). A substrate of radius below this can enter. 0 if no path connects the two.
+pub fn channel_mouth_radius(cavity: &Grid3D, exterior: &Grid3D) -> f32 {
+	assert!(
+		cavity.len_i == exterior.len_i && cavity.len_j == exterior.len_j && cavity.len_k == exterior.len_k,
+		"cavity and exterior dimensions must match"
+	);
+	let open: Vec<bool> = (0..cavity.total_voxels).map(|idx| cavity.get_voxel_index(idx) || exterior.get_voxel_index(idx)).collect();
+	let walls: Vec<bool> = open.iter().map(|&o| !o).collect();
+	let dist2 = squared_distance_to(&walls, cavity.axis_scale(), cavity.len_i, cavity.len_j, cavity.len_k);
+
+	// Widest-path search: pop the voxel with the largest bottleneck so far. Squared distances are
+	// non-negative, so their bit patterns order like the values.
+	let mut best = vec![-1.0f64; cavity.total_voxels];
+	let mut heap: BinaryHeap<(u64, Reverse<usize>)> = BinaryHeap::new();
+	for idx in 0..cavity.total_voxels {
+		if cavity.get_voxel_index(idx) {
+			best[idx] = dist2[idx];
+			heap.push((dist2[idx].to_bits(), Reverse(idx)));
+		}
+	}
+	while let Some((bits, Reverse(idx))) = heap.pop() {
+		let width = f64::from_bits(bits);
+		if width < best[idx] {
+			continue;
+		}
+		if exterior.get_voxel_index(idx) {
+			let g = cavity.grid_size as f64;
+			return if width >= FAR { f32::INFINITY } else { (width.sqrt() * g) as f32 };
+		}
+		for neighbor in face_neighbors(cavity, idx).into_iter().flatten() {
+			let through = width.min(dist2[neighbor]);
+			if open[neighbor] && through > best[neighbor] {
+				best[neighbor] = through;
+				heap.push((through.to_bits(), Reverse(neighbor)));
+			}
+		}
+	}
+	0.0
+}
+
+/// Per-voxel flag for filled voxels with at least one empty face neighbor.
+fn surface_flags(grid: &Grid3D) -> Vec<bool> {
+	(0..grid.total_voxels)
+		.map(|idx| grid.get_voxel_index(idx) && classify_edge_point(grid, idx) != 0)
+		.collect()
+}
+
+/// Exact squared Euclidean distance (in `grid_size` units) from every voxel to the nearest `features` voxel,
+/// computed with separable 1D lower-envelope passes along i, j, then k (Felzenszwalb-Huttenlocher).
+/// `scale` is the per-axis spacing from `Grid3D::axis_scale`.
+pub(crate) fn squared_distance_to(features: &[bool], scale: [f32; 3], len_i: usize, len_j: usize, len_k: usize) -> Vec<f64> {
+	let mut dist2: Vec<f64> = features.iter().map(|&f| if f { 0.0 } else { FAR }).collect();
+	let longest = len_i.max(len_j).max(len_k);
+	let mut line = vec![0.0_f64; longest];
+	let mut out = vec![0.0_f64; longest];
+	let mut hull = vec![0usize; longest];
+	let mut bounds = vec![0.0_f64; longest + 1];
+
+	let stride_j = len_i;
+	let stride_k = len_i * len_j;
+	let weight = scale.map(|s| s as f64 * s as f64);
+	// (axis length, stride along the axis, squared spacing, starting indices of every line along it)
+	let axes: [(usize, usize, f64, Vec<usize>); 3] = [
+		(len_i, 1, weight[0], (0..len_k).flat_map(|k| (0..len_j).map(move |j| j * stride_j + k * stride_k)).collect()),
+		(len_j, stride_j, weight[1], (0..len_k).flat_map(|k| (0..len_i).map(move |i| i + k * stride_k)).collect()),
+		(len_k, stride_k, weight[2], (0..len_j).flat_map(|j| (0..len_i).map(move |i| i + j * stride_j)).collect()),
+	];
+	for (n, stride, w, starts) in axes.iter() {
+		for &start in starts.iter() {
+			for t in 0..*n {
+				line[t] = dist2[start + t * stride];
+			}
+			lower_envelope(&line[..*n], *w, &mut out[..*n], &mut hull, &mut bounds);
+			for t in 0..*n {
+				dist2[start + t * stride] = out[t];
+			}
+		}
+	}
+	dist2
+}
+
+/// 1D squared distance transform: `out[q] = min_p w (q - p)^2 + f[p]`.
+fn lower_envelope(f: &[f64], w: f64, out: &mut [f64], hull: &mut [usize], bounds: &mut [f64]) {
+	let n = f.len();
+	if n == 0 {
+		return;
+	}
+	let mut k = 0usize;
+	hull[0] = 0;
+	bounds[0] = f64::NEG_INFINITY;
+	bounds[1] = f64::INFINITY;
+	for q in 1..n {
+		if f[q] >= FAR {
+			continue;
+		}
+		loop {
+			let p = hull[k];
+			let s = ((f[q] + w * (q * q) as f64) - (f[p] + w * (p * p) as f64)) / (2.0 * w * (q as f64 - p as f64));
+			if f[p] >= FAR || s <= bounds[k] {
+				if k == 0 {
+					hull[0] = q;
+					bounds[0] = f64::NEG_INFINITY;
+					bounds[1] = f64::INFINITY;
+					break;
+				}
+				k -= 1;
+				continue;
+			}
+			k += 1;
+			hull[k] = q;
+			bounds[k] = s;
+			bounds[k + 1] = f64::INFINITY;
+			break;
+		}
+	}
+	let mut k = 0usize;
+	for (q, value) in out.iter_mut().enumerate() {
+		while bounds[k + 1] < q as f64 {
+			k += 1;
+		}
+		let p = hull[k];
+		*value = if f[p] >= FAR { FAR } else { w * (q as f64 - p as f64).powi(2) + f[p] };
+	}
+}
@@ -0,0 +1,38 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+
+use crate::voxel_grid::grid::Grid3D;
+
+/// Voxel values per line in the MAP section, as O writes them.
+const EZD_VALUES_PER_LINE: usize = 7;
+
+impl Grid3D {
+	/// Save the voxel grid as an ASCII EZD map (read by O and older vossvolvox scripts).
+	/// `CELL` is the box size with 90 degree angles, `GRID` and `EXTENT` are the voxel counts,
+	/// `ORIGIN` is the shift in whole voxels (rounded, as in the MRC start indices), and the `MAP`
+	/// section lists one 0/1 value per voxel, i fastest.
+	pub fn write_to_ezd_file(&self, path: &str) -> Result<()> {
+		let mut file = BufWriter::new(File::create(path)?);
+		let spacing = self.spacing();
+		let lens = [self.len_i, self.len_j, self.len_k];
+		let shifts = [self.x_shift, self.y_shift, self.z_shift];
+		let cell: [f64; 3] = std::array::from_fn(|axis| lens[axis] as f64 * spacing[axis] as f64);
+		let origin: [i64; 3] =
+			std::array::from_fn(|axis| (shifts[axis] as f64 / spacing[axis] as f64).round() as i64);
+
+		writeln!(file, "EZD_MAP")?;
+		writeln!(file, "! vossvolvox voxel grid")?;
+		writeln!(file, "CELL {:.3} {:.3} {:.3} 90.000 90.000 90.000", cell[0], cell[1], cell[2])?;
+		writeln!(file, "ORIGIN {} {} {}", origin[0], origin[1], origin[2])?;
+		writeln!(file, "EXTENT {} {} {}", lens[0], lens[1], lens[2])?;
+		writeln!(file, "GRID {} {} {}", lens[0], lens[1], lens[2])?;
+		writeln!(file, "SCALE 1.0")?;
+		writeln!(file, "MAP")?;
+		let values: Vec<&str> = self.iter_mrc_order().map(|filled| if filled { "1" } else { "0" }).collect();
+		for line in values.chunks(EZD_VALUES_PER_LINE) {
+			writeln!(file, "{}", line.join(" "))?;
+		}
+		writeln!(file, "END")?;
+		file.flush()
+	}
+}
@@ -0,0 +1,239 @@
+use std::thread;
+
+use crate::voxel_grid::grid::{check_grid_size, checked_total_voxels, Grid3D};
+use crate::voxel_grid::parallel::worker_threads;
+use crate::voxel_grid::raster::{voxel_range, Atom};
+
+/// 3D grid of per-voxel float values (occupancy fractions, distances), laid out like `Grid3D`
+#[derive(Clone)]
+pub struct FloatGrid3D {
+	pub len_i: usize,  // Number of voxels along I
+	pub len_j: usize,  // Number of voxels along J
+	pub len_k: usize,  // Number of voxels along K
+	pub total_voxels: usize, // Total number of voxels IxJxK
+	pub grid_size: f32,  // Size of each voxel in angstroms (the X spacing on anisotropic grids)
+	pub spacing: [f32; 3],  // Per-axis voxel size in angstroms; [grid_size; 3] on cubic grids
+	pub x_shift: f32,  // Offset for X to align with I=0
+	pub y_shift: f32,  // Offset for Y to align with J=0
+	pub z_shift: f32,  // Offset for Z to align with K=0
+	pub data: Vec<f32>,  // Value per voxel, i-fastest like `Grid3D::ijk_to_index`
+}
+
+/// Which voxels `FloatGrid3D::threshold` keeps. `Above` and `Below` split the values at one level
+/// (`Above` includes it); `Between` keeps an inclusive band. NaN values are never kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Threshold {
+	Above(f32),  // value >= level, the usual contour
+	Below(f32),  // value < level, e.g. negative difference density
+	Between(f32, f32),  // lo <= value <= hi
+}
+
+impl Default for Threshold {
+	/// Half occupancy, the natural contour of a fractional-occupancy grid
+	fn default() -> Self {
+		Threshold::Above(0.5)
+	}
+}
+
+impl Threshold {
+	/// True if `value` is selected
+	pub fn keeps(&self, value: f32) -> bool {
+		match *self {
+			Threshold::Above(level) => value >= level,
+			Threshold::Below(level) => value < level,
+			Threshold::Between(lo, hi) => value >= lo && value <= hi,
+		}
+	}
+}
+
+impl FloatGrid3D {
+	/// Create a new grid with every voxel at 0.0 (panics on a non-positive or non-finite `grid_size`,
+	/// or a voxel count that overflows `usize`)
+	pub fn new(len_i: usize, len_j: usize, len_k: usize, grid_size: f32) -> Self {
+		if let Err(e) = check_grid_size(grid_size) {
+			panic!("{}", e);
+		}
+		let total_voxels = match checked_total_voxels(len_i, len_j, len_k) {
+			Ok(total) => total,
+			Err(e) => panic!("{}", e),
+		};
+		Self {
+			len_i,
+			len_j,
+			len_k,
+			total_voxels,
+			grid_size,
+			spacing: [grid_size; 3],
+			x_shift: 0.0,
+			y_shift: 0.0,
+			z_shift: 0.0,
+			data: vec![0.0; total_voxels],
+		}
+	}
+
+	/// Create an all-zero grid with voxel size `spacing` = [x, y, z] angstroms, as `Grid3D::new_anisotropic`;
+	/// `grid_size` is set to the X spacing. Panics if any spacing is not finite and positive.
+	pub fn new_anisotropic(len_i: usize, len_j: usize, len_k: usize, spacing: [f32; 3]) -> Self {
+		for &size in spacing.iter() {
+			if let Err(e) = check_grid_size(size) {
+				panic!("{}", e);
+			}
+		}
+		let mut float_grid = Self::new(len_i, len_j, len_k, spacing[0]);
+		float_grid.spacing = spacing;
+		float_grid
+	}
+
+	/// Create an all-zero grid with the same dimensions, spacing, and shifts as a `Grid3D`
+	pub fn like_grid(grid: &Grid3D) -> Self {
+		let mut float_grid = Self::new_anisotropic(grid.len_i, grid.len_j, grid.len_k, grid.spacing());
+		float_grid.x_shift = grid.x_shift;
+		float_grid.y_shift = grid.y_shift;
+		float_grid.z_shift = grid.z_shift;
+		float_grid
+	}
+
+	/// Convert (i, j, k) to the flat data index
+	pub fn ijk_to_index(&self, i: usize, j: usize, k: usize) -> usize {
+		i + j * self.len_i + k * self.len_i * self.len_j
+	}
+
+	/// Value at (i, j, k)
+	pub fn get_ijk(&self, i: usize, j: usize, k: usize) -> f32 {
+		self.data[self.ijk_to_index(i, j, k)]
+	}
+
+	/// Bit grid with the same dimensions, spacing, and shifts, filled where `threshold` keeps the value
+	pub fn threshold(&self, threshold: Threshold) -> Grid3D {
+		let mut grid = Grid3D::new_anisotropic(self.len_i, self.len_j, self.len_k, self.spacing)
+			.with_shifts(self.x_shift, self.y_shift, self.z_shift);
+		for (idx, &value) in self.data.iter().enumerate() {
+			if threshold.keeps(value) {
+				grid.fill_voxel_index(idx);
+			}
+		}
+		grid
+	}
+
+	/// Sum of occupancies times the voxel volume, in cubic angstroms
+	pub fn total_volume(&self) -> f64 {
+		let [x, y, z] = self.spacing.map(|size| size as f64);
+		let sum: f64 = self.data.iter().map(|&v| v as f64).sum();
+		sum * x * y * z
+	}
+
+	/// Splatted accessible fill: a voxel whose center lies `d` grid units from an atom center gets
+	/// occupancy `clamp(r - d + 0.5, 0, 1)` for sphere radius `r = (radius + probe) / grid_size`
+	/// (distances scaled per axis by `spacing`, in units of the X spacing `grid_size`),
+	/// and overlapping atoms keep the largest value. Unlike the hard center test of
+	/// `fill_accessible_parallel`, the volume changes continuously as atoms move by fractions of a voxel.
+	/// Runs on one thread. Returns the splatted volume in cubic angstroms.
+	pub fn fill_accessible_splatted(&mut self, atoms: &[Atom], probe: f32) -> f64 {
+		self.data.fill(0.0);
+		let len_i = self.len_i as isize;
+		let len_j = self.len_j as isize;
+		let len_k = self.len_k as isize;
+		if self.total_voxels == 0 {
+			return 0.0;
+		}
+
+		// Exactly 1.0 on cubic grids, so cubic results do not change.
+		let scale = self.spacing.map(|size| size / self.grid_size);
+		for atom in atoms {
+			let r_grid = (atom.radius + probe) / self.grid_size;
+			if r_grid <= 0.0 {
+				continue;
+			}
+			let xk = (atom.x - self.x_shift) / self.spacing[0];
+			let yk = (atom.y - self.y_shift) / self.spacing[1];
+			let zk = (atom.z - self.z_shift) / self.spacing[2];
+			let (imin, imax) = voxel_range(xk, r_grid / scale[0], len_i);
+			let (jmin, jmax) = voxel_range(yk, r_grid / scale[1], len_j);
+			let (kmin, kmax) = voxel_range(zk, r_grid / scale[2], len_k);
+
+			for k in kmin..=kmax {
+				let dz = (zk - k as f32) * scale[2];
+				for j in jmin..=jmax {
+					let dy = (yk - j as f32) * scale[1];
+					for i in imin..=imax {
+						let dx = (xk - i as f32) * scale[0];
+						let dist = (dx * dx + dy * dy + dz * dz).sqrt();
+						let occupancy = (r_grid - dist + 0.5).clamp(0.0, 1.0);
+						if occupancy > 0.0 {
+							let idx = self.ijk_to_index(i as usize, j as usize, k as usize);
+							if occupancy > self.data[idx] {
+								self.data[idx] = occupancy;
+							}
+						}
+					}
+				}
+			}
+		}
+		self.total_volume()
+	}
+
+	/// Gaussian blur with standard deviation `sigma` (angstroms), as three separable 1D passes (i, j, k)
+	/// with a normalized kernel truncated at 3 sigma along each axis's spacing. Values beyond the grid count as 0.
+	/// A non-positive or non-finite `sigma` returns a copy. Thread count follows `parallel::worker_threads()`.
+	pub fn gaussian_blur(&self, sigma: f32) -> FloatGrid3D {
+		self.gaussian_blur_with_threads(sigma, worker_threads())
+	}
+
+	/// `gaussian_blur` on up to `threads` threads. Each pass splits the output into k-slabs; every voxel sums
+	/// the same terms in the same order on any thread count, so the result is identical to one thread.
+	pub fn gaussian_blur_with_threads(&self, sigma: f32, threads: usize) -> FloatGrid3D {
+		let mut out = self.clone();
+		if !(sigma.is_finite() && sigma > 0.0) || self.total_voxels == 0 {
+			return out;
+		}
+		let mut scratch = vec![0.0f32; self.total_voxels];
+		let strides = [1, self.len_i, self.len_i * self.len_j];
+		for (axis, &stride) in strides.iter().enumerate() {
+			let kernel = gaussian_kernel(sigma / self.spacing[axis]);
+			blur_pass(self, &out.data, &mut scratch, axis, stride, &kernel, threads.max(1));
+			std::mem::swap(&mut out.data, &mut scratch);
+		}
+		out
+	}
+}
+
+/// Normalized Gaussian weights for offsets `-r..=r` voxels, `r = ceil(3 sigma)`, sigma in voxels.
+fn gaussian_kernel(sigma_voxels: f32) -> Vec<f32> {
+	let radius = (3.0 * sigma_voxels).ceil() as isize;
+	let weights: Vec<f64> = (-radius..=radius)
+		.map(|t| (-(t * t) as f64 / (2.0 * sigma_voxels as f64 * sigma_voxels as f64)).exp())
+		.collect();
+	let sum: f64 = weights.iter().sum();
+	weights.iter().map(|w| (w / sum) as f32).collect()
+}
+
+/// One separable pass along `axis` (0 = I, 1 = J, 2 = K, flat index step `stride`) from `src` into `dst`.
+fn blur_pass(grid: &FloatGrid3D, src: &[f32], dst: &mut [f32], axis: usize, stride: usize, kernel: &[f32], threads: usize) {
+	let lens = [grid.len_i, grid.len_j, grid.len_k];
+	let len = lens[axis] as isize;
+	let radius = (kernel.len() / 2) as isize;
+	let slab = grid.len_i * grid.len_j;
+	let slabs_per_thread = grid.len_k.div_ceil(threads).max(1);
+
+	thread::scope(|scope| {
+		for (chunk_idx, chunk) in dst.chunks_mut(slab * slabs_per_thread).enumerate() {
+			let start = chunk_idx * slab * slabs_per_thread;
+			scope.spawn(move || {
+				for (offset, value) in chunk.iter_mut().enumerate() {
+					let idx = start + offset;
+					let ijk = [idx % grid.len_i, (idx / grid.len_i) % grid.len_j, idx / slab];
+					let pos = ijk[axis] as isize;
+					let mut sum = 0.0f32;
+					for (t, &w) in kernel.iter().enumerate() {
+						let along = pos + t as isize - radius;
+						if along >= 0 && along < len {
+							sum += w * src[(idx as isize + (along - pos) * stride as isize) as usize];
+						}
+					}
+					*value = sum;
+				}
+			});
+		}
+	});
+}
@@ -1,5 +1,8 @@
-use crate::voxel_grid::grid::Grid3D;
+use std::io;
+
+use crate::voxel_grid::grid::{check_grid_size, Grid3D};
 use crate::voxel_grid::raster::Atom;
+use crate::voxel_grid::utils::estimate_grid_memory;
 
 const MAX_VDW: f32 = 2.0;
 
@@ -18,10 +21,98 @@ pub struct GridParams {
 	pub grid: f32,
 }
 
+/// Radii below this (angstroms) are counted by `AtomSummary::small_radius`; the radius table
+/// gives 0.01 to atoms it has no entry for.
+pub const SMALL_RADIUS: f32 = 0.5;
+
+/// Quick sanity check of loaded atoms, from `atom_summary`.
+/// Bounds and radius figures cover atoms with finite coordinates and radius; all zero when there are none.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AtomSummary {
+	pub count: usize,  // All atoms, including non-finite ones
+	pub non_finite: usize,  // Atoms with a NaN or infinite coordinate or radius
+	pub min_xyz: [f32; 3],
+	pub max_xyz: [f32; 3],
+	pub min_radius: f32,
+	pub max_radius: f32,
+	pub mean_radius: f64,
+	pub small_radius: usize,  // Atoms with radius below `SMALL_RADIUS` (radius table misses)
+	pub at_origin: usize,  // Atoms exactly at (0, 0, 0), a common sign of a parse failure
+}
+
+impl AtomSummary {
+	/// True if most finite atoms have a radius below `SMALL_RADIUS`, i.e. radii are probably missing.
+	pub fn mostly_small_radii(&self) -> bool {
+		let finite = self.count - self.non_finite;
+		finite > 0 && self.small_radius * 2 > finite
+	}
+}
+
+/// Count, bounds, and radius range of `atoms` in one pass, to check inputs before sizing a grid.
+pub fn atom_summary(atoms: &[Atom]) -> AtomSummary {
+	let mut summary = AtomSummary { count: atoms.len(), ..AtomSummary::default() };
+	let mut lo = [f32::MAX; 3];
+	let mut hi = [f32::MIN; 3];
+	let mut min_radius = f32::MAX;
+	let mut max_radius = f32::MIN;
+	let mut radius_sum = 0.0f64;
+	for atom in atoms {
+		let xyz = [atom.x, atom.y, atom.z];
+		if !(xyz.iter().all(|v| v.is_finite()) && atom.radius.is_finite()) {
+			summary.non_finite += 1;
+			continue;
+		}
+		for axis in 0..3 {
+			lo[axis] = lo[axis].min(xyz[axis]);
+			hi[axis] = hi[axis].max(xyz[axis]);
+		}
+		min_radius = min_radius.min(atom.radius);
+		max_radius = max_radius.max(atom.radius);
+		radius_sum += atom.radius as f64;
+		if atom.radius < SMALL_RADIUS {
+			summary.small_radius += 1;
+		}
+		if xyz == [0.0; 3] {
+			summary.at_origin += 1;
+		}
+	}
+	let finite = summary.count - summary.non_finite;
+	if finite > 0 {
+		summary.min_xyz = lo;
+		summary.max_xyz = hi;
+		summary.min_radius = min_radius;
+		summary.max_radius = max_radius;
+		summary.mean_radius = radius_sum / finite as f64;
+	}
+	summary
+}
+
+/// Optional overrides for `GridParams::from_atoms_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct GridOptions {
+	/// Padding in angstroms added beyond the atom centers on each axis; `None` keeps the legacy padding.
+	pub pad_x: Option<f32>,
+	pub pad_y: Option<f32>,
+	pub pad_z: Option<f32>,
+	/// Use the fewest voxels spanning the padded bounds instead of rounding each dimension up to a
+	/// multiple of 4 (e.g. to match an external map's dimensions).
+	pub minimal_dimensions: bool,
+}
+
 impl GridParams {
 	/// Compute grid parameters mimicking the legacy padding/alignment rules.
+	/// Returns `None` with fewer than three atoms or a `grid` that is not finite and positive.
 	pub fn from_atoms(atoms: &[Atom], probe: f32, grid: f32) -> Option<Self> {
-		if atoms.len() < 3 {
+		Self::from_atoms_with_options(atoms, probe, grid, &GridOptions::default())
+	}
+
+	/// Same as `from_atoms`, with per-axis overrides.
+	/// An axis with an explicit pad uses exactly `min - pad .. max + pad` (no 4*grid corner snapping or
+	/// safety cells), which keeps thin slabs thin. Dimensions are rounded up to multiples of 4 unless
+	/// `minimal_dimensions` is set.
+	pub fn from_atoms_with_options(atoms: &[Atom], probe: f32, grid: f32, options: &GridOptions) -> Option<Self> {
+		if atoms.len() < 3 || check_grid_size(grid).is_err() {
 			return None;
 		}
 
@@ -65,28 +156,19 @@ impl GridParams {
 
 		// Initial padding: MAX_VDW + probe + 2*grid, aligned to 4*grid boundaries.
 		let fact = MAX_VDW + probe + 2.0 * grid;
-		min_x = align_down_four(min_x - fact, grid);
-		min_y = align_down_four(min_y - fact, grid);
-		min_z = align_down_four(min_z - fact, grid);
-		max_x = align_up_four(max_x + fact, grid);
-		max_y = align_up_four(max_y + fact, grid);
-		max_z = align_up_four(max_z + fact, grid);
 
 		// Safety padding based on probe/grid ratio.
 		let safety_cells = (probe / grid).ceil() as i32 + 2;
-		if safety_cells > 0 {
-			let padding = safety_cells as f32 * grid;
-			min_x -= padding;
-			min_y -= padding;
-			min_z -= padding;
-			max_x += padding;
-			max_y += padding;
-			max_z += padding;
-		}
+		let safety = if safety_cells > 0 { safety_cells as f32 * grid } else { 0.0 };
+
+		(min_x, max_x) = padded_bounds(min_x, max_x, options.pad_x, fact, safety, grid);
+		(min_y, max_y) = padded_bounds(min_y, max_y, options.pad_y, fact, safety, grid);
+		(min_z, max_z) = padded_bounds(min_z, max_z, options.pad_z, fact, safety, grid);
 
-		let len_i = calculate_dimension(min_x, max_x, grid);
-		let len_j = calculate_dimension(min_y, max_y, grid);
-		let len_k = calculate_dimension(min_z, max_z, grid);
+		let dimension = if options.minimal_dimensions { minimal_dimension } else { calculate_dimension };
+		let len_i = dimension(min_x, max_x, grid);
+		let len_j = dimension(min_y, max_y, grid);
+		let len_k = dimension(min_z, max_z, grid);
 
 		Some(Self {
 			xmin: min_x,
@@ -102,13 +184,135 @@ impl GridParams {
 		})
 	}
 
+	/// Grid parameters for an explicit bounding box (angstroms), so several structures can share one grid.
+	/// With `align`, the corners snap outward to multiples of 4*grid and dimensions round up to multiples
+	/// of 4 as in `from_atoms`; without it the grid starts exactly at `min` with the fewest voxels reaching `max`.
+	/// Errors with `InvalidInput` if `grid` is not finite and positive.
+	pub fn from_box(min: [f32; 3], max: [f32; 3], grid: f32, align: bool) -> io::Result<Self> {
+		check_grid_size(grid)?;
+		let block = 4.0 * grid;
+		let (lo, hi) = if align {
+			(
+				min.map(|v| (v / block).floor() * block),
+				max.map(|v| (v / block).ceil() * block),
+			)
+		} else {
+			(min, max)
+		};
+		let dimension = |axis: usize| {
+			if align {
+				calculate_dimension(lo[axis], hi[axis], grid)
+			} else {
+				minimal_dimension(lo[axis], hi[axis], grid)
+			}
+		};
+
+		Ok(Self {
+			xmin: lo[0],
+			xmax: hi[0],
+			ymin: lo[1],
+			ymax: hi[1],
+			zmin: lo[2],
+			zmax: hi[2],
+			len_i: dimension(0),
+			len_j: dimension(1),
+			len_k: dimension(2),
+			grid,
+		})
+	}
+
+	/// Grid parameters from the bounding box of the atoms' `radius + probe` spheres plus two voxels,
+	/// aligned as `from_box` with `align`. Unlike `from_atoms` this accepts one or two atoms.
+	/// Returns `None` with no atom of usable radius or a `grid` that is not finite and positive.
+	pub fn from_atom_bounds(atoms: &[Atom], probe: f32, grid: f32) -> Option<Self> {
+		if check_grid_size(grid).is_err() {
+			return None;
+		}
+
+		let mut lo = [f32::MAX; 3];
+		let mut hi = [f32::MIN; 3];
+		let mut counted = 0usize;
+		for atom in atoms.iter().filter(|a| a.radius > 0.0 && a.radius < 100.0) {
+			let reach = atom.radius + probe + 2.0 * grid;
+			for (axis, center) in [atom.x, atom.y, atom.z].into_iter().enumerate() {
+				lo[axis] = lo[axis].min(center - reach);
+				hi[axis] = hi[axis].max(center + reach);
+			}
+			counted += 1;
+		}
+
+		if counted == 0 {
+			return None;
+		}
+		Self::from_box(lo, hi, grid, true).ok()
+	}
+
+	/// True if the grid built from these parameters contains every atom's `radius + probe` sphere
+	/// (between the first and last voxel centers on each axis), i.e. nothing would be clipped.
+	pub fn encloses(&self, atoms: &[Atom], probe: f32) -> bool {
+		self.first_atom_outside(atoms, probe).is_none()
+	}
+
+	/// Index of the first atom whose `radius + probe` sphere extends past the grid, if any.
+	pub fn first_atom_outside(&self, atoms: &[Atom], probe: f32) -> Option<usize> {
+		let lo = [self.xmin, self.ymin, self.zmin];
+		let hi = [
+			self.xmin + (self.len_i.max(1) - 1) as f32 * self.grid,
+			self.ymin + (self.len_j.max(1) - 1) as f32 * self.grid,
+			self.zmin + (self.len_k.max(1) - 1) as f32 * self.grid,
+		];
+		first_atom_outside(lo, hi, atoms, probe)
+	}
+
+	/// Estimated bytes needed by `build_grid`, to check before allocating a large grid.
+	pub fn estimated_memory(&self) -> usize {
+		estimate_grid_memory(self.len_i, self.len_j, self.len_k)
+	}
+
 	/// Instantiate a `Grid3D` using these parameters.
 	pub fn build_grid(&self) -> Grid3D {
-		let mut grid = Grid3D::new(self.len_i, self.len_j, self.len_k, self.grid);
-		grid.x_shift = self.xmin;
-		grid.y_shift = self.ymin;
-		grid.z_shift = self.zmin;
-		grid
+		Grid3D::new(self.len_i, self.len_j, self.len_k, self.grid).with_shifts(self.xmin, self.ymin, self.zmin)
+	}
+}
+
+impl Grid3D {
+	/// Empty grid sized and shifted for `atoms` by the legacy padding rules (`GridParams::from_atoms`),
+	/// ready for a fill step of the caller's choosing. `None` under the same conditions as `from_atoms`.
+	pub fn fit_atoms(atoms: &[Atom], probe: f32, grid: f32) -> Option<Grid3D> {
+		GridParams::from_atoms(atoms, probe, grid).map(|params| params.build_grid())
+	}
+
+	/// True if every atom's `radius + probe` sphere lies within the grid (between the first and last
+	/// voxel centers on each axis). Spheres past the edge are silently clipped by the rasterizers.
+	pub fn encloses(&self, atoms: &[Atom], probe: f32) -> bool {
+		self.first_atom_outside(atoms, probe).is_none()
+	}
+
+	/// Index of the first atom whose `radius + probe` sphere extends past the grid, if any.
+	pub fn first_atom_outside(&self, atoms: &[Atom], probe: f32) -> Option<usize> {
+		let lo = self.voxel_to_physical(0, 0, 0);
+		let hi = self.voxel_to_physical(self.len_i.max(1) - 1, self.len_j.max(1) - 1, self.len_k.max(1) - 1);
+		first_atom_outside([lo.0, lo.1, lo.2], [hi.0, hi.1, hi.2], atoms, probe)
+	}
+}
+
+/// First atom whose `radius + probe` sphere (a point for a non-positive sum) leaves the box `lo..=hi`.
+fn first_atom_outside(lo: [f32; 3], hi: [f32; 3], atoms: &[Atom], probe: f32) -> Option<usize> {
+	atoms.iter().position(|atom| {
+		let r = (atom.radius + probe).max(0.0);
+		let center = [atom.x, atom.y, atom.z];
+		(0..3).any(|axis| !(center[axis] - r >= lo[axis] && center[axis] + r <= hi[axis]))
+	})
+}
+
+/// Pad one axis: an explicit pad in angstroms, or the legacy 4*grid-aligned padding plus safety cells.
+fn padded_bounds(min: f32, max: f32, pad: Option<f32>, fact: f32, safety: f32, grid: f32) -> (f32, f32) {
+	match pad {
+		Some(pad) => (min - pad, max + pad),
+		None => (
+			align_down_four(min - fact, grid) - safety,
+			align_up_four(max + fact, grid) + safety,
+		),
 	}
 }
 
@@ -117,6 +321,11 @@ fn calculate_dimension(min: f32, max: f32, grid: f32) -> usize {
 	(((span / 4.0) + 1.0).ceil() as usize) * 4
 }
 
+fn minimal_dimension(min: f32, max: f32, grid: f32) -> usize {
+	let span = ((max - min) / grid).ceil().max(0.0) as usize;
+	span.max(1)
+}
+
 fn align_down_four(value: f32, grid: f32) -> f32 {
 	let factor = value / (4.0 * grid);
 	let t = factor as i32; // truncates toward zero like C int cast
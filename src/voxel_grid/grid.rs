@@ -1,5 +1,11 @@
+use std::fmt;
+use std::io;
+
+use bitvec::slice::BitSlice;
 use bitvec::vec::BitVec;
 
+use crate::voxel_grid::storage::{Tiles, Voxels, VoxelsMut, empty_bits, split_bits};
+
 /// 3D Voxel Grid with bit-packed storage
 #[derive(Clone)]
 pub struct Grid3D {
@@ -7,18 +13,38 @@ pub struct Grid3D {
 	pub len_j: usize,  // Number of voxels along J
 	pub len_k: usize,  // Number of voxels along K
 	pub total_voxels: usize, // Total number of voxels IxJxK
-	pub grid_size: f32,  // Size of each voxel in angstroms
+	pub grid_size: f32,  // Size of each voxel in angstroms (the X spacing on anisotropic grids)
 	pub x_shift: f32,  // Offset for X to align with I=0
 	pub y_shift: f32,  // Offset for Y to align with J=0
 	pub z_shift: f32,  // Offset for Z to align with K=0
-	pub data: BitVec,  // 1-bit per voxel storage
+	pub data: BitVec,  // 1-bit per voxel storage, i fastest; empty when the grid is tiled (see `tile_count`)
+	pub(crate) tiles: Option<Tiles>,  // Tiled storage from `new_tiled`/`with_tiles`; `None` keeps every voxel in `data`
+	pub(crate) filled_cache: Option<usize>,  // Running filled count, tracked once `filled_count_cached` is called
+	pub(crate) last_probe: Option<f32>,  // Probe of the accessible fill that produced `data` (None = not an accessible grid)
+	pub(crate) spacing: Option<[f32; 3]>,  // Per-axis voxel size for anisotropic grids (None = cubic `grid_size`)
 }
 
 impl Grid3D {
 	/// Create a new voxel grid, fully allocated with all voxels set to `false`
+	/// Panics if `grid_size` is not finite and positive or the voxel count overflows `usize`;
+	/// use `try_new` to get an error instead.
 	pub fn new(len_i: usize, len_j: usize, len_k: usize, grid_size: f32) -> Self {
-		let total_voxels = len_i * len_j * len_k;
+		Self::new_tiled(len_i, len_j, len_k, grid_size, 0)
+	}
+
+	/// Like `new`, but the voxels live in tiles of `tile_voxels` bits (one `BitVec` each, 0 = a single `BitVec`),
+	/// so a grid too large for one allocation can still be built, e.g.
+	/// `Grid3D::new_tiled(2048, 2048, 2048, 0.1, 1 << 30)`. Results do not depend on the tiling.
+	pub fn new_tiled(len_i: usize, len_j: usize, len_k: usize, grid_size: f32, tile_voxels: usize) -> Self {
+		if let Err(e) = check_grid_size(grid_size) {
+			panic!("{}", e);
+		}
+		let total_voxels = match checked_total_voxels(len_i, len_j, len_k) {
+			Ok(total) => total,
+			Err(e) => panic!("{}", e),
+		};
 
+		let (data, tiles) = empty_bits(total_voxels, tile_voxels); // Pre-allocate full grid
 		Self {
 			len_i,
 			len_j,
@@ -28,7 +54,235 @@ impl Grid3D {
 			x_shift: 0.0,
 			y_shift: 0.0,
 			z_shift: 0.0,
-			data: BitVec::repeat(false, total_voxels), // Pre-allocate full grid
+			data,
+			tiles,
+			filled_cache: None,
+			last_probe: None,
+			spacing: None,
+		}
+	}
+
+	/// Create an empty grid with voxel size `spacing` = [x, y, z] angstroms, e.g. for cryo-EM maps with
+	/// non-cubic voxels. `grid_size` is set to the X spacing; equal spacings give an ordinary cubic grid.
+	/// Rasterization, coordinate transforms, volumes, distances, meshes, and MRC output honor the spacing;
+	/// the surface-area estimates still assume cubic voxels of `grid_size`.
+	/// Panics if any spacing is not finite and positive; use `try_new_anisotropic` to get an error instead.
+	pub fn new_anisotropic(len_i: usize, len_j: usize, len_k: usize, spacing: [f32; 3]) -> Self {
+		for &size in spacing.iter() {
+			if let Err(e) = check_grid_size(size) {
+				panic!("{}", e);
+			}
+		}
+		let mut grid = Self::new(len_i, len_j, len_k, spacing[0]);
+		if spacing[1] != spacing[0] || spacing[2] != spacing[0] {
+			grid.spacing = Some(spacing);
 		}
+		grid
 	}
+
+	/// Checked `new_anisotropic`: errors with `InvalidInput` unless every spacing is finite and positive
+	/// and the voxel count fits in `usize`
+	pub fn try_new_anisotropic(len_i: usize, len_j: usize, len_k: usize, spacing: [f32; 3]) -> io::Result<Self> {
+		for &size in spacing.iter() {
+			check_grid_size(size)?;
+		}
+		checked_total_voxels(len_i, len_j, len_k)?;
+		Ok(Self::new_anisotropic(len_i, len_j, len_k, spacing))
+	}
+
+	/// Voxel size along X, Y, Z in angstroms
+	pub fn spacing(&self) -> [f32; 3] {
+		self.spacing.unwrap_or([self.grid_size; 3])
+	}
+
+	/// True if the voxels are not cubes
+	pub fn is_anisotropic(&self) -> bool {
+		self.spacing.is_some()
+	}
+
+	/// Volume of one voxel in cubic angstroms
+	pub fn voxel_volume(&self) -> f64 {
+		let [x, y, z] = self.spacing();
+		x as f64 * y as f64 * z as f64
+	}
+
+	/// Per-axis spacing in units of `grid_size`; exactly 1.0 on every axis for cubic grids,
+	/// so scaling voxel-unit distances by it leaves cubic results bit-for-bit unchanged.
+	pub(crate) fn axis_scale(&self) -> [f32; 3] {
+		match self.spacing {
+			Some(spacing) => spacing.map(|size| size / self.grid_size),
+			None => [1.0; 3],
+		}
+	}
+
+	/// Checked `new`: errors with `InvalidInput` unless `grid_size` is finite and positive
+	/// and the voxel count fits in `usize` (a real limit for fine grids on 32-bit targets)
+	pub fn try_new(len_i: usize, len_j: usize, len_k: usize, grid_size: f32) -> io::Result<Self> {
+		check_grid_size(grid_size)?;
+		checked_total_voxels(len_i, len_j, len_k)?;
+		Ok(Self::new(len_i, len_j, len_k, grid_size))
+	}
+
+	/// Set the physical position (angstroms) of voxel (0, 0, 0), e.g.
+	/// `Grid3D::new(64, 64, 64, 0.5).with_shifts(-16.0, -16.0, -16.0)`
+	pub fn with_shifts(mut self, x_shift: f32, y_shift: f32, z_shift: f32) -> Self {
+		self.x_shift = x_shift;
+		self.y_shift = y_shift;
+		self.z_shift = z_shift;
+		self
+	}
+
+	/// Store the voxels in tiles of `tile_voxels` bits (one `BitVec` each) instead of a single allocation.
+	/// Voxel values are kept; `0` (or a tile covering the whole grid) returns to one `BitVec` in `data`.
+	/// Every method gives the same result on tiled and whole storage, but a tiled grid's `data` field is empty.
+	pub fn with_tiles(mut self, tile_voxels: usize) -> Self {
+		let (mut data, mut tiles) = empty_bits(self.total_voxels, tile_voxels);
+		let mut target = VoxelsMut::new(&mut data, &mut tiles);
+		for idx in self.voxels().iter_ones() {
+			target.set(idx, true);
+		}
+		self.data = data;
+		self.tiles = tiles;
+		self
+	}
+
+	/// Voxels per storage tile, or 0 when the grid is one `BitVec`
+	pub fn tile_voxels(&self) -> usize {
+		self.voxels().tile_bits()
+	}
+
+	/// Number of separate bit allocations backing the grid (1 unless the grid is tiled)
+	pub fn tile_count(&self) -> usize {
+		self.voxels().tile_count()
+	}
+
+	/// The whole-grid `BitVec` (flat index, i fastest), or `None` for a tiled grid, whose `data` is empty
+	pub fn bits(&self) -> Option<&BitVec> {
+		match self.tiles {
+			Some(_) => None,
+			None => Some(&self.data),
+		}
+	}
+
+	/// Mutable `bits`, as a slice so the length cannot change; turns off the filled-count cache,
+	/// since writes through it bypass the voxel setters
+	pub fn bits_mut(&mut self) -> Option<&mut BitSlice> {
+		self.filled_cache = None;
+		match self.tiles {
+			Some(_) => None,
+			None => Some(self.data.as_mut_bitslice()),
+		}
+	}
+
+	/// Read-only view of the voxels, whole or tiled
+	pub(crate) fn voxels(&self) -> Voxels<'_> {
+		Voxels::new(&self.data, &self.tiles)
+	}
+
+	/// Mutable view of the voxels, whole or tiled
+	pub(crate) fn voxels_mut(&mut self) -> VoxelsMut<'_> {
+		VoxelsMut::new(&mut self.data, &mut self.tiles)
+	}
+
+	/// Replace the voxel storage with `bits`, keeping the current tiling
+	pub(crate) fn replace_bits(&mut self, bits: BitVec) {
+		let (data, tiles) = split_bits(bits, self.tile_voxels());
+		self.data = data;
+		self.tiles = tiles;
+	}
+
+	/// Create an empty grid with the same dimensions, spacing, shifts, and storage tiling
+	pub fn empty_like(&self) -> Self {
+		let mut grid = Self::new_tiled(self.len_i, self.len_j, self.len_k, self.grid_size, self.tile_voxels())
+			.with_shifts(self.x_shift, self.y_shift, self.z_shift);
+		grid.spacing = self.spacing;
+		grid
+	}
+
+	/// 64-bit FNV-1a hash of the dimensions, spacing, shifts, and filled voxel indices, for deduplicating
+	/// cached outputs and cheap equality checks. Stable across runs and platforms (unlike `DefaultHasher`),
+	/// so it can name cache files. Equal hashes mean equal grids with overwhelming probability.
+	pub fn content_hash(&self) -> u64 {
+		let mut hash = FNV_OFFSET;
+		let mut feed = |word: u64| {
+			for byte in word.to_le_bytes() {
+				hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+			}
+		};
+		for len in [self.len_i, self.len_j, self.len_k] {
+			feed(len as u64);
+		}
+		for value in self.spacing().into_iter().chain([self.grid_size, self.x_shift, self.y_shift, self.z_shift]) {
+			feed(value.to_bits() as u64);
+		}
+		for idx in self.iter_filled() {
+			feed(idx as u64);
+		}
+		hash
+	}
+}
+
+/// 64-bit FNV-1a parameters for `content_hash`.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Summary instead of the raw bits: dimensions, spacing, shifts, and the filled count
+impl fmt::Debug for Grid3D {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut out = f.debug_struct("Grid3D");
+		out.field("len_i", &self.len_i)
+			.field("len_j", &self.len_j)
+			.field("len_k", &self.len_k)
+			.field("grid_size", &self.grid_size);
+		if let Some(spacing) = self.spacing {
+			out.field("spacing", &spacing);
+		}
+		out.field("x_shift", &self.x_shift)
+			.field("y_shift", &self.y_shift)
+			.field("z_shift", &self.z_shift)
+			.field("total_voxels", &self.total_voxels)
+			.field("filled", &self.count_filled())
+			.finish()
+	}
+}
+
+/// One-line summary, e.g. `64x64x64 grid, 0.500 A voxels, 12034 of 262144 filled (4.59%)`
+impl fmt::Display for Grid3D {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}x{}x{} grid, ", self.len_i, self.len_j, self.len_k)?;
+		match self.spacing {
+			Some([sx, sy, sz]) => write!(f, "{:.3}x{:.3}x{:.3} A voxels", sx, sy, sz)?,
+			None => write!(f, "{:.3} A voxels", self.grid_size)?,
+		}
+		write!(
+			f,
+			", {} of {} filled ({:.2}%)",
+			self.count_filled(),
+			self.total_voxels,
+			100.0 * self.occupancy_fraction()
+		)
+	}
+}
+
+/// Voxel spacing must be finite and positive, or every `/ grid_size` in the rasterizer yields NaN/Inf
+pub(crate) fn check_grid_size(grid_size: f32) -> io::Result<()> {
+	if grid_size.is_finite() && grid_size > 0.0 {
+		Ok(())
+	} else {
+		Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("grid_size must be finite and positive, got {}", grid_size),
+		))
+	}
+}
+
+/// `len_i * len_j * len_k`, or `InvalidInput` if it overflows `usize` (the product would otherwise wrap
+/// in release builds and allocate a grid far smaller than its dimensions claim)
+pub(crate) fn checked_total_voxels(len_i: usize, len_j: usize, len_k: usize) -> io::Result<usize> {
+	len_i.checked_mul(len_j).and_then(|n| n.checked_mul(len_k)).ok_or_else(|| {
+		io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("grid of {}x{}x{} voxels overflows the addressable voxel count", len_i, len_j, len_k),
+		)
+	})
 }
@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+
+use crate::voxel_grid::raster::Atom;
+
+/// Volume (cubic angstroms) of the convex hull of the atom centers, by 3D quickhull.
+/// Radii are ignored. Atoms with non-finite coordinates are skipped; fewer than four
+/// non-coplanar centers give 0.0. Dividing a molecular volume by this gives a compactness measure.
+pub fn convex_hull_volume(atoms: &[Atom]) -> f64 {
+	let points: Vec<[f64; 3]> = atoms
+		.iter()
+		.filter(|atom| atom.x.is_finite() && atom.y.is_finite() && atom.z.is_finite())
+		.map(|atom| [atom.x as f64, atom.y as f64, atom.z as f64])
+		.collect();
+	match quickhull(&points) {
+		Some((faces, inside)) => faces
+			.iter()
+			.map(|&[a, b, c]| tetra_volume(inside, points[a], points[b], points[c]))
+			.sum(),
+		None => 0.0,
+	}
+}
+
+/// Hull face with an outward unit normal and the points still outside it.
+struct Face {
+	v: [usize; 3],
+	normal: [f64; 3],
+	offset: f64,
+	outside: Vec<usize>,
+	alive: bool,
+}
+
+impl Face {
+	fn distance(&self, p: [f64; 3]) -> f64 {
+		dot(self.normal, p) - self.offset
+	}
+}
+
+/// Triangles of the hull (counter-clockwise seen from outside) and a point inside it,
+/// or `None` if the points do not span a volume.
+fn quickhull(points: &[[f64; 3]]) -> Option<(Vec<[usize; 3]>, [f64; 3])> {
+	if points.len() < 4 {
+		return None;
+	}
+	let mut lo = [f64::MAX; 3];
+	let mut hi = [f64::MIN; 3];
+	for p in points {
+		for axis in 0..3 {
+			lo[axis] = lo[axis].min(p[axis]);
+			hi[axis] = hi[axis].max(p[axis]);
+		}
+	}
+	let extent = (0..3).map(|axis| hi[axis] - lo[axis]).fold(0.0, f64::max);
+	let eps = extent * 1e-9;
+	if extent == 0.0 {
+		return None;
+	}
+
+	// Initial tetrahedron: the farthest pair along the widest axis, then the farthest point from
+	// their line, then the farthest point from that plane.
+	let axis = (0..3).max_by(|&a, &b| (hi[a] - lo[a]).total_cmp(&(hi[b] - lo[b])))?;
+	let p0 = (0..points.len()).min_by(|&a, &b| points[a][axis].total_cmp(&points[b][axis]))?;
+	let p1 = (0..points.len()).max_by(|&a, &b| points[a][axis].total_cmp(&points[b][axis]))?;
+	let line = sub(points[p1], points[p0]);
+	let p2 = (0..points.len()).max_by(|&a, &b| {
+		let da = norm2(cross(line, sub(points[a], points[p0])));
+		let db = norm2(cross(line, sub(points[b], points[p0])));
+		da.total_cmp(&db)
+	})?;
+	let plane = cross(line, sub(points[p2], points[p0]));
+	if norm2(plane).sqrt() <= eps * norm2(line).sqrt() {
+		return None;
+	}
+	let p3 = (0..points.len()).max_by(|&a, &b| {
+		let da = dot(plane, sub(points[a], points[p0])).abs();
+		let db = dot(plane, sub(points[b], points[p0])).abs();
+		da.total_cmp(&db)
+	})?;
+	let unit_plane = scale(plane, 1.0 / norm2(plane).sqrt());
+	if dot(unit_plane, sub(points[p3], points[p0])).abs() <= eps {
+		return None;
+	}
+
+	let inside = scale(add(add(points[p0], points[p1]), add(points[p2], points[p3])), 0.25);
+	let mut faces: Vec<Face> = Vec::new();
+	for tri in [[p0, p1, p2], [p0, p1, p3], [p0, p2, p3], [p1, p2, p3]] {
+		faces.push(make_face(points, tri, inside));
+	}
+	let corners = [p0, p1, p2, p3];
+	for (idx, &p) in points.iter().enumerate() {
+		if !corners.contains(&idx) {
+			assign_outside(&mut faces, 0, idx, p, eps);
+		}
+	}
+
+	while let Some(face_idx) = faces.iter().position(|f| f.alive && !f.outside.is_empty()) {
+		let face = &faces[face_idx];
+		let apex = *face
+			.outside
+			.iter()
+			.max_by(|&&a, &&b| face.distance(points[a]).total_cmp(&face.distance(points[b])))?;
+		let visible: Vec<usize> = (0..faces.len())
+			.filter(|&f| faces[f].alive && faces[f].distance(points[apex]) > eps)
+			.collect();
+
+		// Horizon: directed edges of visible faces whose reverse edge is not also visible.
+		let edges: Vec<(usize, usize)> = visible
+			.iter()
+			.flat_map(|&f| {
+				let [a, b, c] = faces[f].v;
+				[(a, b), (b, c), (c, a)]
+			})
+			.collect();
+		let edge_set: HashSet<(usize, usize)> = edges.iter().copied().collect();
+		let mut orphans: Vec<usize> = Vec::new();
+		for &f in visible.iter() {
+			faces[f].alive = false;
+			orphans.append(&mut faces[f].outside);
+		}
+
+		let first_new = faces.len();
+		for &(a, b) in edges.iter() {
+			if !edge_set.contains(&(b, a)) {
+				faces.push(make_face(points, [a, b, apex], inside));
+			}
+		}
+		for idx in orphans {
+			if idx != apex {
+				assign_outside(&mut faces, first_new, idx, points[idx], eps);
+			}
+		}
+	}
+
+	let triangles = faces.iter().filter(|f| f.alive).map(|f| f.v).collect();
+	Some((triangles, inside))
+}
+
+/// Face through `tri`, wound so its normal points away from `inside`.
+fn make_face(points: &[[f64; 3]], tri: [usize; 3], inside: [f64; 3]) -> Face {
+	let [a, b, c] = tri;
+	let mut normal = cross(sub(points[b], points[a]), sub(points[c], points[a]));
+	let length = norm2(normal).sqrt();
+	if length > 0.0 {
+		normal = scale(normal, 1.0 / length);
+	}
+	let mut face = Face { v: tri, normal, offset: dot(normal, points[a]), outside: Vec::new(), alive: true };
+	if face.distance(inside) > 0.0 {
+		face.v = [a, c, b];
+		face.normal = scale(normal, -1.0);
+		face.offset = -face.offset;
+	}
+	face
+}
+
+/// Give the point to the first live face from `first` on that it lies outside of, if any.
+fn assign_outside(faces: &mut [Face], first: usize, idx: usize, p: [f64; 3], eps: f64) {
+	if let Some(face) = faces[first..].iter_mut().find(|f| f.alive && f.distance(p) > eps) {
+		face.outside.push(idx);
+	}
+}
+
+/// Signed volume of the tetrahedron from `apex` to triangle (a, b, c); positive when the triangle
+/// winds counter-clockwise seen from outside.
+fn tetra_volume(apex: [f64; 3], a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> f64 {
+	dot(sub(a, apex), cross(sub(b, apex), sub(c, apex))) / 6.0
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], s: f64) -> [f64; 3] {
+	[a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn norm2(a: [f64; 3]) -> f64 {
+	dot(a, a)
+}
@@ -1,36 +1,67 @@
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Once;
 
-/// Print citation information (only prints once)
+/// Process-wide switch for `print_citation` / `print_compile_info` (on by default).
+static VERBOSE: AtomicBool = AtomicBool::new(true);
+
+/// Turn the info printers on or off; a library embedding the crate can silence them and show
+/// `citation_text()` itself where it fits.
+pub fn set_verbose(verbose: bool) {
+	VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// True unless `set_verbose(false)` was called.
+pub fn is_verbose() -> bool {
+	VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Citation for the method, as printed by `print_citation`.
+pub fn citation_text() -> &'static str {
+	"Citation: Neil R Voss, et al. J Mol Biol. v360 (4): 2006, pp. 893-906.\n\
+	 DOI: http://dx.doi.org/10.1016/j.jmb.2006.05.023\n\
+	 E-mail: M Gerstein <mark.gerstein@yale.edu> or NR Voss <vossman77@yahoo.com>\n"
+}
+
+/// Program name, build date, and version, as printed by `print_compile_info`.
+pub fn compile_info_text() -> String {
+	// Get the executable name
+	let program_name = env::current_exe()
+	.ok()
+	.as_ref()
+	.and_then(|path| path.file_name()) // Extract filename
+	.and_then(|name| name.to_str()) // Convert to &str
+	.unwrap_or("Unknown Program") // Fallback
+
+	.to_string();
+
+	format!(
+		"Program: {}\nCompiled on: {} at {}\nRust version: {}",
+		program_name,
+		env!("COMPILE_DATE"),
+		env!("COMPILE_TIME"),
+		env!("CARGO_PKG_VERSION")
+	)
+}
+
+/// Print citation information (only prints once; nothing when `set_verbose(false)`)
 pub fn print_citation() {
+	if !is_verbose() {
+		return;
+	}
 	static PRINT_CITATION_ONCE: Once = Once::new();
 	PRINT_CITATION_ONCE.call_once(|| {
-		eprintln!("Citation: Neil R Voss, et al. J Mol Biol. v360 (4): 2006, pp. 893-906.");
-		eprintln!("DOI: http://dx.doi.org/10.1016/j.jmb.2006.05.023");
-		eprintln!("E-mail: M Gerstein <mark.gerstein@yale.edu> or NR Voss <vossman77@yahoo.com>\n");
+		eprintln!("{}", citation_text());
 	});
 }
 
-/// Print compilation information (only prints once)
+/// Print compilation information (only prints once; nothing when `set_verbose(false)`)
 pub fn print_compile_info() {
+	if !is_verbose() {
+		return;
+	}
 	static PRINT_COMPILE_ONCE: Once = Once::new();
 	PRINT_COMPILE_ONCE.call_once(|| {
-		// Get the executable name
-		let program_name = env::current_exe()
-		.ok()
-		.as_ref()
-		.and_then(|path| path.file_name()) // Extract filename
-		.and_then(|name| name.to_str()) // Convert to &str
-		.unwrap_or("Unknown Program") // Fallback
-
-		.to_string();
-
-		eprintln!("Program: {}", program_name);
-		eprintln!(
-			"Compiled on: {} at {}",
-			env!("COMPILE_DATE"),
-					 env!("COMPILE_TIME")
-		);
-		eprintln!("Rust version: {}", env!("CARGO_PKG_VERSION"));
+		eprintln!("{}", compile_info_text());
 	});
 }
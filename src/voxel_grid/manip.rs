@@ -1,4 +1,6 @@
+use crate::voxel_grid::float_grid::FloatGrid3D;
 use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::raster::Atom;
 use indicatif::{ProgressBar, ProgressStyle};
 
 impl Grid3D {
@@ -68,4 +70,124 @@ impl Grid3D {
 		self.modify_sphere(ci, cj, ck, radius, false);
 	}
 
+	/// Voxels filled in either grid (dimensions must match)
+	pub fn union(&self, other: &Grid3D) -> Grid3D {
+		self.combine(other, |a, b| a || b)
+	}
+
+	/// Voxels filled in both grids (dimensions must match)
+	pub fn intersection(&self, other: &Grid3D) -> Grid3D {
+		self.combine(other, |a, b| a && b)
+	}
+
+	/// Voxels filled in `self` but not in `other` (dimensions must match)
+	pub fn difference(&self, other: &Grid3D) -> Grid3D {
+		self.combine(other, |a, b| a && !b)
+	}
+
+	/// Voxels filled in exactly one of the grids (dimensions must match)
+	pub fn symmetric_difference(&self, other: &Grid3D) -> Grid3D {
+		self.combine(other, |a, b| a != b)
+	}
+
+	/// Signed difference map: +1.0 where only `self` is filled, -1.0 where only `other` is, 0.0 elsewhere
+	/// (dimensions must match; the result takes the spacing and shifts of `self`). Write it with
+	/// `FloatGrid3D::write_to_mrc_file` to contour gained and lost volume separately.
+	pub fn signed_difference(&self, other: &Grid3D) -> FloatGrid3D {
+		assert!(
+			self.len_i == other.len_i && self.len_j == other.len_j && self.len_k == other.len_k,
+			"grid dimensions must match for boolean operations"
+		);
+		let mut out = FloatGrid3D::like_grid(self);
+		for (idx, value) in out.data.iter_mut().enumerate() {
+			*value = match (self.get_voxel_index(idx), other.get_voxel_index(idx)) {
+				(true, false) => 1.0,
+				(false, true) => -1.0,
+				_ => 0.0,
+			};
+		}
+		out
+	}
+
+	/// Apply a per-voxel boolean operation; the result keeps the dimensions and shifts of `self`.
+	fn combine(&self, other: &Grid3D, op: impl Fn(bool, bool) -> bool) -> Grid3D {
+		assert!(
+			self.len_i == other.len_i && self.len_j == other.len_j && self.len_k == other.len_k,
+			"grid dimensions must match for boolean operations"
+		);
+		let mut out = self.empty_like();
+		for idx in 0..self.total_voxels {
+			if op(self.get_voxel_index(idx), other.get_voxel_index(idx)) {
+				out.fill_voxel_index(idx);
+			}
+		}
+		out
+	}
+
+	/// New grid holding only the filled voxels within `radius` angstroms of `center` (physical coordinates).
+	/// The sphere is stamped with the rasterizer's stencil (voxel centers strictly inside `radius`).
+	pub fn mask_sphere(&self, center: [f32; 3], radius: f32) -> Grid3D {
+		let mut sphere = self.empty_like();
+		sphere.fill_accessible_into(&[Atom::new(center[0], center[1], center[2], radius)], 0.0);
+		self.intersection(&sphere)
+	}
+
+	/// Clear filled voxels on the positive side of the plane `normal . p + offset = 0` (physical coordinates).
+	/// Voxels on the plane are kept. A plane that misses the grid clears either every voxel or none,
+	/// depending on which side of it the grid lies.
+	pub fn clip_plane(&mut self, normal: [f32; 3], offset: f32) {
+		for idx in 0..self.total_voxels {
+			if !self.get_voxel_index(idx) {
+				continue;
+			}
+			let (i, j, k) = self.index_to_ijk(idx);
+			let (x, y, z) = self.voxel_to_physical(i, j, k);
+			if normal[0] * x + normal[1] * y + normal[2] * z + offset > 0.0 {
+				self.empty_voxel_index(idx);
+			}
+		}
+	}
+
+	/// Copy of the grid cropped to the bounding box of its filled voxels plus `pad` empty voxels on every
+	/// side (the padding may extend past this grid), with shifts moved so every voxel keeps its physical
+	/// position. Returns `None` for an empty grid. Shrinks mostly-padding grids before MRC export.
+	pub fn crop_to_content(&self, pad: usize) -> Option<Grid3D> {
+		self.crop_where(pad, |idx| self.get_voxel_index(idx))
+	}
+
+	/// Crop to the voxels where `keep(index)` holds, as `crop_to_content` does for filled voxels.
+	pub(crate) fn crop_where<F: Fn(usize) -> bool>(&self, pad: usize, keep: F) -> Option<Grid3D> {
+		let mut lo = [usize::MAX; 3];
+		let mut hi = [0usize; 3];
+		for idx in (0..self.total_voxels).filter(|&idx| keep(idx)) {
+			let (i, j, k) = self.index_to_ijk(idx);
+			for (axis, v) in [i, j, k].into_iter().enumerate() {
+				lo[axis] = lo[axis].min(v);
+				hi[axis] = hi[axis].max(v);
+			}
+		}
+		if lo[0] > hi[0] {
+			return None;
+		}
+
+		let [sx, sy, sz] = self.spacing();
+		let origin = [0, 1, 2].map(|axis| lo[axis] as isize - pad as isize);
+		let lens = [0, 1, 2].map(|axis| hi[axis] - lo[axis] + 1 + 2 * pad);
+		let mut cropped = Grid3D::new(lens[0], lens[1], lens[2], self.grid_size).with_shifts(
+			self.x_shift + origin[0] as f32 * sx,
+			self.y_shift + origin[1] as f32 * sy,
+			self.z_shift + origin[2] as f32 * sz,
+		);
+		cropped.spacing = self.spacing;
+		for k in lo[2]..=hi[2] {
+			for j in lo[1]..=hi[1] {
+				for i in lo[0]..=hi[0] {
+					if keep(self.ijk_to_index(i, j, k)) {
+						cropped.fill_voxel_ijk(i - lo[0] + pad, j - lo[1] + pad, k - lo[2] + pad);
+					}
+				}
+			}
+		}
+		Some(cropped)
+	}
 }
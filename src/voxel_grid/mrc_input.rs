@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+
+use crate::voxel_grid::grid::Grid3D;
+
+/// Fixed MRC header size in bytes (extended header follows, `nsymbt` bytes long).
+const HEADER_BYTES: usize = 1024;
+
+impl Grid3D {
+	/// Read a byte-mode (mode 0) MRC map such as those from `write_to_mrc_file`; nonzero voxels are filled.
+	/// Voxel spacing per axis is the cell length over the sampling count (`x_length / mx`, ...), so maps
+	/// with non-cubic voxels come back anisotropic. Any `mapc/mapr/maps` axis order is accepted.
+	/// Shifts come from the origin fields, falling back to start index times spacing when the origin is 0.
+	/// Errors with `InvalidData` for other modes, a missing "MAP " stamp, or a truncated file.
+	pub fn read_mrc_file(path: &str) -> io::Result<Grid3D> {
+		let bytes = fs::read(path)?;
+		if bytes.len() < HEADER_BYTES {
+			return Err(invalid(format!("{}: file is shorter than an MRC header", path)));
+		}
+		if &bytes[208..212] != b"MAP " {
+			return Err(invalid(format!("{}: missing MAP stamp in MRC header", path)));
+		}
+		let word = |n: usize| i32::from_le_bytes([bytes[4 * n], bytes[4 * n + 1], bytes[4 * n + 2], bytes[4 * n + 3]]);
+		let real = |n: usize| f32::from_le_bytes([bytes[4 * n], bytes[4 * n + 1], bytes[4 * n + 2], bytes[4 * n + 3]]);
+
+		let counts = [word(0), word(1), word(2)];
+		let mode = word(3);
+		let starts = [word(4), word(5), word(6)];
+		let sampling = [word(7), word(8), word(9)];
+		let cell = [real(10), real(11), real(12)];
+		let order = [word(16), word(17), word(18)];
+		let nsymbt = word(23);
+		let origin = [real(49), real(50), real(51)];
+
+		if mode != 0 {
+			return Err(invalid(format!("{}: only byte (mode 0) MRC maps are supported, got mode {}", path, mode)));
+		}
+		let mut sorted = order;
+		sorted.sort_unstable();
+		if sorted != [1, 2, 3] {
+			return Err(invalid(format!("{}: bad MRC axis order {:?}", path, order)));
+		}
+		if counts.iter().chain(sampling.iter()).any(|&n| n <= 0) || nsymbt < 0 {
+			return Err(invalid(format!("{}: bad MRC dimensions {:?} / sampling {:?}", path, counts, sampling)));
+		}
+
+		// Counts and starts by physical axis (X, Y, Z) from column/row/section order.
+		let mut lens = [0usize; 3];
+		let mut axis_starts = [0i32; 3];
+		for (slot, &axis) in order.iter().enumerate() {
+			lens[(axis - 1) as usize] = counts[slot] as usize;
+			axis_starts[(axis - 1) as usize] = starts[slot];
+		}
+		let spacing: [f32; 3] = std::array::from_fn(|axis| cell[axis] / sampling[axis] as f32);
+		let shift = |axis: usize| {
+			if origin[axis] != 0.0 { origin[axis] } else { axis_starts[axis] as f32 * spacing[axis] }
+		};
+		let mut grid = Grid3D::try_new_anisotropic(lens[0], lens[1], lens[2], spacing)?
+			.with_shifts(shift(0), shift(1), shift(2));
+
+		let data_start = HEADER_BYTES + nsymbt as usize;
+		let voxels = &bytes[data_start.min(bytes.len())..];
+		if voxels.len() < grid.total_voxels {
+			return Err(invalid(format!(
+				"{}: expected {} voxel bytes, found {}", path, grid.total_voxels, voxels.len()
+			)));
+		}
+
+		// Columns fastest along order[0], then rows along order[1], then sections (as `mrc_voxel_bytes`).
+		let axis = order.map(|a| (a - 1) as usize);
+		let mut ijk = [0usize; 3];
+		let mut pos = 0usize;
+		for s in 0..lens[axis[2]] {
+			ijk[axis[2]] = s;
+			for r in 0..lens[axis[1]] {
+				ijk[axis[1]] = r;
+				for c in 0..lens[axis[0]] {
+					ijk[axis[0]] = c;
+					if voxels[pos] != 0 {
+						grid.fill_voxel_ijk(ijk[0], ijk[1], ijk[2]);
+					}
+					pos += 1;
+				}
+			}
+		}
+		Ok(grid)
+	}
+}
+
+fn invalid(message: String) -> io::Error {
+	io::Error::new(ErrorKind::InvalidData, message)
+}
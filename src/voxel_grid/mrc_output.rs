@@ -1,15 +1,31 @@
 use std::fs::File;
-use std::io::{Write, Result};
+use std::io::{self, Write, Result};
+use crate::voxel_grid::float_grid::FloatGrid3D;
 use crate::voxel_grid::grid;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::time::Instant;
 
+/// Where the MRC header puts the grid origin; our voxel (0, 0, 0) is centered at the grid shifts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MrcOrigin {
+	/// `xorigin` = center of voxel 0 (the shifts), `nxstart` = shift / spacing rounded.
+	/// ChimeraX places index 0 at `xorigin` when it is nonzero, so this lines up with "fit in map".
+	#[default]
+	Center,
+	/// `xorigin` = outer corner of voxel 0 (shift minus half a voxel), for tools that treat the origin as
+	/// the map edge; ChimeraX would show such a map shifted by half a voxel.
+	Corner,
+	/// `xorigin` = 0 with the origin carried only by `nxstart` (shift / spacing rounded, so sub-voxel
+	/// offsets are lost); for programs that ignore the origin fields. ChimeraX uses `nxstart * step` then.
+	StartOnly,
+}
+
 /// MRC Header Struct
 #[repr(C)]
 #[derive(Debug)]
 pub struct MRCHeader {
 	len_i: i32, len_j: i32, len_k: i32,  // Grid dimensions
-	mode: i32,                  // Data mode (0: Byte)
+	mode: i32,                  // Data mode (0: Byte, 2: 32-bit float)
 	istart: i32, jstart: i32, kstart: i32,  // Start positions
 	m_i: i32, m_j: i32, m_k: i32,  // Grid size
 	x_length: f32, y_length: f32, z_length: f32,  // Physical size
@@ -25,15 +41,24 @@ pub struct MRCHeader {
 
 impl MRCHeader {
 	/// Create a new MRC header
+	/// Start indices are the shifts in voxel units (rounded), so cropped or translated maps
+	/// line up with the full map they came from.
 	pub fn new(len_i: usize, len_j: usize, len_k: usize, grid_size: f32, x_shift: f32, y_shift: f32, z_shift: f32) -> Self {
+		Self::new_anisotropic(len_i, len_j, len_k, [grid_size; 3], x_shift, y_shift, z_shift)
+	}
+
+	/// Same as `new` with a voxel size per axis; `x/y/z_length` become count times spacing on each axis.
+	pub fn new_anisotropic(len_i: usize, len_j: usize, len_k: usize, spacing: [f32; 3], x_shift: f32, y_shift: f32, z_shift: f32) -> Self {
 		MRCHeader {
 			len_i: len_i as i32, len_j: len_j as i32, len_k: len_k as i32,
 			mode: 0,  // BYTE mode
-			istart: 0, jstart: 0, kstart: 0,
+			istart: start_index(x_shift, spacing[0]),
+			jstart: start_index(y_shift, spacing[1]),
+			kstart: start_index(z_shift, spacing[2]),
 			m_i: len_i as i32, m_j: len_j as i32, m_k: len_k as i32,
-			x_length: (len_i as f32) * grid_size,
-			y_length: (len_j as f32) * grid_size,
-			z_length: (len_k as f32) * grid_size,
+			x_length: cell_length(len_i, spacing[0]),
+			y_length: cell_length(len_j, spacing[1]),
+			z_length: cell_length(len_k, spacing[2]),
 			alpha: 90.0, beta: 90.0, gamma: 90.0,
 			mapc: 1, mapr: 2, maps: 3,
 			amin: 0.0, amax: 1.0, amean: 0.1,
@@ -48,6 +73,56 @@ impl MRCHeader {
 		}
 	}
 
+	/// Set `mapc/mapr/maps` (1 = X/I, 2 = Y/J, 3 = Z/K) and permute the column/row/section counts and
+	/// start indices to match. Errors with `InvalidInput` unless `order` is a permutation of 1, 2, 3.
+	pub fn set_axis_order(&mut self, order: [i32; 3]) -> Result<()> {
+		let mut sorted = order;
+		sorted.sort_unstable();
+		if sorted != [1, 2, 3] {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("MRC axis order must be a permutation of 1, 2, 3, got {:?}", order),
+			));
+		}
+		// Counts and starts by physical axis, from the current mapping.
+		let current = [self.mapc, self.mapr, self.maps];
+		let mut lens = [0i32; 3];
+		let mut starts = [0i32; 3];
+		for (slot, &axis) in current.iter().enumerate() {
+			lens[(axis - 1) as usize] = [self.len_i, self.len_j, self.len_k][slot];
+			starts[(axis - 1) as usize] = [self.istart, self.jstart, self.kstart][slot];
+		}
+		let pick = |values: &[i32; 3], slot: usize| values[(order[slot] - 1) as usize];
+		self.len_i = pick(&lens, 0);
+		self.len_j = pick(&lens, 1);
+		self.len_k = pick(&lens, 2);
+		self.istart = pick(&starts, 0);
+		self.jstart = pick(&starts, 1);
+		self.kstart = pick(&starts, 2);
+		self.mapc = order[0];
+		self.mapr = order[1];
+		self.maps = order[2];
+		Ok(())
+	}
+
+	/// Set the origin fields and start indices for `shift` (center of voxel 0, angstroms) and `spacing`
+	/// under `convention`; start indices follow the current `mapc/mapr/maps` order.
+	pub fn set_origin(&mut self, shift: [f32; 3], spacing: [f32; 3], convention: MrcOrigin) {
+		let starts: [i32; 3] = std::array::from_fn(|axis| start_index(shift[axis], spacing[axis]));
+		let origin = match convention {
+			MrcOrigin::Center => shift,
+			MrcOrigin::Corner => std::array::from_fn(|axis| shift[axis] - 0.5 * spacing[axis]),
+			MrcOrigin::StartOnly => [0.0; 3],
+		};
+		self.xorigin = origin[0];
+		self.yorigin = origin[1];
+		self.zorigin = origin[2];
+		let slot_start = |axis: i32| starts[(axis - 1) as usize];
+		self.istart = slot_start(self.mapc);
+		self.jstart = slot_start(self.mapr);
+		self.kstart = slot_start(self.maps);
+	}
+
 	/// Write the header to an MRC file
 	pub fn write_to_file(&self, file: &mut File) -> Result<()> {
 		let header_bytes = unsafe {
@@ -61,6 +136,20 @@ impl MRCHeader {
 	}
 }
 
+/// Convert a physical shift into the nearest whole-voxel start index
+fn start_index(shift: f32, grid_size: f32) -> i32 {
+	if grid_size > 0.0 {
+		(shift as f64 / grid_size as f64).round() as i32
+	} else {
+		0
+	}
+}
+
+/// Cell length along one axis, multiplied in f64 so large counts round only once
+fn cell_length(count: usize, spacing: f32) -> f32 {
+	(count as f64 * spacing as f64) as f32
+}
+
 impl grid::Grid3D {
 	/// Save the voxel grid as an MRC file and report save time
 	pub fn write_to_mrc_file(&self, filename: &str) {
@@ -68,9 +157,9 @@ impl grid::Grid3D {
 			let start_time = Instant::now(); // ⏱ Start Timer
 
 			// Create and write the MRC header
-			let header = MRCHeader::new(
+			let header = MRCHeader::new_anisotropic(
 				self.len_i, self.len_j, self.len_k,
-				self.grid_size, self.x_shift, self.y_shift, self.z_shift,
+				self.spacing(), self.x_shift, self.y_shift, self.z_shift,
 			);
 
 			if let Err(e) = header.write_to_file(&mut file) {
@@ -79,10 +168,7 @@ impl grid::Grid3D {
 			}
 
 			// Store voxel data as `u8` (no `i8`)
-			let mut voxel_bytes = vec![0u8; self.total_voxels];
-			self.data.iter().enumerate().for_each(|(i, bit)| {
-				voxel_bytes[i] = if *bit { 1u8 } else { 0u8 }; // Store as `0` or `1`
-			});
+			let voxel_bytes = self.mrc_voxel_bytes([1, 2, 3]);
 
 			// Write voxel data directly as `u8`
 			if let Err(e) = file.write_all(&voxel_bytes) {
@@ -97,4 +183,72 @@ impl grid::Grid3D {
 			eprintln!("Failed to create file: {}", filename);
 		}
 	}
+
+	/// Save as MRC with the given `mapc/mapr/maps` axis order (1 = X/I, 2 = Y/J, 3 = Z/K).
+	/// Voxels are written columns fastest along `order[0]`, then rows along `order[1]`, then sections,
+	/// so viewers honoring the header place every voxel where `write_to_mrc_file` would.
+	pub fn write_to_mrc_file_with_axes(&self, filename: &str, order: [i32; 3]) -> Result<()> {
+		self.write_to_mrc_file_with_origin(filename, order, MrcOrigin::Center)
+	}
+
+	/// Same as `write_to_mrc_file_with_axes`, with the origin written under `origin` (see `MrcOrigin`).
+	pub fn write_to_mrc_file_with_origin(&self, filename: &str, order: [i32; 3], origin: MrcOrigin) -> Result<()> {
+		let mut header = MRCHeader::new_anisotropic(
+			self.len_i, self.len_j, self.len_k,
+			self.spacing(), self.x_shift, self.y_shift, self.z_shift,
+		);
+		header.set_axis_order(order)?;
+		header.set_origin([self.x_shift, self.y_shift, self.z_shift], self.spacing(), origin);
+		let mut file = File::create(filename)?;
+		header.write_to_file(&mut file)?;
+		file.write_all(&self.mrc_voxel_bytes(order))?;
+		Ok(())
+	}
+
+	/// Voxels as 0/1 bytes in MRC column/row/section order for a valid axis `order`.
+	fn mrc_voxel_bytes(&self, order: [i32; 3]) -> Vec<u8> {
+		self.iter_axis_order(order.map(|a| (a - 1) as usize)).map(u8::from).collect()
+	}
+}
+
+impl FloatGrid3D {
+	/// Save as a mode 2 (32-bit float, little-endian) MRC map, with the origin and start indices of
+	/// `Grid3D::write_to_mrc_file` and the value range, mean, and RMS deviation in the header.
+	pub fn write_to_mrc_file(&self, filename: &str) -> Result<()> {
+		let mut header = MRCHeader::new_anisotropic(
+			self.len_i, self.len_j, self.len_k,
+			self.spacing, self.x_shift, self.y_shift, self.z_shift,
+		);
+		header.mode = 2;
+		if !self.data.is_empty() {
+			let n = self.data.len() as f64;
+			let mean = self.data.iter().map(|&v| v as f64).sum::<f64>() / n;
+			let variance = self.data.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n;
+			header.amin = self.data.iter().copied().fold(f32::INFINITY, f32::min);
+			header.amax = self.data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+			header.amean = mean as f32;
+			header.rms = variance.sqrt() as f32;
+		}
+		let mut file = File::create(filename)?;
+		header.write_to_file(&mut file)?;
+		let bytes: Vec<u8> = self.data.iter().flat_map(|v| v.to_le_bytes()).collect();
+		file.write_all(&bytes)?;
+		Ok(())
+	}
+}
+
+/// Write each grid as its own MRC map in directory `dir` (created if missing), named `volume_000.mrc`,
+/// `volume_001.mrc`, ... in input order, and return the written paths. ChimeraX opens the directory's
+/// maps as a series, so one decomposition (per pocket, per component) ships as one folder.
+pub fn write_multi_mrc(grids: &[&grid::Grid3D], dir: &str) -> Result<Vec<String>> {
+	std::fs::create_dir_all(dir)?;
+	let width = grids.len().saturating_sub(1).to_string().len().max(3);
+	let mut paths = Vec::with_capacity(grids.len());
+	for (index, grid) in grids.iter().enumerate() {
+		let path = std::path::Path::new(dir).join(format!("volume_{:0width$}.mrc", index, width = width));
+		let path = path.to_string_lossy().into_owned();
+		grid.write_to_mrc_file_with_axes(&path, [1, 2, 3])?;
+		paths.push(path);
+	}
+	Ok(paths)
 }
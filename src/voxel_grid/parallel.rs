@@ -0,0 +1,33 @@
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Environment variable consulted when no limit has been set in code.
+pub const THREADS_ENV_VAR: &str = "VOSSVOLVOX_THREADS";
+
+/// Process-wide worker thread cap (0 = not set).
+static THREAD_LIMIT: AtomicUsize = AtomicUsize::new(0);
+
+/// Cap the number of worker threads used by the parallel grid routines (0 clears the cap).
+pub fn set_thread_limit(limit: usize) {
+	THREAD_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// Number of worker threads to spawn.
+/// Order of precedence: `set_thread_limit`, then `VOSSVOLVOX_THREADS`, then `available_parallelism()`.
+pub fn worker_threads() -> usize {
+	let limit = THREAD_LIMIT.load(Ordering::Relaxed);
+	if limit > 0 {
+		return limit;
+	}
+	if let Some(n) = env::var(THREADS_ENV_VAR)
+		.ok()
+		.and_then(|v| v.trim().parse::<usize>().ok())
+		.filter(|&n| n > 0)
+	{
+		return n;
+	}
+	thread::available_parallelism()
+		.map(|n| n.get())
+		.unwrap_or(1)
+}
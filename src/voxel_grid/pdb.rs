@@ -8,20 +8,67 @@ use regex::Regex;
 use crate::voxel_grid::raster::Atom;
 
 /// Filtering options analogous to the C++ flags.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Filters {
 	pub exclude_water: bool,
 	pub exclude_ions: bool,
 	pub exclude_ligands: bool,
-	pub exclude_hetatm: bool,
+	pub exclude_hetatm: bool,  // HETATM-only residues, except polymer residues such as MSE
 	pub exclude_nucleic_acids: bool,
 	pub exclude_amino_acids: bool,
+	// Residue codes (case-insensitive) whose built-in classification is replaced; a code in several lists
+	// takes the last of water, ion, ligand, polymer
+	pub force_water: Vec<String>,
+	pub force_ion: Vec<String>,
+	pub force_ligand: Vec<String>,
+	pub force_polymer: Vec<String>,  // Kept by `exclude_hetatm` and never a ligand
+}
+
+/// Where atom radii come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RadiusSource {
+	/// Embedded atmtypenumbers radius table.
+	#[default]
+	Table,
+	/// B-factor column (61-66); zero or blank falls back to the table.
+	BFactorColumn,
+	/// Occupancy column (55-60); zero or blank falls back to the table.
+	OccupancyColumn,
+}
+
+/// Which alternate conformers (altLoc column 17) are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AltLocPolicy {
+	/// Every record, whatever its altLoc.
+	#[default]
+	All,
+	/// Per atom (chain, residue number, atom name), only the altLoc with the highest occupancy;
+	/// equal occupancies go to the alphabetically first altLoc. Records without an altLoc are kept.
+	HighestOccupancy,
 }
 
 #[derive(Debug, Clone)]
 pub struct PdbOptions {
 	pub use_united: bool,
 	pub filters: Filters,
+	pub radius_source: RadiusSource,
+	pub alt_loc: AltLocPolicy,
+	pub strict_radius_table: bool,  // Fail to load if any radius table pattern failed to compile
+	// Residue code (case-insensitive) -> `use_united` for that residue's table radii, e.g. explicit
+	// radii for a ligand in an otherwise united-atom run; unlisted residues follow `use_united`
+	pub united_overrides: HashMap<String, bool>,
+}
+
+impl PdbOptions {
+	/// Whether table radii for `residue` use united-atom values: its `united_overrides` entry, else `use_united`
+	pub fn use_united_for(&self, residue: &str) -> bool {
+		self.united_overrides
+			.iter()
+			.find(|(code, _)| code.trim().eq_ignore_ascii_case(residue.trim()))
+			.map_or(self.use_united, |(_, &united)| united)
+	}
 }
 
 impl Default for PdbOptions {
@@ -29,6 +76,10 @@ impl Default for PdbOptions {
 		Self {
 			use_united: true,
 			filters: Filters::default(),
+			radius_source: RadiusSource::Table,
+			alt_loc: AltLocPolicy::All,
+			strict_radius_table: false,
+			united_overrides: HashMap::new(),
 		}
 	}
 }
@@ -48,10 +99,19 @@ struct PatternEntry {
 	key: String,
 }
 
+/// A radius table pattern line whose residue or atom regex failed to compile; from `radius_table_bad_patterns`.
+#[derive(Debug, Clone)]
+pub struct BadPattern {
+	pub line: usize,  // 1-based line in the radius table
+	pub pattern: String,  // Residue and atom patterns as written
+	pub error: String,  // Regex compile error
+}
+
 #[derive(Debug)]
 struct RadiusTable {
 	patterns: Vec<PatternEntry>,
 	radii: HashMap<String, RadiusEntry>,
+	bad_patterns: Vec<BadPattern>,
 }
 
 static RADIUS_TABLE: OnceLock<RadiusTable> = OnceLock::new();
@@ -74,8 +134,9 @@ fn parse_radius_table() -> RadiusTable {
 	let text = load_atmtypenumbers_text();
 	let mut radii: HashMap<String, RadiusEntry> = HashMap::new();
 	let mut patterns: Vec<PatternEntry> = Vec::new();
+	let mut bad_patterns: Vec<BadPattern> = Vec::new();
 
-	for raw_line in text.lines() {
+	for (line_index, raw_line) in text.lines().enumerate() {
 		let line_no_comment = raw_line
 			.split_once('#')
 			.map(|(before, _)| before)
@@ -124,16 +185,47 @@ fn parse_radius_table() -> RadiusTable {
 		}
 		let residue_regex = format!("^{}$", residue_pattern);
 		let atom_regex = format!("^{}$", atom_pattern);
-		if let (Ok(r_res), Ok(r_atom)) = (Regex::new(&residue_regex), Regex::new(&atom_regex)) {
-			patterns.push(PatternEntry {
+		match (Regex::new(&residue_regex), Regex::new(&atom_regex)) {
+			(Ok(r_res), Ok(r_atom)) => patterns.push(PatternEntry {
 				residue: r_res,
 				atom: r_atom,
 				key: tokens[2].to_string(),
-			});
+			}),
+			(res, atom) => {
+				let error = res.err().or(atom.err()).map(|e| e.to_string()).unwrap_or_default();
+				bad_patterns.push(BadPattern {
+					line: line_index + 1,
+					pattern: format!("{} {}", tokens[0], tokens[1]),
+					error,
+				});
+			}
 		}
 	}
 
-	RadiusTable { patterns, radii }
+	RadiusTable { patterns, radii, bad_patterns }
+}
+
+/// Radius table patterns that failed to compile, in table order (empty for a clean table).
+/// Loading skips them, so atoms they should match get the 0.01 default; callers decide whether to warn.
+pub fn radius_table_bad_patterns() -> &'static [BadPattern] {
+	&radius_table().bad_patterns
+}
+
+/// Error with `InvalidData` listing every radius table pattern that failed to compile.
+/// Bad patterns are otherwise skipped silently; see `radius_table_bad_patterns`.
+pub fn check_radius_table() -> io::Result<()> {
+	let bad = radius_table_bad_patterns();
+	if bad.is_empty() {
+		return Ok(());
+	}
+	let details: Vec<String> = bad
+		.iter()
+		.map(|b| format!("line {}: '{}' ({})", b.line, b.pattern, b.error))
+		.collect();
+	Err(io::Error::new(
+		io::ErrorKind::InvalidData,
+		format!("radius table has {} bad pattern(s): {}", bad.len(), details.join("; ")),
+	))
 }
 
 fn radius_table() -> &'static RadiusTable {
@@ -163,16 +255,18 @@ fn get_field(line: &str, start: usize, len: usize) -> &str {
 	&line[start..end]
 }
 
-fn normalize_atom_name(raw: &str) -> String {
+/// Map a raw 4-column atom name to its radius-table name; hydrogens collapse to "H".
+/// For names starting "HG" in column 13 the element column wins: "HG" keeps the name (mercury, even for
+/// HG1/HG2), H/D makes it hydrogen. Only with a blank element column does a following digit
+/// (HG11, HG21, ... branch hydrogens) mark a hydrogen.
+fn normalize_atom_name(raw: &str, element: &str) -> String {
 	let mut chars: Vec<char> = raw.chars().collect();
-	while chars.len() < 2 {
+	while chars.len() < 3 {
 		chars.push(' ');
 	}
-	if chars.len() > 2 {
-		chars.truncate(2);
-	}
 	let c0 = chars[0];
 	let c1 = chars[1];
+	let c2 = chars[2];
 	let c0_upper = c0.to_ascii_uppercase();
 	let c1_upper = c1.to_ascii_uppercase();
 	let first_blank_digit = c0 == ' ' || c0.is_ascii_digit();
@@ -185,6 +279,12 @@ fn normalize_atom_name(raw: &str) -> String {
 	if first_h && !second_g {
 		return "H".to_string();
 	}
+	let element_upper = to_upper(trim(element));
+	let hydrogen_element = element_upper == "H" || element_upper == "D";
+	let digit_hydrogen = element_upper.is_empty() && c2.is_ascii_digit();
+	if first_h && second_g && element_upper != "HG" && (hydrogen_element || digit_hydrogen) {
+		return "H".to_string();
+	}
 	let mut trimmed = raw.trim().to_string();
 	trimmed.retain(|c| c != ' ');
 	trimmed
@@ -197,18 +297,23 @@ struct AtomRecord {
 	z: String,
 	residue: String,
 	atom: String,
+	raw_atom: String,  // Trimmed atom name exactly as written (columns 13-16), before normalization
 	resnum: String,
+	insertion_code: String,  // Residue insertion code (column 27), empty when blank
 	chain: String,
 	element: String,
 	record: String,
+	occupancy: String,
+	b_factor: String,
+	alt_loc: char,  // ' ' when the record has no alternate location
+	segid: String,  // Segment ID (columns 73-76), used by CHARMM files in place of the chain
+	line: usize,  // 1-based input line number, for error messages
 }
 
 #[derive(Debug, Clone)]
 struct ResidueInfo {
 	name: String,
-	#[allow(dead_code)]
 	chain: String,
-	#[allow(dead_code)]
 	resnum: String,
 	atom_count: usize,
 	polymer_flag: bool,
@@ -289,22 +394,28 @@ fn is_ion(info: &ResidueInfo) -> bool {
 	false
 }
 
+/// Chain ID, or the segment ID when the chain column is blank (CHARMM and PDBx-converted files).
+fn chain_or_segid(atom: &AtomRecord) -> &str {
+	if atom.chain.is_empty() { &atom.segid } else { &atom.chain }
+}
+
 fn make_residue_key(atom: &AtomRecord) -> String {
 	format!(
-		"{}|{}|{}",
+		"{}|{}|{}|{}",
 		to_upper(&atom.chain),
+		to_upper(&atom.segid),
 		atom.resnum,
 		to_upper(&atom.residue)
 	)
 }
 
-fn classify_residues(atoms: &[AtomRecord]) -> HashMap<String, ResidueInfo> {
+fn classify_residues(atoms: &[AtomRecord], filters: &Filters) -> HashMap<String, ResidueInfo> {
 	let mut residues: HashMap<String, ResidueInfo> = HashMap::new();
 	for atom in atoms {
 		let key = make_residue_key(atom);
 		let entry = residues.entry(key).or_insert_with(|| ResidueInfo {
 			name: atom.residue.clone(),
-			chain: atom.chain.clone(),
+			chain: chain_or_segid(atom).to_string(),
 			resnum: atom.resnum.clone(),
 			atom_count: 0,
 			polymer_flag: false,
@@ -337,10 +448,38 @@ fn classify_residues(atoms: &[AtomRecord]) -> HashMap<String, ResidueInfo> {
 		info.is_nucleic = is_nucleic(&info.name);
 		info.is_ion = is_ion(info);
 		info.is_ligand = !info.polymer_flag && !info.is_water && !info.is_ion;
+		apply_forced_class(info, filters);
 	}
 	residues
 }
 
+/// Replace a residue's classification when its code is listed in one of the `force_*` filters.
+fn apply_forced_class(info: &mut ResidueInfo, filters: &Filters) {
+	let upper = to_upper(&info.name);
+	let listed = |codes: &[String]| codes.iter().any(|code| to_upper(code.trim()) == upper);
+	let forced = [
+		(&filters.force_water, [true, false, false]),
+		(&filters.force_ion, [false, true, false]),
+		(&filters.force_ligand, [false, false, true]),
+	];
+	for (codes, [water, ion, ligand]) in forced {
+		if listed(codes) {
+			info.is_water = water;
+			info.is_ion = ion;
+			info.is_ligand = ligand;
+			info.polymer_flag = false;
+			info.is_amino = false;
+			info.is_nucleic = false;
+		}
+	}
+	if listed(&filters.force_polymer) {
+		info.is_water = false;
+		info.is_ion = false;
+		info.is_ligand = false;
+		info.polymer_flag = true;
+	}
+}
+
 fn should_filter(info: &ResidueInfo, filters: &Filters) -> bool {
 	if filters.exclude_water && info.is_water {
 		return true;
@@ -351,7 +490,7 @@ fn should_filter(info: &ResidueInfo, filters: &Filters) -> bool {
 	if filters.exclude_ligands && info.is_ligand {
 		return true;
 	}
-	if filters.exclude_hetatm && info.hetatm_only {
+	if filters.exclude_hetatm && info.hetatm_only && !info.polymer_flag {
 		return true;
 	}
 	if filters.exclude_nucleic_acids && info.is_nucleic {
@@ -363,7 +502,11 @@ fn should_filter(info: &ResidueInfo, filters: &Filters) -> bool {
 	false
 }
 
-fn radius_for(residue: &str, atom: &str, use_united: bool) -> f32 {
+/// Radius (angstroms) from the embedded atmtypenumbers table for `atom` in `residue`: the united-atom
+/// radius if `use_united`, else the explicit-hydrogen one; 0.01 when no pattern matches.
+/// Names must already be normalized the way the PDB parser stores them: `residue` trimmed (e.g. "ALA"),
+/// `atom` with its column spaces removed (" CA " is "CA") and every hydrogen name given as "H".
+pub fn lookup_radius(residue: &str, atom: &str, use_united: bool) -> f32 {
 	let table = radius_table();
 	for entry in &table.patterns {
 		if entry.residue.is_match(residue) && entry.atom.is_match(atom) {
@@ -375,7 +518,8 @@ fn radius_for(residue: &str, atom: &str, use_united: bool) -> f32 {
 	0.01
 }
 
-fn radius_text_for(residue: &str, atom: &str, use_united: bool) -> String {
+/// `lookup_radius` as the table's original text (e.g. "1.61"), so output keeps the table's precision.
+pub fn lookup_radius_text(residue: &str, atom: &str, use_united: bool) -> String {
 	let table = radius_table();
 	for entry in &table.patterns {
 		if entry.residue.is_match(residue) && entry.atom.is_match(atom) {
@@ -391,11 +535,39 @@ fn radius_text_for(residue: &str, atom: &str, use_united: bool) -> String {
 	"0.01".to_string()
 }
 
+/// Radius text from the column selected by `source`, if that column holds a positive number.
+fn column_radius_text(rec: &AtomRecord, source: RadiusSource) -> Option<&str> {
+	let text = match source {
+		RadiusSource::Table => return None,
+		RadiusSource::BFactorColumn => rec.b_factor.as_str(),
+		RadiusSource::OccupancyColumn => rec.occupancy.as_str(),
+	};
+	match text.parse::<f32>() {
+		Ok(r) if r > 0.0 => Some(text),
+		_ => None,
+	}
+}
+
 fn parse_float(s: &str) -> f32 {
 	s.trim().parse::<f32>().unwrap_or(0.0)
 }
 
+/// Parsed x, y, z of a record; `InvalidData` naming the input line if a coordinate is not a number.
+fn record_coordinates(rec: &AtomRecord) -> io::Result<[f32; 3]> {
+	let mut xyz = [0.0f32; 3];
+	for (value, (axis, text)) in xyz.iter_mut().zip([("x", &rec.x), ("y", &rec.y), ("z", &rec.z)]) {
+		*value = text.trim().parse::<f32>().map_err(|_| {
+			io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("line {}: {} {} coordinate '{}' is not a number", rec.line, rec.record, axis, text.trim()),
+			)
+		})?;
+	}
+	Ok(xyz)
+}
+
 /// Parse a PDB file into atoms with radii according to the embedded atmtypenumbers table.
+/// Every record of the file is held in memory at once; see `load_atoms_streaming` for huge files.
 pub fn load_atoms_from_pdb_path(path: &str, opts: &PdbOptions) -> io::Result<Vec<Atom>> {
 	let file = File::open(path)?;
 	let reader = BufReader::new(file);
@@ -406,30 +578,236 @@ pub fn load_atoms_from_reader<R: BufRead>(
 	reader: R,
 	opts: &PdbOptions,
 ) -> io::Result<Vec<Atom>> {
+	if opts.strict_radius_table {
+		check_radius_table()?;
+	}
 	let atoms = parse_atom_records(reader)?;
+	atoms_from_records(atoms, opts)
+}
+
+/// Parse a multi-model PDB file (MODEL/ENDMDL blocks) into one atom list per model.
+pub fn load_models_from_pdb_path(path: &str, opts: &PdbOptions) -> io::Result<Vec<Vec<Atom>>> {
+	let file = File::open(path)?;
+	let reader = BufReader::new(file);
+	load_models_from_reader(reader, opts)
+}
+
+/// One atom list per MODEL block; a file without MODEL records yields a single model.
+/// Residues are classified and filtered within each model.
+pub fn load_models_from_reader<R: BufRead>(
+	reader: R,
+	opts: &PdbOptions,
+) -> io::Result<Vec<Vec<Atom>>> {
+	if opts.strict_radius_table {
+		check_radius_table()?;
+	}
+	load_atoms_streaming(reader, opts)?.collect()
+}
+
+/// How one residue was classified, and whether `opts.filters` keeps it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ResidueClassification {
+	pub chain: String,  // Chain ID, or the segment ID when the chain column is blank
+	pub resnum: String,
+	pub name: String,
+	pub is_water: bool,
+	pub is_ion: bool,
+	pub is_ligand: bool,
+	pub is_amino: bool,
+	pub is_nucleic: bool,
+	pub hetatm_only: bool,  // Every record of the residue is HETATM (what `exclude_hetatm` tests)
+	pub kept: bool,
+}
+
+/// Per-residue classification of a PDB file, in order of first appearance, for auditing the filters.
+pub fn classify_pdb(path: &str, opts: &PdbOptions) -> io::Result<Vec<ResidueClassification>> {
+	let file = File::open(path)?;
+	let reader = BufReader::new(file);
+	classify_pdb_reader(reader, opts)
+}
+
+pub fn classify_pdb_reader<R: BufRead>(reader: R, opts: &PdbOptions) -> io::Result<Vec<ResidueClassification>> {
+	let atoms = select_alt_locs(parse_atom_records(reader)?, opts.alt_loc);
+	let residue_map = classify_residues(&atoms, &opts.filters);
+	let mut seen: HashSet<String> = HashSet::new();
+	let mut out: Vec<ResidueClassification> = Vec::new();
+	for rec in atoms.iter() {
+		let key = make_residue_key(rec);
+		if !seen.insert(key.clone()) {
+			continue;
+		}
+		if let Some(info) = residue_map.get(&key) {
+			out.push(ResidueClassification {
+				chain: info.chain.clone(),
+				resnum: info.resnum.clone(),
+				name: info.name.clone(),
+				is_water: info.is_water,
+				is_ion: info.is_ion,
+				is_ligand: info.is_ligand,
+				is_amino: info.is_amino,
+				is_nucleic: info.is_nucleic,
+				hetatm_only: info.hetatm_only,
+				kept: !should_filter(info, &opts.filters),
+			});
+		}
+	}
+	Ok(out)
+}
+
+/// Streaming counterpart of `load_models_from_pdb_path`.
+pub fn load_atoms_streaming_from_pdb_path(path: &str, opts: &PdbOptions) -> io::Result<ModelStream<BufReader<File>>> {
+	let file = File::open(path)?;
+	load_atoms_streaming(BufReader::new(file), opts)
+}
 
-	let residue_map = classify_residues(&atoms);
+/// Iterate over the models of a PDB stream one at a time; only the current model's records are held
+/// in memory, so peak use is one model rather than the whole file. Residues are classified and
+/// filtered within each model, exactly as in `load_models_from_reader`.
+pub fn load_atoms_streaming<R: BufRead>(reader: R, opts: &PdbOptions) -> io::Result<ModelStream<R>> {
+	if opts.strict_radius_table {
+		check_radius_table()?;
+	}
+	Ok(ModelStream {
+		lines: reader.lines(),
+		opts: opts.clone(),
+		line_number: 0,
+		finished: false,
+	})
+}
+
+/// Iterator returned by `load_atoms_streaming`, yielding each model's atoms (or the first read error).
+pub struct ModelStream<R> {
+	lines: io::Lines<R>,
+	opts: PdbOptions,
+	line_number: usize,
+	finished: bool,
+}
+
+impl<R: BufRead> Iterator for ModelStream<R> {
+	type Item = io::Result<Vec<Atom>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.finished {
+			return None;
+		}
+		match next_model_records(&mut self.lines, &mut self.line_number) {
+			Ok(Some(records)) => Some(atoms_from_records(records, &self.opts)),
+			Ok(None) => {
+				self.finished = true;
+				None
+			}
+			Err(e) => {
+				self.finished = true;
+				Some(Err(e))
+			}
+		}
+	}
+}
+
+/// Filtered atoms of one model; `InvalidData` naming the line if a kept record has a coordinate that is
+/// not a number, as in `write_xyzr_from_reader`.
+fn atoms_from_records(atoms: Vec<AtomRecord>, opts: &PdbOptions) -> io::Result<Vec<Atom>> {
+	let atoms = select_alt_locs(atoms, opts.alt_loc);
+	let residue_map = classify_residues(&atoms, &opts.filters);
+	// Residue ids are assigned from 1 in order of first appearance in the file.
+	let mut residue_ids: HashMap<String, u32> = HashMap::new();
 	let mut out: Vec<Atom> = Vec::new();
 	for rec in atoms {
 		let key = make_residue_key(&rec);
+		let next_id = residue_ids.len() as u32 + 1;
+		let residue_id = *residue_ids.entry(key.clone()).or_insert(next_id);
 		if let Some(info) = residue_map.get(&key) {
 			if should_filter(info, &opts.filters) {
 				continue;
 			}
 		}
-		let radius = radius_for(&rec.residue, &rec.atom, opts.use_united);
+		let radius = match column_radius_text(&rec, opts.radius_source) {
+			Some(text) => parse_float(text),
+			None => lookup_radius(&rec.residue, &rec.atom, opts.use_united_for(&rec.residue)),
+		};
+		let [x, y, z] = record_coordinates(&rec)?;
 		out.push(Atom {
-			x: parse_float(&rec.x),
-			y: parse_float(&rec.y),
-			z: parse_float(&rec.z),
+			x,
+			y,
+			z,
 			radius,
+			residue_id,
 		});
 	}
+	Ok(out)
+}
+
+/// Parse a PQR file (as written by PDB2PQR): coordinates and the per-atom radius column are used
+/// directly, bypassing the radius table and residue filters; charges are ignored.
+pub fn load_atoms_from_pqr_path(path: &str) -> io::Result<Vec<Atom>> {
+	let file = File::open(path)?;
+	let reader = BufReader::new(file);
+	load_atoms_from_pqr_reader(reader).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))
+}
 
+/// PQR atoms from a reader. ATOM/HETATM lines are read free-format (the last five fields are
+/// x, y, z, charge, radius, whatever precedes them); lines whose coordinates run together fall back
+/// to the PDB coordinate columns with charge and radius after them. Residue ids are assigned from 1
+/// in order of first appearance, as for PDB input. Errors with `InvalidData` on an unreadable line
+/// or a negative or non-finite radius.
+pub fn load_atoms_from_pqr_reader<R: BufRead>(reader: R) -> io::Result<Vec<Atom>> {
+	let mut residue_ids: HashMap<String, u32> = HashMap::new();
+	let mut out: Vec<Atom> = Vec::new();
+	for (line_no, line_res) in reader.lines().enumerate() {
+		let line = line_res?;
+		// First token, less any serial number run into it ("HETATM12345")
+		let first = line.split_whitespace().next().unwrap_or("").to_ascii_uppercase();
+		let record = first.trim_end_matches(|c: char| c.is_ascii_digit());
+		if record != "ATOM" && record != "HETATM" {
+			continue;
+		}
+		let bad = |what: &str| {
+			io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_no + 1, what))
+		};
+		let (key, values) = parse_pqr_line(&line).ok_or_else(|| bad("cannot read PQR coordinates, charge, and radius"))?;
+		let [x, y, z, _charge, radius] = values;
+		if !(radius.is_finite() && radius >= 0.0) {
+			return Err(bad(&format!("radius {} is not a finite non-negative number", radius)));
+		}
+		let next_id = residue_ids.len() as u32 + 1;
+		let residue_id = *residue_ids.entry(key).or_insert(next_id);
+		out.push(Atom { x, y, z, radius, residue_id });
+	}
 	Ok(out)
 }
 
-/// Write XYZR lines to writer. Returns number of atoms written.
+/// Residue key and [x, y, z, charge, radius] from one PQR ATOM/HETATM line.
+fn parse_pqr_line(line: &str) -> Option<(String, [f32; 5])> {
+	let fields: Vec<&str> = line.split_whitespace().collect();
+	// ATOM serial name resname [chain] resnum x y z charge radius
+	if fields.len() >= 10 {
+		let tail = &fields[fields.len() - 5..];
+		let parsed: Vec<f32> = tail.iter().filter_map(|f| f.parse::<f32>().ok()).collect();
+		if let Ok(values) = <[f32; 5]>::try_from(parsed) {
+			return Some((fields[3..fields.len() - 5].join(" "), values));
+		}
+	}
+	// Fixed columns: coordinates in 31-54 as in PDB, charge and radius after them.
+	let coords: Vec<f32> = [30, 38, 46]
+		.iter()
+		.filter_map(|&start| trim(get_field(line, start, 8)).parse::<f32>().ok())
+		.collect();
+	let rest: Vec<f32> = line
+		.get(54..)?
+		.split_whitespace()
+		.take(2)
+		.filter_map(|f| f.parse::<f32>().ok())
+		.collect();
+	if coords.len() != 3 || rest.len() != 2 {
+		return None;
+	}
+	let key = trim(get_field(line, 17, 10)).to_string();
+	Some((key, [coords[0], coords[1], coords[2], rest[0], rest[1]]))
+}
+
+/// Write XYZR lines (coordinates as `{:8.3}`) to writer. Returns number of atoms written.
+/// Errors with `InvalidData`, naming the line, if a kept record has a coordinate that is not a number.
 pub fn write_xyzr_from_path(path: &str, opts: &PdbOptions, mut w: impl Write) -> io::Result<usize> {
 	let file = File::open(path)?;
 	let reader = BufReader::new(file);
@@ -441,8 +819,11 @@ pub fn write_xyzr_from_reader<R: BufRead>(
 	opts: &PdbOptions,
 	mut w: impl Write,
 ) -> io::Result<usize> {
-	let atoms = parse_atom_records(reader)?;
-	let residue_map = classify_residues(&atoms);
+	if opts.strict_radius_table {
+		check_radius_table()?;
+	}
+	let atoms = select_alt_locs(parse_atom_records(reader)?, opts.alt_loc);
+	let residue_map = classify_residues(&atoms, &opts.filters);
 	let mut count = 0usize;
 	for rec in atoms {
 		let key = make_residue_key(&rec);
@@ -451,56 +832,160 @@ pub fn write_xyzr_from_reader<R: BufRead>(
 				continue;
 			}
 		}
-		let radius_text = radius_text_for(&rec.residue, &rec.atom, opts.use_united);
-		writeln!(
-			w,
-			"{:>8} {:>8} {:>8} {}",
-			rec.x.trim(),
-			rec.y.trim(),
-			rec.z.trim(),
-			radius_text
-		)?;
+		let radius_text = match column_radius_text(&rec, opts.radius_source) {
+			Some(text) => text.to_string(),
+			None => lookup_radius_text(&rec.residue, &rec.atom, opts.use_united_for(&rec.residue)),
+		};
+		// Reformat parsed values so columns stay numeric and aligned whatever the input field looked like.
+		let [x, y, z] = record_coordinates(&rec)?;
+		writeln!(w, "{:8.3} {:8.3} {:8.3} {}", x, y, z, radius_text)?;
 		count += 1;
 	}
 	Ok(count)
 }
 
+/// Drop the alternate conformers `policy` does not keep, preserving file order.
+fn select_alt_locs(records: Vec<AtomRecord>, policy: AltLocPolicy) -> Vec<AtomRecord> {
+	if policy == AltLocPolicy::All {
+		return records;
+	}
+	// Best (occupancy, altLoc, record index) per atom identity.
+	let mut best: HashMap<String, (f32, char, usize)> = HashMap::new();
+	for (idx, rec) in records.iter().enumerate() {
+		if rec.alt_loc == ' ' {
+			continue;
+		}
+		let occupancy = rec.occupancy.parse::<f32>().unwrap_or(0.0);
+		let key = atom_site_key(rec);
+		let better = |&(occ, alt, _): &(f32, char, usize)| occupancy > occ || (occupancy == occ && rec.alt_loc < alt);
+		if best.get(&key).is_none_or(better) {
+			best.insert(key, (occupancy, rec.alt_loc, idx));
+		}
+	}
+	let keep: HashSet<usize> = best.values().map(|&(_, _, idx)| idx).collect();
+	records
+		.into_iter()
+		.enumerate()
+		.filter(|(idx, rec)| rec.alt_loc == ' ' || keep.contains(idx))
+		.map(|(_, rec)| rec)
+		.collect()
+}
+
+/// Atom identity shared by its alternate conformers: chain, segment, residue number, insertion code, and
+/// atom name as written (not the residue name, which may differ between conformers, and not the normalized
+/// name, which maps every hydrogen to "H").
+fn atom_site_key(atom: &AtomRecord) -> String {
+	format!(
+		"{}|{}|{}|{}|{}",
+		to_upper(&atom.chain),
+		to_upper(&atom.segid),
+		atom.resnum,
+		atom.insertion_code,
+		atom.raw_atom
+	)
+}
+
 fn parse_atom_records<R: BufRead>(reader: R) -> io::Result<Vec<AtomRecord>> {
 	let mut atoms: Vec<AtomRecord> = Vec::new();
-	for line_res in reader.lines() {
+	for (index, line_res) in reader.lines().enumerate() {
 		let line = line_res?;
-		if line.len() < 6 {
-			continue;
+		if let Some(rec) = parse_record_line(&line, index + 1) {
+			atoms.push(rec);
 		}
-		let record = trim(&line[..6]).to_ascii_uppercase();
-		if record != "ATOM" && record != "HETATM" {
+	}
+	Ok(atoms)
+}
+
+/// Read ATOM/HETATM records up to the end of the next non-empty MODEL block (or end of input).
+/// Records outside any MODEL block form their own model. `None` once the input is exhausted.
+/// `line_number` counts the lines read so far across calls.
+fn next_model_records<R: BufRead>(
+	lines: &mut io::Lines<R>,
+	line_number: &mut usize,
+) -> io::Result<Option<Vec<AtomRecord>>> {
+	let mut current: Vec<AtomRecord> = Vec::new();
+	for line_res in lines.by_ref() {
+		let line = line_res?;
+		*line_number += 1;
+		let record = trim(get_field(&line, 0, 6)).to_ascii_uppercase();
+		if record == "MODEL" || record == "ENDMDL" {
+			if !current.is_empty() {
+				return Ok(Some(current));
+			}
 			continue;
 		}
-		let raw_x = get_field(&line, 30, 8);
-		let raw_y = get_field(&line, 38, 8);
-		let raw_z = get_field(&line, 46, 8);
-		if trim(raw_x).is_empty() || trim(raw_y).is_empty() || trim(raw_z).is_empty() {
-			continue;
+		if let Some(rec) = parse_record_line(&line, *line_number) {
+			current.push(rec);
 		}
-		let residue = trim(get_field(&line, 17, 3)).to_string();
-		let atom_name = normalize_atom_name(get_field(&line, 12, 4));
-		let resnum = trim(get_field(&line, 22, 4)).to_string();
-		let chain = trim(get_field(&line, 21, 1)).to_string();
-		let mut element = trim(get_field(&line, 76, 2)).to_string();
-		if element.is_empty() && !atom_name.is_empty() {
-			element = atom_name.chars().next().unwrap_or(' ').to_ascii_uppercase().to_string();
+	}
+	Ok(if current.is_empty() { None } else { Some(current) })
+}
+
+/// Element for a record with a blank element column. Two-letter elements (from `ION_ELEMENTS`) are
+/// left-justified in column 13 ("ZN  ", "FE  "), unlike one-letter ones (" CA " is an alpha carbon,
+/// "CA  " calcium); a name that equals its residue name (ZN in ZN) also counts. Otherwise the
+/// first letter of the name is used.
+fn guess_element(raw_name: &str, atom_name: &str, residue: &str) -> String {
+	let upper = to_upper(atom_name);
+	let starts_column_13 = raw_name.chars().next().is_some_and(|c| c.is_ascii_alphabetic());
+	let letters: String = upper
+		.trim_start_matches(|c: char| c.is_ascii_digit())
+		.chars()
+		.take_while(|c| c.is_ascii_alphabetic())
+		.collect();
+	if letters.len() >= 2 {
+		let two = &letters[..2];
+		let names_residue = upper == to_upper(residue);
+		if ION_ELEMENTS.contains(&two) && (starts_column_13 || names_residue) {
+			return two.to_string();
 		}
-		atoms.push(AtomRecord {
-			x: raw_x.to_string(),
-			y: raw_y.to_string(),
-			z: raw_z.to_string(),
-			residue,
-			atom: atom_name,
-			resnum,
-			chain,
-			element,
-			record,
-		});
 	}
-	Ok(atoms)
+	letters
+		.chars()
+		.next()
+		.unwrap_or_else(|| upper.chars().next().unwrap_or(' '))
+		.to_string()
+}
+
+fn parse_record_line(line: &str, line_number: usize) -> Option<AtomRecord> {
+	if line.len() < 6 {
+		return None;
+	}
+	let record = trim(&line[..6]).to_ascii_uppercase();
+	if record != "ATOM" && record != "HETATM" {
+		return None;
+	}
+	let raw_x = get_field(line, 30, 8);
+	let raw_y = get_field(line, 38, 8);
+	let raw_z = get_field(line, 46, 8);
+	if trim(raw_x).is_empty() || trim(raw_y).is_empty() || trim(raw_z).is_empty() {
+		return None;
+	}
+	let residue = trim(get_field(line, 17, 3)).to_string();
+	let mut element = trim(get_field(line, 76, 2)).to_string();
+	let raw_name = get_field(line, 12, 4);
+	let atom_name = normalize_atom_name(raw_name, &element);
+	let resnum = trim(get_field(line, 22, 4)).to_string();
+	let chain = trim(get_field(line, 21, 1)).to_string();
+	if element.is_empty() && !atom_name.is_empty() {
+		element = guess_element(raw_name, &atom_name, &residue);
+	}
+	Some(AtomRecord {
+		x: raw_x.to_string(),
+		y: raw_y.to_string(),
+		z: raw_z.to_string(),
+		residue,
+		atom: atom_name,
+		raw_atom: trim(raw_name).to_string(),
+		resnum,
+		insertion_code: trim(get_field(line, 26, 1)).to_string(),
+		chain,
+		element,
+		record,
+		occupancy: trim(get_field(line, 54, 6)).to_string(),
+		b_factor: trim(get_field(line, 60, 6)).to_string(),
+		alt_loc: get_field(line, 16, 1).chars().next().unwrap_or(' '),
+		segid: trim(get_field(line, 72, 4)).to_string(),
+		line: line_number,
+	})
 }
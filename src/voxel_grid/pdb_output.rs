@@ -1,14 +1,62 @@
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
+use crate::voxel_grid::analyze::face_neighbors;
 use crate::voxel_grid::grid::Grid3D;
 use crate::voxel_grid::surface_area::classify_edge_point;
 
+/// Surface points per chain block in `write_surface_pdb_blocked`; each block is closed with a TER record.
+pub const SURFACE_POINTS_PER_BLOCK: usize = 1000;
+
+/// Chain IDs cycled through by successive blocks.
+const CHAIN_IDS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
 /// Write surface voxels to a PDB file.
 /// A voxel is considered surface if any of its 6 face neighbors is empty or out of bounds.
+/// Every point is written as residue `RES A   1` with consecutive serials, matching the C++ output
+/// byte for byte; use `write_surface_pdb_blocked` for TER-separated chains.
+/// Points are written in voxel index order (k slowest, then j, i fastest). That order is part of the
+/// output format and is kept stable across versions, so surface files from two builds can be diffed.
 pub fn write_surface_pdb(grid: &Grid3D, path: &str) -> std::io::Result<()> {
+	write_surface_pdb_where(grid, path, false, false, |typ| typ != 0)
+}
+
+/// Same points and order as `write_surface_pdb`, split into blocks of `SURFACE_POINTS_PER_BLOCK`,
+/// each with its own chain ID (cycling A-Z), residue numbers restarting at 1, and a closing TER record,
+/// so viewers do not try to bond the whole surface; serials wrap at 99999.
+pub fn write_surface_pdb_blocked(grid: &Grid3D, path: &str) -> std::io::Result<()> {
+	write_surface_pdb_where(grid, path, false, true, |typ| typ != 0)
+}
+
+/// Same points and order as `write_surface_pdb`, with standard PDB coordinate columns plus the
+/// `classify_edge_point` type (1-9) in the B-factor column (61-66) and the number of exposed faces
+/// (empty or out-of-grid face neighbors, 1-6) in the occupancy column (55-60), e.g. for `spectrum b` in PyMOL.
+pub fn write_surface_pdb_annotated(grid: &Grid3D, path: &str) -> std::io::Result<()> {
+	write_surface_pdb_where(grid, path, true, false, |typ| typ != 0)
+}
+
+/// Same as `write_surface_pdb`, but only voxels whose `classify_edge_point` type is in `types` are written
+/// (e.g. `&[9]` for isolated single voxels). Type 0 (interior) is honored if listed.
+pub fn write_surface_pdb_filtered(grid: &Grid3D, path: &str, types: &[usize]) -> std::io::Result<()> {
+	write_surface_pdb_where(grid, path, false, false, |typ| types.contains(&typ))
+}
+
+/// Write filled voxels whose edge type passes `keep`; `annotated` selects the `write_surface_pdb_annotated`
+/// line format and `blocked` the `write_surface_pdb_blocked` chains and TER records.
+/// Any parallel classification added here must collect and sort by voxel index before writing,
+/// to keep the documented output order.
+fn write_surface_pdb_where<F: Fn(usize) -> bool>(
+	grid: &Grid3D,
+	path: &str,
+	annotated: bool,
+	blocked: bool,
+	keep: F,
+) -> std::io::Result<()> {
 	let mut file = BufWriter::new(File::create(path)?);
 	let mut serial = 1usize;
+	let mut points = 0usize;
+	let mut chain = CHAIN_IDS[0] as char;
+	let mut resnum = 1usize;
     for k in 0..grid.len_k {
         for j in 0..grid.len_j {
             for i in 0..grid.len_i {
@@ -16,21 +64,54 @@ pub fn write_surface_pdb(grid: &Grid3D, path: &str) -> std::io::Result<()> {
                     continue;
                 }
                 let idx = i + j * grid.len_i + k * grid.len_i * grid.len_j;
-                if classify_edge_point(grid, idx) == 0 {
+                let typ = classify_edge_point(grid, idx);
+                if !keep(typ) {
+                    continue;
+                }
+                let (x, y, z) = grid.voxel_to_physical(i, j, k);
+                if blocked {
+                    let block = points / SURFACE_POINTS_PER_BLOCK;
+                    chain = CHAIN_IDS[block % CHAIN_IDS.len()] as char;
+                    resnum = points % SURFACE_POINTS_PER_BLOCK + 1;
+                }
+                if annotated {
+                    let exposed = face_neighbors(grid, idx)
+                        .iter()
+                        .filter(|n| n.is_none_or(|n| !grid.get_voxel_index(n)))
+                        .count();
+                    writeln!(
+                        file,
+                        "ATOM  {:5}  C   RES {}{:4}    {:8.3}{:8.3}{:8.3}{:6.2}{:6.2}",
+                        serial, chain, resnum, x, y, z, exposed as f32, typ as f32
+                    )?;
+                } else {
+                    writeln!(
+                        file,
+                        "ATOM  {:5}  C   RES {}{:4}    {:8.3} {:8.3} {:8.3}",
+                        serial, chain, resnum, x, y, z
+                    )?;
+                }
+                points += 1;
+                if !blocked {
+                    serial += 1;
                     continue;
                 }
-                let x = i as f32 * grid.grid_size + grid.x_shift;
-                let y = j as f32 * grid.grid_size + grid.y_shift;
-                let z = k as f32 * grid.grid_size + grid.z_shift;
-                writeln!(
-                    file,
-                    "ATOM  {:5}  C   RES A   1    {:8.3} {:8.3} {:8.3}",
-                    serial, x, y, z
-                )?;
-                serial += 1;
+                serial = next_serial(serial);
+                if points.is_multiple_of(SURFACE_POINTS_PER_BLOCK) {
+                    writeln!(file, "TER   {:5}      RES {}{:4}", serial, chain, resnum)?;
+                    serial = next_serial(serial);
+                }
             }
         }
     }
+    if blocked && !points.is_multiple_of(SURFACE_POINTS_PER_BLOCK) {
+        writeln!(file, "TER   {:5}      RES {}{:4}", serial, chain, resnum)?;
+    }
     writeln!(file, "END")?;
     Ok(())
 }
+
+/// Next atom serial, wrapping to 1 after the 5-column maximum.
+fn next_serial(serial: usize) -> usize {
+	if serial >= 99999 { 1 } else { serial + 1 }
+}
@@ -0,0 +1,48 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::surface_area::classify_edge_point;
+
+/// Write surface voxels as an ASCII PLY point cloud (one vertex per surface voxel center).
+/// Surface voxels are chosen as in `write_surface_pdb`. With `normals`, each vertex also carries the
+/// unit outward normal from `Grid3D::surface_normal` as nx/ny/nz.
+pub fn write_surface_ply(grid: &Grid3D, path: &str, normals: bool) -> std::io::Result<()> {
+	let mut points: Vec<(usize, usize, usize)> = Vec::new();
+	for k in 0..grid.len_k {
+		for j in 0..grid.len_j {
+			for i in 0..grid.len_i {
+				let idx = grid.ijk_to_index(i, j, k);
+				if grid.get_voxel_index(idx) && classify_edge_point(grid, idx) != 0 {
+					points.push((i, j, k));
+				}
+			}
+		}
+	}
+
+	let mut file = BufWriter::new(File::create(path)?);
+	writeln!(file, "ply")?;
+	writeln!(file, "format ascii 1.0")?;
+	writeln!(file, "comment vossvolvox surface voxels, grid {:.3} A", grid.grid_size)?;
+	writeln!(file, "element vertex {}", points.len())?;
+	writeln!(file, "property float x")?;
+	writeln!(file, "property float y")?;
+	writeln!(file, "property float z")?;
+	if normals {
+		writeln!(file, "property float nx")?;
+		writeln!(file, "property float ny")?;
+		writeln!(file, "property float nz")?;
+	}
+	writeln!(file, "end_header")?;
+
+	for &(i, j, k) in points.iter() {
+		let (x, y, z) = grid.voxel_to_physical(i, j, k);
+		if normals {
+			let n = grid.surface_normal(i, j, k);
+			writeln!(file, "{:.3} {:.3} {:.3} {:.4} {:.4} {:.4}", x, y, z, n[0], n[1], n[2])?;
+		} else {
+			writeln!(file, "{:.3} {:.3} {:.3}", x, y, z)?;
+		}
+	}
+	file.flush()
+}
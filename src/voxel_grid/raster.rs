@@ -1,39 +1,244 @@
+use std::io;
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 use std::thread;
 
 use bitvec::vec::BitVec;
-use bitvec::slice::BitSlice;
 
+use crate::voxel_grid::geometry::GridParams;
 use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::parallel::worker_threads;
+use crate::voxel_grid::storage::{Voxels, VoxelsMut};
 
 /// Minimal atom representation for rasterization
-#[derive(Debug, Clone)]
+/// Build with `Atom::new` or `Atom { .., ..Atom::default() }` so new optional fields keep call sites compiling.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Atom {
 	pub x: f32,
 	pub y: f32,
 	pub z: f32,
 	pub radius: f32,
+	pub residue_id: u32,  // Residue this atom belongs to (0 = unassigned)
+}
+
+impl Atom {
+	/// Atom at (x, y, z) angstroms with the given radius; other fields take their defaults
+	pub fn new(x: f32, y: f32, z: f32, radius: f32) -> Self {
+		Self {
+			x,
+			y,
+			z,
+			radius,
+			..Self::default()
+		}
+	}
+}
+
+/// How `Grid3D::fill_accessible_supersampled` turns sub-voxel coverage into filled voxels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Downsample {
+	/// Fill a voxel when more than half of its sub-voxel centers lie inside a sphere. Shrinks convex
+	/// shapes, so it is no more accurate in volume than a plain single-center fill.
+	Majority,
+	/// Fill voxels in order of coverage until the filled count matches the fine-grid volume; ties at the
+	/// cut-off are spread evenly in index order. The default: it tracks volume more closely than
+	/// majority vote or a plain fill.
+	#[default]
+	VolumePreserving,
+}
+
+/// Volume and surface area from `Grid3D::measure`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Measurement {
+	pub volume_a3: f64,  // Filled volume in cubic angstroms
+	pub surface_a2: f64,  // Edge-weighted surface area estimate in square angstroms
+	pub filled_voxels: usize,
 }
 
 impl Grid3D {
 	/// Fill the grid with spheres (accessible volume) in parallel.
 	/// Atoms are specified in physical units; `probe` is added to each atom radius.
+	/// Thread count follows `parallel::worker_threads()`.
 	/// Returns the number of filled voxels.
 	pub fn fill_accessible_parallel(&mut self, atoms: &[Atom], probe: f32) -> usize {
+		self.fill_accessible_with_threads(atoms, probe, worker_threads())
+	}
+
+	/// Fill the solvent-excluded volume: accessible fill, then contract by `probe` when `probe > 0`.
+	/// Returns the number of filled voxels.
+	pub fn fill_excluded(&mut self, atoms: &[Atom], probe: f32) -> usize {
+		let filled = self.fill_accessible_parallel(atoms, probe);
+		if probe > 0.0 {
+			self.contract_exclusion_parallel(probe)
+		} else {
+			filled
+		}
+	}
+
+	/// Accessible fill, then volume and surface area of the result, in one call.
+	/// Same as `fill_accessible_parallel`, `filled_volume`, and `estimate_surface_area_with_edges` in that order.
+	pub fn measure(&mut self, atoms: &[Atom], probe: f32) -> Measurement {
+		let filled_voxels = self.fill_accessible_parallel(atoms, probe);
+		Measurement {
+			volume_a3: self.filled_volume(),
+			surface_a2: self.estimate_surface_area_with_edges().0,
+			filled_voxels,
+		}
+	}
+
+	/// Accessible fill on a grid sized for `atoms` at spacing `grid`, computed on a `factor`-times finer grid
+	/// and downsampled with `mode`; see `fill_accessible_supersampled`. `None` if the atoms cannot size a grid.
+	pub fn rasterize_supersampled(atoms: &[Atom], probe: f32, grid: f32, factor: usize, mode: Downsample) -> Option<Grid3D> {
+		let params = GridParams::from_atoms(atoms, probe, grid)?;
+		let mut coarse = params.build_grid();
+		coarse.fill_accessible_supersampled(atoms, probe, factor, mode);
+		Some(coarse)
+	}
+
+	/// Accessible fill with `factor`^3 sub-voxels per voxel, each voxel filled from how many sub-voxel
+	/// centers lie inside a sphere as `mode` decides. The temporary fine grid needs `factor`^3 times this
+	/// grid's memory. `factor` 0 or 1 is a plain `fill_accessible_parallel`. Returns the number of filled voxels.
+	pub fn fill_accessible_supersampled(&mut self, atoms: &[Atom], probe: f32, factor: usize, mode: Downsample) -> usize {
+		if factor <= 1 {
+			return self.fill_accessible_parallel(atoms, probe);
+		}
+		let spacing = self.spacing();
+		let step = spacing.map(|size| size / factor as f32);
+		// Sub-voxel centers sit symmetrically inside each voxel.
+		let offset = |axis: usize| 0.5 * step[axis] - 0.5 * spacing[axis];
+		let mut fine = Grid3D::new_anisotropic(self.len_i * factor, self.len_j * factor, self.len_k * factor, step)
+			.with_shifts(self.x_shift + offset(0), self.y_shift + offset(1), self.z_shift + offset(2));
+		fine.fill_accessible_parallel(atoms, probe);
+
+		let cube = factor * factor * factor;
+		let mut coverage = vec![0u32; self.total_voxels];
+		let mut histogram = vec![0usize; cube + 1];
+		let mut inside_total = 0usize;
+		for k in 0..self.len_k {
+			for j in 0..self.len_j {
+				for i in 0..self.len_i {
+					let mut inside = 0usize;
+					for dk in 0..factor {
+						for dj in 0..factor {
+							for di in 0..factor {
+								inside += fine.get_voxel_ijk(i * factor + di, j * factor + dj, k * factor + dk) as usize;
+							}
+						}
+					}
+					coverage[self.ijk_to_index(i, j, k)] = inside as u32;
+					histogram[inside] += 1;
+					inside_total += inside;
+				}
+			}
+		}
+		drop(fine);
+
+		if mode == Downsample::Majority {
+			let mut bits = BitVec::repeat(false, self.total_voxels);
+			let mut filled = 0usize;
+			for (idx, &inside) in coverage.iter().enumerate() {
+				if 2 * inside as usize > cube {
+					bits.set(idx, true);
+					filled += 1;
+				}
+			}
+			self.set_data(bits, filled);
+			self.last_probe = Some(probe);
+			return filled;
+		}
+
+		// Highest coverage first until the fine-grid volume is reached; `threshold` is the partial level.
+		let target = (inside_total + cube / 2) / cube;
+		let mut threshold = 0usize;
+		let mut at_threshold = 0usize;
+		let mut taken = 0usize;
+		for level in (1..=cube).rev() {
+			if taken + histogram[level] <= target {
+				taken += histogram[level];
+				continue;
+			}
+			threshold = level;
+			at_threshold = target - taken;
+			break;
+		}
+
+		let mut bits = BitVec::repeat(false, self.total_voxels);
+		let mut filled = 0usize;
+		let mut seen = 0usize;
+		for (idx, &inside) in coverage.iter().enumerate() {
+			let inside = inside as usize;
+			let take = if inside == 0 {
+				false
+			} else if inside > threshold {
+				true
+			} else if inside == threshold {
+				// Pick `at_threshold` of the tied voxels, evenly spaced.
+				let n = histogram[threshold];
+				let pick = (seen + 1) * at_threshold / n > seen * at_threshold / n;
+				seen += 1;
+				pick
+			} else {
+				false
+			};
+			if take {
+				bits.set(idx, true);
+				filled += 1;
+			}
+		}
+		self.set_data(bits, filled);
+		self.last_probe = Some(probe);
+		filled
+	}
+
+	/// Same as `fill_accessible_parallel` with an explicit thread count.
+	/// Workers only ever store 1 into a cell, so `data` and the filled count are the same
+	/// for any thread count or chunk split.
+	pub fn fill_accessible_with_threads(&mut self, atoms: &[Atom], probe: f32, threads: usize) -> usize {
+		let probes = vec![probe; atoms.len()];
+		let filled = self.fill_spheres_with_threads(atoms, &probes, threads);
+		self.last_probe = Some(probe);
+		filled
+	}
+
+	/// Accessible fill with a probe per atom: `probes[i]` is added to `atoms[i].radius`.
+	/// Errors with `InvalidInput` if the slices differ in length. The grid is not marked with a single
+	/// probe, so a following `contract_exclusion_parallel` will warn.
+	pub fn fill_accessible_variable(&mut self, atoms: &[Atom], probes: &[f32]) -> io::Result<usize> {
+		if atoms.len() != probes.len() {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("{} atoms but {} probe values", atoms.len(), probes.len()),
+			));
+		}
+		let filled = self.fill_spheres_with_threads(atoms, probes, worker_threads());
+		self.last_probe = None;
+		Ok(filled)
+	}
+
+	/// Shared parallel sphere fill; `probes` has one entry per atom.
+	/// Tiled grids are filled tile by tile in place, so no buffer spans the whole grid.
+	fn fill_spheres_with_threads(&mut self, atoms: &[Atom], probes: &[f32], threads: usize) -> usize {
 		if atoms.is_empty() {
-			self.data.fill(false);
+			self.zero_grid();
 			return 0;
 		}
+		if self.tile_count() > 1 {
+			return self.fill_spheres_in_place(atoms, probes, true, threads);
+		}
 
 		let total_voxels = self.total_voxels;
 		let grid_size = self.grid_size;
+		let [sx, sy, sz] = self.axis_scale();
 		let len_i = self.len_i as isize;
 		let len_j = self.len_j as isize;
 		let len_k = self.len_k as isize;
-		let x_shift = self.x_shift;
-		let y_shift = self.y_shift;
-		let z_shift = self.z_shift;
+
+		// Atom centers in voxel coordinates.
+		let centers: Vec<(f32, f32, f32)> = atoms
+			.iter()
+			.map(|atom| self.physical_to_voxel(atom.x, atom.y, atom.z))
+			.collect();
 
 		// Thread-friendly backing buffer; each cell is 0/1.
 		let backing: Arc<Vec<AtomicU8>> = Arc::new(
@@ -42,16 +247,18 @@ impl Grid3D {
 				.collect(),
 		);
 
-		let threads = thread::available_parallelism()
-			.map(|n| n.get())
-			.unwrap_or(1);
+		let threads = threads.max(1);
 		let chunk_size = (atoms.len() + threads - 1) / threads;
 
 		thread::scope(|scope| {
-			for atom_chunk in atoms.chunks(chunk_size) {
+			let chunks = atoms
+				.chunks(chunk_size)
+				.zip(centers.chunks(chunk_size))
+				.zip(probes.chunks(chunk_size));
+			for ((atom_chunk, center_chunk), probe_chunk) in chunks {
 				let data = Arc::clone(&backing);
 				scope.spawn(move || {
-					for atom in atom_chunk {
+					for ((atom, &(xk, yk, zk)), &probe) in atom_chunk.iter().zip(center_chunk).zip(probe_chunk) {
 						let effective_r = atom.radius + probe;
 						let r_grid = effective_r / grid_size;
 						if r_grid <= 0.0 {
@@ -59,26 +266,19 @@ impl Grid3D {
 						}
 						let cutoff = r_grid * r_grid;
 
-						let xk = (atom.x - x_shift) / grid_size;
-						let yk = (atom.y - y_shift) / grid_size;
-						let zk = (atom.z - z_shift) / grid_size;
-
 						// Bounding box in voxel coordinates, clamped to grid.
-						let imin = ((xk - r_grid - 1.0).floor() as isize).clamp(0, len_i - 1);
-						let jmin = ((yk - r_grid - 1.0).floor() as isize).clamp(0, len_j - 1);
-						let kmin = ((zk - r_grid - 1.0).floor() as isize).clamp(0, len_k - 1);
-						let imax = ((xk + r_grid + 1.0).ceil() as isize).clamp(0, len_i - 1);
-						let jmax = ((yk + r_grid + 1.0).ceil() as isize).clamp(0, len_j - 1);
-						let kmax = ((zk + r_grid + 1.0).ceil() as isize).clamp(0, len_k - 1);
+						let (imin, imax) = voxel_range(xk, r_grid / sx, len_i);
+						let (jmin, jmax) = voxel_range(yk, r_grid / sy, len_j);
+						let (kmin, kmax) = voxel_range(zk, r_grid / sz, len_k);
 
 						for i in imin..=imax {
-							let dx = xk - i as f32;
+							let dx = (xk - i as f32) * sx;
 							let dx2 = dx * dx;
 							for j in jmin..=jmax {
-								let dy = yk - j as f32;
+								let dy = (yk - j as f32) * sy;
 								let dy2 = dy * dy;
 								for k in kmin..=kmax {
-									let dz = zk - k as f32;
+									let dz = (zk - k as f32) * sz;
 									let dist2 = dx2 + dy2 + dz * dz;
 									if dist2 < cutoff {
 										let idx = i as usize + j as usize * (len_i as usize) + k as usize * (len_i as usize) * (len_j as usize);
@@ -102,63 +302,254 @@ impl Grid3D {
 			}
 			bits.push(v);
 		}
-		self.data = bits;
+		self.set_data(bits, filled);
+		filled
+	}
+
+	/// Accessible fill that writes into the existing `data` allocation instead of building new buffers.
+	/// Each thread owns contiguous runs of k-slabs (cut at tile edges on tiled grids), so no shared scratch
+	/// buffer is needed; the result is identical to `fill_accessible_parallel`. Meant for re-rasterizing many
+	/// structures into same-sized grids.
+	pub fn fill_accessible_into(&mut self, atoms: &[Atom], probe: f32) -> usize {
+		let probes = vec![probe; atoms.len()];
+		let filled = self.fill_spheres_in_place(atoms, &probes, true, worker_threads());
+		self.last_probe = Some(probe);
 		filled
 	}
 
+	/// Accessible fill OR-ed into the current occupancy: voxels already filled stay filled, e.g. to add a
+	/// ligand loaded separately to a protein grid. Equal to one fill of both atom sets with the same probe.
+	/// Contraction afterwards checks the probe only if every fill so far used `probe`.
+	/// Returns the number of filled voxels.
+	pub fn fill_accessible_or(&mut self, atoms: &[Atom], probe: f32) -> usize {
+		let same_probe = self.last_probe == Some(probe) || self.voxels().not_any();
+		let probes = vec![probe; atoms.len()];
+		let filled = self.fill_spheres_in_place(atoms, &probes, false, worker_threads());
+		self.last_probe = if same_probe { Some(probe) } else { None };
+		filled
+	}
+
+	/// In-place sphere fill behind `fill_accessible_into` (`clear`), `fill_accessible_or` (keep existing
+	/// voxels), and the tiled-grid case of `fill_spheres_with_threads`; `probes` has one entry per atom.
+	/// Leaves `last_probe` to the caller.
+	fn fill_spheres_in_place(&mut self, atoms: &[Atom], probes: &[f32], clear: bool, threads: usize) -> usize {
+		let total_voxels = self.total_voxels;
+		if clear {
+			self.voxels_mut().fill(false);
+		}
+		if atoms.is_empty() || total_voxels == 0 {
+			let filled = if clear { 0 } else { self.count_filled() };
+			self.refresh_filled_cache(filled);
+			return filled;
+		}
+
+		let grid_size = self.grid_size;
+		let [sx, sy, sz] = self.axis_scale();
+		let len_i = self.len_i as isize;
+		let len_j = self.len_j as isize;
+		let len_k = self.len_k as isize;
+		let slab = self.len_i * self.len_j;
+
+		// Atom centers in voxel coordinates.
+		let centers: Vec<(f32, f32, f32)> = atoms
+			.iter()
+			.map(|atom| self.physical_to_voxel(atom.x, atom.y, atom.z))
+			.collect();
+
+		let threads = threads.max(1);
+		let slabs_per_thread = self.len_k.div_ceil(threads);
+
+		// Runs of whole k-slabs, cut at tile boundaries on tiled grids; each piece knows its first flat index.
+		let piece_bits = slabs_per_thread * slab;
+		let mut pieces: Vec<_> = self
+			.voxels_mut()
+			.buffers_mut()
+			.into_iter()
+			.flat_map(|(base, bits)| {
+				bits.chunks_mut(piece_bits)
+					.enumerate()
+					.map(move |(n, chunk)| (base + n * piece_bits, chunk))
+			})
+			.collect();
+		let pieces_per_thread = pieces.len().div_ceil(threads);
+
+		thread::scope(|scope| {
+			for group in pieces.chunks_mut(pieces_per_thread) {
+				let centers = &centers;
+				scope.spawn(move || {
+					for (base, chunk) in group.iter_mut() {
+						let base = *base;
+						let k_first = (base / slab) as isize;
+						let k_last = ((base + chunk.len() - 1) / slab) as isize;
+						for ((atom, &(xk, yk, zk)), &probe) in atoms.iter().zip(centers.iter()).zip(probes) {
+							let r_grid = (atom.radius + probe) / grid_size;
+							if r_grid <= 0.0 {
+								continue;
+							}
+							let (kmin, kmax) = voxel_range(zk, r_grid / sz, len_k);
+							let kmin = kmin.max(k_first);
+							let kmax = kmax.min(k_last);
+							if kmin > kmax {
+								continue;
+							}
+							let cutoff = r_grid * r_grid;
+							let (imin, imax) = voxel_range(xk, r_grid / sx, len_i);
+							let (jmin, jmax) = voxel_range(yk, r_grid / sy, len_j);
+
+							for k in kmin..=kmax {
+								let dz = (zk - k as f32) * sz;
+								for j in jmin..=jmax {
+									let dy = (yk - j as f32) * sy;
+									for i in imin..=imax {
+										let dx = (xk - i as f32) * sx;
+										// Same operand order as `fill_accessible_with_threads` for identical rounding.
+										let dist2 = dx * dx + dy * dy + dz * dz;
+										if dist2 < cutoff {
+											let flat = i as usize + j as usize * (len_i as usize) + k as usize * slab;
+											if let Some(mut bit) = flat.checked_sub(base).and_then(|local| chunk.get_mut(local)) {
+												*bit = true;
+											}
+										}
+									}
+								}
+							}
+						}
+					}
+				});
+			}
+		});
+
+		let filled = self.count_filled();
+		self.refresh_filled_cache(filled);
+		filled
+	}
+
+	/// Accessible fill that also records, per voxel, the `residue_id` of the closest atom center
+	/// among the spheres covering it (ties go to the earlier atom). Runs on one thread.
+	/// `data` ends up the same as after `fill_accessible_parallel`; returns per-voxel labels (0 = empty).
+	pub fn fill_accessible_labeled(&mut self, atoms: &[Atom], probe: f32) -> Vec<u32> {
+		let total_voxels = self.total_voxels;
+		let len_i = self.len_i as isize;
+		let len_j = self.len_j as isize;
+		let len_k = self.len_k as isize;
+		let mut labels = vec![0u32; total_voxels];
+		let mut best_dist2 = vec![f32::INFINITY; total_voxels];
+		let mut bits = BitVec::repeat(false, total_voxels);
+		let [sx, sy, sz] = self.axis_scale();
+
+		for atom in atoms {
+			let r_grid = (atom.radius + probe) / self.grid_size;
+			if r_grid <= 0.0 {
+				continue;
+			}
+			let cutoff = r_grid * r_grid;
+			let (xk, yk, zk) = self.physical_to_voxel(atom.x, atom.y, atom.z);
+			let (imin, imax) = voxel_range(xk, r_grid / sx, len_i);
+			let (jmin, jmax) = voxel_range(yk, r_grid / sy, len_j);
+			let (kmin, kmax) = voxel_range(zk, r_grid / sz, len_k);
+
+			for i in imin..=imax {
+				let dx = (xk - i as f32) * sx;
+				let dx2 = dx * dx;
+				for j in jmin..=jmax {
+					let dy = (yk - j as f32) * sy;
+					let dy2 = dy * dy;
+					for k in kmin..=kmax {
+						let dz = (zk - k as f32) * sz;
+						let dist2 = dx2 + dy2 + dz * dz;
+						if dist2 < cutoff {
+							let idx = self.ijk_to_index(i as usize, j as usize, k as usize);
+							bits.set(idx, true);
+							if dist2 < best_dist2[idx] {
+								best_dist2[idx] = dist2;
+								labels[idx] = atom.residue_id;
+							}
+						}
+					}
+				}
+			}
+		}
+
+		let filled = bits.count_ones();
+		self.set_data(bits, filled);
+		self.last_probe = Some(probe);
+		labels
+	}
+
 	/// Contract accessible grid into excluded grid (trun_ExcludeGrid_fast analogue).
 	/// Uses the current grid occupancy as the accessible input and writes the contracted
 	/// grid back into `self.data`. Returns the number of filled voxels after contraction.
+	/// Thread count follows `parallel::worker_threads()`.
 	pub fn contract_exclusion_parallel(&mut self, probe: f32) -> usize {
+		self.contract_exclusion_with_threads(probe, worker_threads())
+	}
+
+	/// Same as `contract_exclusion_parallel` with an explicit thread count.
+	/// Workers read only the accessible input and only ever store 0 into the output,
+	/// so the result is the same for any thread count or chunk split.
+	/// Warns on stderr if the grid was not produced by an accessible fill with the same `probe`.
+	pub fn contract_exclusion_with_threads(&mut self, probe: f32, threads: usize) -> usize {
+		self.check_accessible_probe(probe);
+		self.contract_by_radius_with_threads(probe, threads)
+	}
+
+	/// Contract the filled region by `radius` angstroms, which need not match the probe of the fill:
+	/// every voxel closer than `radius` to an empty voxel is cleared. With an accessible fill at probe `p`,
+	/// `radius == p` is the standard excluded volume; a different `radius` gives e.g. a rolling-probe surface
+	/// with its own re-entrant radius. Unlike `contract_exclusion_parallel`, no fill-probe warning is printed.
+	/// Thread count follows `parallel::worker_threads()`. Returns the number of filled voxels.
+	pub fn contract_by_radius(&mut self, radius: f32) -> usize {
+		self.contract_by_radius_with_threads(radius, worker_threads())
+	}
+
+	/// Same as `contract_by_radius` with an explicit thread count.
+	/// The output is a copy of the input bits with the same tiling, cleared piece by piece, so peak memory is
+	/// two grids' worth of bits plus the list of boundary voxels.
+	pub fn contract_by_radius_with_threads(&mut self, radius: f32, threads: usize) -> usize {
 		let total_voxels = self.total_voxels;
 		let len_i = self.len_i;
 		let len_j = self.len_j;
 		let len_k = self.len_k;
-		let acc: &BitSlice = self.data.as_bitslice();
-
-		// Output buffer initialized from the accessible grid.
-		let backing: Arc<Vec<AtomicU8>> = Arc::new(
-			(0..total_voxels)
-				.map(|idx| {
-					if acc[idx] {
-						AtomicU8::new(1)
-					} else {
-						AtomicU8::new(0)
-					}
-				})
-				.collect(),
-		);
-
-		let radius_units = probe / self.grid_size;
-		let offsets = compute_offsets(radius_units, len_i, len_j);
-		let offsets_arc = Arc::new(offsets);
+		let radius_units = radius / self.grid_size;
+		let offsets = compute_offsets(radius_units, self.axis_scale(), len_i, len_j);
+		let reach = offsets.iter().map(|offset| offset.unsigned_abs()).max().unwrap_or(0);
+		let threads = threads.max(1);
+		let seeds = contraction_seeds(self.voxels(), threads, len_i, len_j, len_k);
 
-		let threads = thread::available_parallelism()
-			.map(|n| n.get())
-			.unwrap_or(1);
-		let chunk = (total_voxels + threads - 1) / threads;
+		// Each thread owns runs of the output (cut at tile edges) and applies every seed within `reach` of them.
+		let mut output_data = self.data.clone();
+		let mut output_tiles = self.tiles.clone();
+		let piece_bits = total_voxels.div_ceil(threads).max(1);
+		let mut pieces: Vec<_> = VoxelsMut::new(&mut output_data, &mut output_tiles)
+			.buffers_mut()
+			.into_iter()
+			.flat_map(|(base, bits)| {
+				bits.chunks_mut(piece_bits)
+					.enumerate()
+					.map(move |(n, chunk)| (base + n * piece_bits, chunk))
+			})
+			.collect();
+		let pieces_per_thread = pieces.len().div_ceil(threads).max(1);
 
 		thread::scope(|scope| {
-			for (chunk_idx, range_start) in (0..total_voxels).step_by(chunk).enumerate() {
-				let data = Arc::clone(&backing);
-				let acc_ref = acc;
-				let offsets_ref = Arc::clone(&offsets_arc);
-				let start = range_start;
-				let end = ((chunk_idx + 1) * chunk).min(total_voxels);
+			for group in pieces.chunks_mut(pieces_per_thread) {
+				let seeds = &seeds;
+				let offsets = &offsets;
 				scope.spawn(move || {
-					for idx in start..end {
-						// Skip if occupied in accessible grid.
-						if acc_ref[idx] {
-							continue;
-						}
-						if !has_filled_neighbor(idx, acc_ref, len_i, len_j, len_k) {
-							continue;
-						}
-						let center = idx as isize;
-						for &offset in offsets_ref.iter() {
-							let neighbor = center + offset;
-							if neighbor >= 0 && (neighbor as usize) < total_voxels {
-								data[neighbor as usize].store(0, Ordering::Relaxed);
+					for (base, chunk) in group.iter_mut() {
+						let base = *base;
+						let start = base.saturating_sub(reach);
+						let end = base + chunk.len() + reach;
+						let first = seeds.partition_point(|&seed| seed < start);
+						for &seed in seeds[first..].iter().take_while(|&&seed| seed < end) {
+							for &offset in offsets.iter() {
+								let neighbor = seed as isize + offset;
+								if neighbor < 0 {
+									continue;
+								}
+								if let Some(mut bit) = (neighbor as usize).checked_sub(base).and_then(|local| chunk.get_mut(local)) {
+									*bit = false;
+								}
 							}
 						}
 					}
@@ -166,21 +557,71 @@ impl Grid3D {
 			}
 		});
 
-		let mut filled = 0usize;
-		let mut bits = BitVec::with_capacity(total_voxels);
-		for cell in backing.iter() {
-			let v = cell.load(Ordering::Relaxed) != 0;
-			if v {
-				filled += 1;
-			}
-			bits.push(v);
-		}
-		self.data = bits;
+		self.data = output_data;
+		self.tiles = output_tiles;
+		let filled = self.count_filled();
+		self.refresh_filled_cache(filled);
+		self.last_probe = None;
 		filled
 	}
+
+	/// Warn if `data` does not look like an accessible grid filled with `probe`.
+	fn check_accessible_probe(&self, probe: f32) {
+		match self.last_probe {
+			None => eprintln!(
+				"Warning: contracting a grid that was not filled by an accessible fill; \
+				 call fill_accessible_parallel(atoms, {:.2}) first", probe
+			),
+			Some(filled_probe) if (filled_probe - probe).abs() > f32::EPSILON => eprintln!(
+				"Warning: contracting with probe {:.2} but the grid was filled with probe {:.2}; \
+				 use contract_by_radius to contract by a different radius on purpose",
+				probe, filled_probe
+			),
+			Some(_) => {}
+		}
+	}
+}
+
+/// Empty voxels with a filled face neighbor, in increasing order: the centers the contraction sphere is
+/// stamped around. Each thread scans one contiguous index range.
+fn contraction_seeds(acc: Voxels<'_>, threads: usize, len_i: usize, len_j: usize, len_k: usize) -> Vec<usize> {
+	let total_voxels = acc.len();
+	let chunk = total_voxels.div_ceil(threads).max(1);
+	thread::scope(|scope| {
+		let workers: Vec<_> = (0..total_voxels)
+			.step_by(chunk)
+			.map(|start| {
+				let end = (start + chunk).min(total_voxels);
+				scope.spawn(move || {
+					(start..end)
+						.filter(|&idx| !acc.get(idx) && has_filled_neighbor(idx, acc, len_i, len_j, len_k))
+						.collect::<Vec<usize>>()
+				})
+			})
+			.collect();
+		workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+	})
+}
+
+/// Extra voxels added on each side of an atom's bounding box before the per-voxel `< cutoff` test.
+/// `floor`/`ceil` of `center +/- r_grid` already cover the exact sphere, so with exact arithmetic 0 would do;
+/// the margin absorbs f32 rounding in the center and radius (computed from shifts and grid size in a
+/// different order than the distance test), which can put a boundary voxel one index past the box.
+/// Extra voxels only cost distance tests, never wrong fills. Widen it if a boundary voxel goes missing.
+pub const RASTER_MARGIN_VOXELS: f32 = 1.0;
+
+/// Inclusive voxel index range covering `center +/- r_grid` along one axis plus `RASTER_MARGIN_VOXELS`,
+/// clamped to the grid.
+pub(crate) fn voxel_range(center: f32, r_grid: f32, len: isize) -> (isize, isize) {
+	let lo = ((center - r_grid - RASTER_MARGIN_VOXELS).floor() as isize).clamp(0, len - 1);
+	let hi = ((center + r_grid + RASTER_MARGIN_VOXELS).ceil() as isize).clamp(0, len - 1);
+	(lo, hi)
 }
 
-fn has_filled_neighbor(idx: usize, acc: &BitSlice, len_i: usize, len_j: usize, len_k: usize) -> bool {
+/// True if a face neighbor of `idx` is filled. Only such empty voxels need to stamp the contraction sphere,
+/// for any radius: the empty voxel nearest to a filled voxel always has a filled face neighbor (one step toward
+/// that filled voxel is in the grid and strictly closer, so it cannot be empty), so diagonal-only contacts add nothing.
+fn has_filled_neighbor(idx: usize, acc: Voxels<'_>, len_i: usize, len_j: usize, len_k: usize) -> bool {
 	let stride_j = len_i;
 	let stride_k = len_i * len_j;
 	let i = idx % len_i;
@@ -188,42 +629,49 @@ fn has_filled_neighbor(idx: usize, acc: &BitSlice, len_i: usize, len_j: usize, l
 	let k = idx / stride_k;
 
 	// +/- i
-	if i > 0 && acc[idx - 1] {
+	if i > 0 && acc.get(idx - 1) {
 		return true;
 	}
-	if i + 1 < len_i && acc[idx + 1] {
+	if i + 1 < len_i && acc.get(idx + 1) {
 		return true;
 	}
 	// +/- j
-	if j > 0 && acc[idx - stride_j] {
+	if j > 0 && acc.get(idx - stride_j) {
 		return true;
 	}
-	if j + 1 < len_j && acc[idx + stride_j] {
+	if j + 1 < len_j && acc.get(idx + stride_j) {
 		return true;
 	}
 	// +/- k
-	if k > 0 && acc[idx - stride_k] {
+	if k > 0 && acc.get(idx - stride_k) {
 		return true;
 	}
-	if k + 1 < len_k && acc[idx + stride_k] {
+	if k + 1 < len_k && acc.get(idx + stride_k) {
 		return true;
 	}
 	false
 }
 
-fn compute_offsets(radius_units: f32, len_i: usize, len_j: usize) -> Vec<isize> {
+/// Flat-index offsets within `radius_units` (in `grid_size` units); `scale` is `Grid3D::axis_scale`.
+fn compute_offsets(radius_units: f32, scale: [f32; 3], len_i: usize, len_j: usize) -> Vec<isize> {
 	let mut offsets = Vec::new();
 	if radius_units <= 0.0 {
 		return offsets;
 	}
 	let cutoff = radius_units * radius_units;
-	let max_r = radius_units.ceil() as isize;
+	let [sx, sy, sz] = scale;
+	let max_i = (radius_units / sx).ceil() as isize;
+	let max_j = (radius_units / sy).ceil() as isize;
+	let max_k = (radius_units / sz).ceil() as isize;
 	let stride_j = len_i as isize;
 	let stride_k = (len_i * len_j) as isize;
-	for di in -max_r..=max_r {
-		for dj in -max_r..=max_r {
-			for dk in -max_r..=max_r {
-				let dist2 = (di * di + dj * dj + dk * dk) as f32;
+	for di in -max_i..=max_i {
+		let dx = di as f32 * sx;
+		for dj in -max_j..=max_j {
+			let dy = dj as f32 * sy;
+			for dk in -max_k..=max_k {
+				let dz = dk as f32 * sz;
+				let dist2 = dx * dx + dy * dy + dz * dz;
 				if dist2 < cutoff {
 					offsets.push(di + dj * stride_j + dk * stride_k);
 				}
@@ -0,0 +1,74 @@
+use crate::voxel_grid::grid::Grid3D;
+
+impl Grid3D {
+	/// First filled voxel hit by the ray `origin + t * direction` (t >= 0, physical coordinates), or `None`.
+	/// Voxel (i, j, k) is the box of one voxel spacing centered on `voxel_to_physical(i, j, k)`; a ray
+	/// starting inside a filled voxel returns that voxel. Traversal is a 3D DDA (Amanatides-Woo), so it
+	/// visits only the voxels the ray passes through. A zero or non-finite direction returns `None`.
+	pub fn raycast(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<(usize, usize, usize)> {
+		if self.total_voxels == 0 || origin.iter().chain(direction.iter()).any(|v| !v.is_finite()) {
+			return None;
+		}
+		if direction.iter().all(|&v| v == 0.0) {
+			return None;
+		}
+
+		// Work in voxel units shifted by half a voxel, so voxel n spans [n, n + 1) on each axis.
+		let spacing = self.spacing();
+		let shifts = [self.x_shift, self.y_shift, self.z_shift];
+		let lens = [self.len_i, self.len_j, self.len_k];
+		let start: [f64; 3] = [0, 1, 2].map(|a| ((origin[a] - shifts[a]) / spacing[a]) as f64 + 0.5);
+		let dir: [f64; 3] = [0, 1, 2].map(|a| (direction[a] / spacing[a]) as f64);
+
+		// Clip the ray to the grid box.
+		let mut t_enter = 0.0f64;
+		let mut t_exit = f64::INFINITY;
+		for a in 0..3 {
+			let len = lens[a] as f64;
+			if dir[a] == 0.0 {
+				if start[a] < 0.0 || start[a] >= len {
+					return None;
+				}
+				continue;
+			}
+			let t0 = (0.0 - start[a]) / dir[a];
+			let t1 = (len - start[a]) / dir[a];
+			t_enter = t_enter.max(t0.min(t1));
+			t_exit = t_exit.min(t0.max(t1));
+		}
+		if t_enter > t_exit {
+			return None;
+		}
+
+		let entry: [f64; 3] = [0, 1, 2].map(|a| start[a] + dir[a] * t_enter);
+		let mut cell: [isize; 3] =
+			[0, 1, 2].map(|a| (entry[a].floor() as isize).clamp(0, lens[a] as isize - 1));
+		let step: [isize; 3] = dir.map(|d| if d > 0.0 { 1 } else if d < 0.0 { -1 } else { 0 });
+		let t_delta: [f64; 3] = dir.map(|d| if d == 0.0 { f64::INFINITY } else { 1.0 / d.abs() });
+		// Ray parameter at which the next boundary along each axis is crossed.
+		let mut t_max: [f64; 3] = [0, 1, 2].map(|a| match step[a] {
+			1 => t_enter + (cell[a] as f64 + 1.0 - entry[a]) / dir[a],
+			-1 => t_enter + (cell[a] as f64 - entry[a]) / dir[a],
+			_ => f64::INFINITY,
+		});
+
+		loop {
+			let (i, j, k) = (cell[0] as usize, cell[1] as usize, cell[2] as usize);
+			if self.get_voxel_ijk(i, j, k) {
+				return Some((i, j, k));
+			}
+			let axis = if t_max[0] <= t_max[1] && t_max[0] <= t_max[2] {
+				0
+			} else if t_max[1] <= t_max[2] {
+				1
+			} else {
+				2
+			};
+			cell[axis] += step[axis];
+			if cell[axis] < 0 || cell[axis] >= lens[axis] as isize {
+				return None;
+			}
+			t_max[axis] += t_delta[axis];
+		}
+	}
+}
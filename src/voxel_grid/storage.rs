@@ -0,0 +1,193 @@
+use bitvec::slice::BitSlice;
+use bitvec::vec::BitVec;
+
+/// Fixed-size tiles of `tile_bits` voxels each (the last tile may be shorter), so no single allocation
+/// has to hold every voxel. A tiled `Grid3D` keeps these in its private `tiles` field and leaves the
+/// public `data` field empty; both layouts use the same flat index, i fastest and k slowest.
+#[derive(Clone)]
+pub(crate) struct Tiles {
+	tiles: Vec<BitVec>,
+	tile_bits: usize,
+	len: usize,
+}
+
+/// Storage for `len` voxels as the (`data`, `tiles`) field pair of `Grid3D`: `bits` whole when
+/// `tile_bits == 0` (or a tile at least as large as the grid), otherwise split into tiles.
+pub(crate) fn split_bits(bits: BitVec, tile_bits: usize) -> (BitVec, Option<Tiles>) {
+	let len = bits.len();
+	if tile_bits == 0 || tile_bits >= len {
+		return (bits, None);
+	}
+	let tiles = bits
+		.chunks(tile_bits)
+		.map(|chunk| {
+			let mut tile = BitVec::with_capacity(chunk.len());
+			tile.extend_from_bitslice(chunk);
+			tile
+		})
+		.collect();
+	(BitVec::new(), Some(Tiles { tiles, tile_bits, len }))
+}
+
+/// `len` empty voxels laid out as in `split_bits`, without building a whole-grid `BitVec` first
+pub(crate) fn empty_bits(len: usize, tile_bits: usize) -> (BitVec, Option<Tiles>) {
+	if tile_bits == 0 || tile_bits >= len {
+		return (BitVec::repeat(false, len), None);
+	}
+	let tiles = (0..len)
+		.step_by(tile_bits)
+		.map(|start| BitVec::repeat(false, tile_bits.min(len - start)))
+		.collect();
+	(BitVec::new(), Some(Tiles { tiles, tile_bits, len }))
+}
+
+/// Read-only view of a grid's voxels, whole or tiled.
+#[derive(Clone, Copy)]
+pub(crate) enum Voxels<'a> {
+	Monolithic(&'a BitVec),
+	Tiled(&'a Tiles),
+}
+
+impl<'a> Voxels<'a> {
+	pub(crate) fn new(data: &'a BitVec, tiles: &'a Option<Tiles>) -> Self {
+		match tiles {
+			Some(tiles) => Self::Tiled(tiles),
+			None => Self::Monolithic(data),
+		}
+	}
+
+	/// Voxels per tile, or 0 for monolithic storage
+	pub(crate) fn tile_bits(self) -> usize {
+		match self {
+			Self::Monolithic(_) => 0,
+			Self::Tiled(tiles) => tiles.tile_bits,
+		}
+	}
+
+	/// Number of separate allocations
+	pub(crate) fn tile_count(self) -> usize {
+		match self {
+			Self::Monolithic(_) => 1,
+			Self::Tiled(tiles) => tiles.tiles.len(),
+		}
+	}
+
+	/// Bits allocated across all tiles
+	pub(crate) fn capacity(self) -> usize {
+		match self {
+			Self::Monolithic(bits) => bits.capacity(),
+			Self::Tiled(tiles) => tiles.tiles.iter().map(|tile| tile.capacity()).sum(),
+		}
+	}
+
+	/// Number of voxels
+	pub(crate) fn len(self) -> usize {
+		match self {
+			Self::Monolithic(bits) => bits.len(),
+			Self::Tiled(tiles) => tiles.len,
+		}
+	}
+
+	/// Voxel at flat `index` (panics if out of bounds)
+	#[inline]
+	pub(crate) fn get(self, index: usize) -> bool {
+		match self {
+			Self::Monolithic(bits) => bits[index],
+			Self::Tiled(Tiles { tiles, tile_bits, len }) => {
+				assert!(index < *len, "voxel index {} out of range for {} voxels", index, len);
+				tiles[index / tile_bits][index % tile_bits]
+			}
+		}
+	}
+
+	pub(crate) fn count_ones(self) -> usize {
+		match self {
+			Self::Monolithic(bits) => bits.count_ones(),
+			Self::Tiled(tiles) => tiles.tiles.iter().map(|tile| tile.count_ones()).sum(),
+		}
+	}
+
+	pub(crate) fn not_any(self) -> bool {
+		match self {
+			Self::Monolithic(bits) => bits.not_any(),
+			Self::Tiled(tiles) => tiles.tiles.iter().all(|tile| tile.not_any()),
+		}
+	}
+
+	/// Flat indices of the filled voxels, in increasing order
+	pub(crate) fn iter_ones(self) -> Box<dyn Iterator<Item = usize> + 'a> {
+		match self {
+			Self::Monolithic(bits) => Box::new(bits.iter_ones()),
+			Self::Tiled(Tiles { tiles, tile_bits, .. }) => Box::new(
+				tiles
+					.iter()
+					.enumerate()
+					.flat_map(move |(t, tile)| tile.iter_ones().map(move |idx| t * tile_bits + idx)),
+			),
+		}
+	}
+}
+
+/// Mutable view of a grid's voxels, whole or tiled.
+pub(crate) enum VoxelsMut<'a> {
+	Monolithic(&'a mut BitVec),
+	Tiled(&'a mut Tiles),
+}
+
+impl<'a> VoxelsMut<'a> {
+	pub(crate) fn new(data: &'a mut BitVec, tiles: &'a mut Option<Tiles>) -> Self {
+		match tiles {
+			Some(tiles) => Self::Tiled(tiles),
+			None => Self::Monolithic(data),
+		}
+	}
+
+	/// Set the voxel at flat `index` (panics if out of bounds)
+	#[inline]
+	pub(crate) fn set(&mut self, index: usize, value: bool) {
+		match self {
+			Self::Monolithic(bits) => bits.set(index, value),
+			Self::Tiled(Tiles { tiles, tile_bits, len }) => {
+				assert!(index < *len, "voxel index {} out of range for {} voxels", index, len);
+				tiles[index / *tile_bits].set(index % *tile_bits, value);
+			}
+		}
+	}
+
+	/// Set every voxel to `value`
+	pub(crate) fn fill(&mut self, value: bool) {
+		match self {
+			Self::Monolithic(bits) => bits.fill(value),
+			Self::Tiled(tiles) => tiles.tiles.iter_mut().for_each(|tile| tile.fill(value)),
+		}
+	}
+
+	/// Flip every voxel
+	pub(crate) fn invert(&mut self) {
+		let flip = |bits: &mut BitVec| {
+			for mut bit in bits.as_mut_bitslice().iter_mut() {
+				*bit = !*bit;
+			}
+		};
+		match self {
+			Self::Monolithic(bits) => flip(bits),
+			Self::Tiled(tiles) => tiles.tiles.iter_mut().for_each(flip),
+		}
+	}
+
+	/// Each backing bit slice with the flat index of its first voxel, for filling disjoint ranges in parallel.
+	/// Slices rather than `BitVec`s, so callers cannot change a tile's length.
+	pub(crate) fn buffers_mut(self) -> Vec<(usize, &'a mut BitSlice)> {
+		match self {
+			Self::Monolithic(bits) => vec![(0, bits.as_mut_bitslice())],
+			Self::Tiled(Tiles { tiles, tile_bits, .. }) => {
+				let tile_bits = *tile_bits;
+				tiles
+					.iter_mut()
+					.enumerate()
+					.map(|(t, tile)| (t * tile_bits, tile.as_mut_bitslice()))
+					.collect()
+			}
+		}
+	}
+}
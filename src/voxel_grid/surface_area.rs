@@ -1,4 +1,21 @@
+use crate::voxel_grid::geometry::GridParams;
 use crate::voxel_grid::grid::Grid3D;
+use crate::voxel_grid::raster::Atom;
+
+/// Weighting factors indexed by classified edge type (1-based).
+const EDGE_WEIGHTS: [f64; 11] = [0.0, 0.894, 1.3409, 1.5879, 4.0, 2.6667, 3.3333, 1.79, 2.68, 4.08, 0.0];
+
+/// Neighborhood used by `Grid3D::estimate_surface_area_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Neighborhood {
+	/// Six face neighbors with the legacy edge weights (matches the C++ calibration).
+	#[default]
+	Face6,
+	/// Face and edge neighbors (18) used to estimate a local surface normal.
+	Edge18,
+	/// Face, edge, and corner neighbors (26) used to estimate a local surface normal.
+	Vertex26,
+}
 
 /// Edge classification types as in C++ `classifyEdgePoint`.
 pub fn classify_edge_point(grid: &Grid3D, idx: usize) -> usize {
@@ -46,17 +63,36 @@ pub fn classify_edge_point(grid: &Grid3D, idx: usize) -> usize {
 }
 
 impl Grid3D {
+	/// Solvent-accessible surface area (SASA, square angstroms): the surface traced by the probe center,
+	/// i.e. of the spheres of radius `radius + probe`. Accessible fill on a grid sized by
+	/// `GridParams::from_atom_bounds` at spacing `grid`, then the edge estimator. Not the molecular surface;
+	/// see `solvent_excluded_surface_area`. Works for a single atom; `None` if no atom has a usable radius
+	/// or `grid` is not finite and positive.
+	pub fn solvent_accessible_surface_area(atoms: &[Atom], probe: f32, grid: f32) -> Option<f64> {
+		let mut sas = GridParams::from_atom_bounds(atoms, probe, grid)?.build_grid();
+		sas.fill_accessible_parallel(atoms, probe);
+		Some(sas.estimate_surface_area_with_edges().0)
+	}
+
+	/// Solvent-excluded surface area (SESA, the Connolly molecular surface, square angstroms): the surface
+	/// the probe sphere touches, which never lies farther out than the atom spheres. Accessible fill, then
+	/// contraction by `probe` (`fill_excluded`), then the edge estimator. For a lone atom this is
+	/// 4 pi r^2, while SASA is 4 pi (r + probe)^2, so the two must not be mixed up when comparing with
+	/// other programs. Sized as `solvent_accessible_surface_area`, with the same `None` cases.
+	pub fn solvent_excluded_surface_area(atoms: &[Atom], probe: f32, grid: f32) -> Option<f64> {
+		let mut ses = GridParams::from_atom_bounds(atoms, probe, grid)?.build_grid();
+		ses.fill_excluded(atoms, probe);
+		Some(ses.estimate_surface_area_with_edges().0)
+	}
+
 	/// Estimate surface area using legacy edge classification weights (matches C++ utils-main.cpp).
 	pub fn estimate_surface_area_with_edges(&self) -> (f64, [f64; 10]) {
-		// Weighting factors indexed by classified edge type (1-based).
-		let wt = [0.0_f64, 0.894, 1.3409, 1.5879, 4.0, 2.6667, 3.3333, 1.79, 2.68, 4.08, 0.0];
-
 		let mut edges = [0usize; 10];
 		for k in 0..self.len_k {
 			for j in 0..self.len_j {
 				for i in 0..self.len_i {
 					let idx = i + j * self.len_i + k * self.len_i * self.len_j;
-					if !self.data[idx] {
+					if !self.get_voxel_index(idx) {
 						continue;
 					}
 					let typ = classify_edge_point(self, idx);
@@ -71,13 +107,214 @@ impl Grid3D {
 		let mut edges_f = [0.0_f64; 10];
 		for (ty, &count) in edges.iter().enumerate() {
 			edges_f[ty] = count as f64;
-			if ty < wt.len() {
-				surf += (count as f64) * wt[ty];
+			if ty < EDGE_WEIGHTS.len() {
+				surf += (count as f64) * EDGE_WEIGHTS[ty];
 			}
 		}
 		let surface = surf * (self.grid_size as f64) * (self.grid_size as f64);
 		(surface, edges_f)
 	}
+
+	/// Number of filled voxels that `classify_edge_point` does not classify as interior (type 0),
+	/// a cheap area proxy. Like the weighted estimate, neighbors are looked up by flat index, so a
+	/// voxel touching the grid's i or j faces sees the wrapped row; keep shapes off the grid faces.
+	pub fn count_surface_voxels(&self) -> usize {
+		(0..self.total_voxels)
+			.filter(|&idx| self.get_voxel_index(idx) && classify_edge_point(self, idx) != 0)
+			.count()
+	}
+
+	/// Estimate surface area with the chosen neighborhood.
+	/// `Face6` is the legacy estimate. `Edge18`/`Vertex26` estimate a normal `n` per surface voxel from the
+	/// empty voxels in that neighborhood and count its exposed faces scaled by `|n|_2 / |n|_1`, which removes
+	/// most of the staircase bias on curved and diagonal surfaces.
+	pub fn estimate_surface_area_mode(&self, mode: Neighborhood) -> f64 {
+		let corners = match mode {
+			Neighborhood::Face6 => return self.estimate_surface_area_with_edges().0,
+			Neighborhood::Edge18 => false,
+			Neighborhood::Vertex26 => true,
+		};
+
+		// Neighbor offsets; each contributes its unit direction weighted by inverse squared distance (1/len^3 overall).
+		let mut offsets: Vec<(isize, isize, isize, f64)> = Vec::new();
+		for dk in -1isize..=1 {
+			for dj in -1isize..=1 {
+				for di in -1isize..=1 {
+					let manhattan = di.abs() + dj.abs() + dk.abs();
+					if manhattan == 0 || (manhattan == 3 && !corners) {
+						continue;
+					}
+					let len = (manhattan as f64).sqrt();
+					offsets.push((di, dj, dk, 1.0 / (len * len * len)));
+				}
+			}
+		}
+
+		let filled_at = |i: isize, j: isize, k: isize| -> bool {
+			i >= 0
+				&& j >= 0
+				&& k >= 0
+				&& (i as usize) < self.len_i
+				&& (j as usize) < self.len_j
+				&& (k as usize) < self.len_k
+				&& self.get_voxel_ijk(i as usize, j as usize, k as usize)
+		};
+
+		let mut surf = 0.0_f64;
+		for k in 0..self.len_k as isize {
+			for j in 0..self.len_j as isize {
+				for i in 0..self.len_i as isize {
+					if !filled_at(i, j, k) {
+						continue;
+					}
+					let exposed = offsets
+						.iter()
+						.filter(|&&(di, dj, dk, _)| di.abs() + dj.abs() + dk.abs() == 1 && !filled_at(i + di, j + dj, k + dk))
+						.count();
+					if exposed == 0 {
+						continue;
+					}
+					let (mut nx, mut ny, mut nz) = (0.0_f64, 0.0_f64, 0.0_f64);
+					for &(di, dj, dk, w) in offsets.iter() {
+						if !filled_at(i + di, j + dj, k + dk) {
+							nx += di as f64 * w;
+							ny += dj as f64 * w;
+							nz += dk as f64 * w;
+						}
+					}
+					let l2 = (nx * nx + ny * ny + nz * nz).sqrt();
+					let l1 = nx.abs() + ny.abs() + nz.abs();
+					surf += if l2 > 0.0 { exposed as f64 * l2 / l1 } else { exposed as f64 };
+				}
+			}
+		}
+		surf * (self.grid_size as f64) * (self.grid_size as f64)
+	}
+
+	/// Unit outward normal at voxel (i, j, k), pointing toward the empty voxels among its 26 neighbors
+	/// (each weighted by 1/len^3, as in `estimate_surface_area_mode`). Out-of-grid neighbors count as empty.
+	/// Returns zeros when the empty neighbors cancel out or there are none.
+	pub fn surface_normal(&self, i: usize, j: usize, k: usize) -> [f32; 3] {
+		let (i, j, k) = (i as isize, j as isize, k as isize);
+		let (mut nx, mut ny, mut nz) = (0.0_f64, 0.0_f64, 0.0_f64);
+		for dk in -1isize..=1 {
+			for dj in -1isize..=1 {
+				for di in -1isize..=1 {
+					let manhattan = di.abs() + dj.abs() + dk.abs();
+					if manhattan == 0 {
+						continue;
+					}
+					let (ni, nj, nk) = (i + di, j + dj, k + dk);
+					let filled = ni >= 0
+						&& nj >= 0
+						&& nk >= 0
+						&& (ni as usize) < self.len_i
+						&& (nj as usize) < self.len_j
+						&& (nk as usize) < self.len_k
+						&& self.get_voxel_ijk(ni as usize, nj as usize, nk as usize);
+					if filled {
+						continue;
+					}
+					let len = (manhattan as f64).sqrt();
+					let w = 1.0 / (len * len * len);
+					nx += di as f64 * w;
+					ny += dj as f64 * w;
+					nz += dk as f64 * w;
+				}
+			}
+		}
+		let l2 = (nx * nx + ny * ny + nz * nz).sqrt();
+		if l2 > 1e-9 {
+			[(nx / l2) as f32, (ny / l2) as f32, (nz / l2) as f32]
+		} else {
+			[0.0, 0.0, 0.0]
+		}
+	}
+
+	/// Estimate the wall area of a pocket: `cavity` is a mask of empty voxels and `self` the molecule grid.
+	/// Each cavity voxel gets its edge weight from the cavity mask, split evenly over its exposed faces;
+	/// only faces bordering filled molecule voxels (wall) count, faces opening onto other empty space (mouth) do not.
+	pub fn cavity_surface_area(&self, cavity: &Grid3D) -> f64 {
+		assert!(
+			self.len_i == cavity.len_i && self.len_j == cavity.len_j && self.len_k == cavity.len_k,
+			"cavity mask dimensions must match the molecule grid"
+		);
+		let strides = [1usize, self.len_i, self.len_i * self.len_j];
+
+		let mut surf = 0.0_f64;
+		for idx in 0..cavity.total_voxels {
+			if !cavity.get_voxel_index(idx) {
+				continue;
+			}
+			let typ = classify_edge_point(cavity, idx);
+			if typ == 0 || typ >= EDGE_WEIGHTS.len() {
+				continue;
+			}
+			let mut exposed = 0usize;
+			let mut wall = 0usize;
+			for &stride in strides.iter() {
+				for positive in [false, true] {
+					if neighbor_filled(cavity, idx, stride, positive) {
+						continue;
+					}
+					exposed += 1;
+					if neighbor_filled(self, idx, stride, positive) {
+						wall += 1;
+					}
+				}
+			}
+			if exposed > 0 {
+				surf += EDGE_WEIGHTS[typ] * (wall as f64) / (exposed as f64);
+			}
+		}
+		surf * (self.grid_size as f64) * (self.grid_size as f64)
+	}
+
+	/// Split the legacy surface estimate into (external, internal) area in square angstroms.
+	/// Each surface voxel's edge weight is divided evenly over its exposed faces; a face counts as
+	/// internal when the empty voxel beyond it belongs to a buried region (an empty component not
+	/// touching the grid boundary, as in `interior_cavities`), otherwise as external. The two parts add
+	/// up to `estimate_surface_area_with_edges().0`; internal / external is a porosity measure.
+	pub fn external_internal_surface_area(&self) -> (f64, f64) {
+		let (labels, count) = self.connected_components_parallel(false);
+		let exterior = self.boundary_flags(&labels, count);
+		let strides = [1usize, self.len_i, self.len_i * self.len_j];
+
+		let mut external = 0.0_f64;
+		let mut internal = 0.0_f64;
+		for idx in 0..self.total_voxels {
+			if !self.get_voxel_index(idx) {
+				continue;
+			}
+			let typ = classify_edge_point(self, idx);
+			if typ == 0 || typ >= EDGE_WEIGHTS.len() {
+				continue;
+			}
+			let mut exposed = 0usize;
+			let mut buried = 0usize;
+			for &stride in strides.iter() {
+				for positive in [false, true] {
+					if neighbor_filled(self, idx, stride, positive) {
+						continue;
+					}
+					exposed += 1;
+					let neighbor = if positive { Some(idx + stride) } else { idx.checked_sub(stride) };
+					// Neighbors past the first or last voxel are outside the grid, hence exterior.
+					if let Some(n) = neighbor.filter(|&n| n < self.total_voxels)
+						&& !exterior[labels[n] as usize]
+					{
+						buried += 1;
+					}
+				}
+			}
+			if exposed > 0 {
+				internal += EDGE_WEIGHTS[typ] * (buried as f64) / (exposed as f64);
+				external += EDGE_WEIGHTS[typ] * ((exposed - buried) as f64) / (exposed as f64);
+			}
+		}
+		let face = (self.grid_size as f64) * (self.grid_size as f64);
+		(external * face, internal * face)
+	}
 }
 
 fn neighbor_filled(grid: &Grid3D, pt: usize, stride: usize, positive: bool) -> bool {
@@ -86,11 +323,11 @@ fn neighbor_filled(grid: &Grid3D, pt: usize, stride: usize, positive: bool) -> b
 		if idx >= grid.total_voxels {
 			false
 		} else {
-			grid.data[idx]
+			grid.get_voxel_index(idx)
 		}
 	} else {
 		match pt.checked_sub(stride) {
-			Some(idx) => grid.data[idx],
+			Some(idx) => grid.get_voxel_index(idx),
 			None => false,
 		}
 	}
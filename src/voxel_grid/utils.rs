@@ -1,6 +1,8 @@
+use std::io;
 use std::mem::size_of;
 use bitvec::prelude::BitVec;
 use crate::voxel_grid::grid;
+use crate::voxel_grid::storage::Tiles;
 
 /// Format large numbers with KB, MB, GB, TB suffixes
 fn format_bytes(bytes: usize) -> String {
@@ -22,22 +24,118 @@ fn format_bytes(bytes: usize) -> String {
 	}
 }
 
+/// Memory usage breakdown for a grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemoryReport {
+	pub dimensions: (usize, usize, usize),
+	pub total_voxels: usize,
+	pub grid_size: f32,
+	pub struct_bytes: usize,  // Struct overhead, excluding bit storage
+	pub bitvec_bytes: usize,  // Allocated bit storage
+	pub total_bytes: usize,
+}
+
+/// Bytes used by the `Grid3D` struct itself, excluding the dynamic bit storage
+fn struct_overhead() -> usize {
+	size_of::<grid::Grid3D>() - size_of::<BitVec>() - size_of::<Option<Tiles>>()
+}
+
+/// Estimate the memory a grid of these dimensions will need, before allocating it.
+/// Bit storage is rounded up to whole machine words, matching `BitVec::repeat`.
+pub fn estimate_grid_memory(len_i: usize, len_j: usize, len_k: usize) -> usize {
+	let bits = len_i.saturating_mul(len_j).saturating_mul(len_k);
+	let words = bits.div_ceil(usize::BITS as usize);
+	struct_overhead().saturating_add(words.saturating_mul(size_of::<usize>()))
+}
+
+/// Voxel values of a grid in a file format's storage order, from `Grid3D::iter_mrc_order` and friends.
+/// `axes` lists the axes (0 = I, 1 = J, 2 = K) from fastest to slowest varying.
+#[derive(Debug, Clone)]
+pub struct VoxelOrderIter<'a> {
+	grid: &'a grid::Grid3D,
+	axes: [usize; 3],
+	ijk: [usize; 3],
+	remaining: usize,
+}
+
+impl Iterator for VoxelOrderIter<'_> {
+	type Item = bool;
+
+	fn next(&mut self) -> Option<bool> {
+		if self.remaining == 0 {
+			return None;
+		}
+		let value = self.grid.get_voxel_ijk(self.ijk[0], self.ijk[1], self.ijk[2]);
+		self.remaining -= 1;
+		let lens = [self.grid.len_i, self.grid.len_j, self.grid.len_k];
+		for &axis in self.axes.iter() {
+			self.ijk[axis] += 1;
+			if self.ijk[axis] < lens[axis] {
+				break;
+			}
+			self.ijk[axis] = 0;
+		}
+		Some(value)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(self.remaining, Some(self.remaining))
+	}
+}
+
+impl ExactSizeIterator for VoxelOrderIter<'_> {}
+
 impl grid::Grid3D {
+	/// Voxel values with I fastest and K slowest: MRC (default axis order), EZD, and VTK storage order,
+	/// which is also the flat index order
+	pub fn iter_mrc_order(&self) -> VoxelOrderIter<'_> {
+		self.iter_axis_order([0, 1, 2])
+	}
+
+	/// Voxel values with K fastest and I slowest, the order of OpenDX `gridpositions` data
+	pub fn iter_dx_order(&self) -> VoxelOrderIter<'_> {
+		self.iter_axis_order([2, 1, 0])
+	}
+
+	/// Voxel values with K fastest and I slowest, the order of Gaussian cube files (same as DX)
+	pub fn iter_cube_order(&self) -> VoxelOrderIter<'_> {
+		self.iter_axis_order([2, 1, 0])
+	}
+
+	/// Voxel values walking `axes` (0 = I, 1 = J, 2 = K, a permutation) from fastest to slowest
+	pub(crate) fn iter_axis_order(&self, axes: [usize; 3]) -> VoxelOrderIter<'_> {
+		VoxelOrderIter { grid: self, axes, ijk: [0; 3], remaining: self.total_voxels }
+	}
+
+	/// Collect memory usage figures
+	pub fn memory_report(&self) -> MemoryReport {
+		let struct_bytes = struct_overhead(); // Exclude dynamic storage
+		let bitvec_bits = self.voxels().capacity(); // Total bits allocated across the BitVec tiles
+		let bitvec_bytes = bitvec_bits / 8; // Convert bits to bytes
+		MemoryReport {
+			dimensions: (self.len_i, self.len_j, self.len_k),
+			total_voxels: self.total_voxels,
+			grid_size: self.grid_size,
+			struct_bytes,
+			bitvec_bytes,
+			total_bytes: struct_bytes + bitvec_bytes,
+		}
+	}
+
 	/// Report memory usage and print a detailed breakdown
 	pub fn report_memory(&self) {
-		let struct_overhead = size_of::<Self>() - size_of::<BitVec>(); // Exclude dynamic storage
-		let bitvec_bits = self.data.capacity(); // Total bits allocated in BitVec
-		let bitvec_bytes = bitvec_bits / 8; // Convert bits to bytes
-		let total_memory = struct_overhead + bitvec_bytes;
+		let report = self.memory_report();
+		let (len_i, len_j, len_k) = report.dimensions;
 
 		eprintln!("Grid3D Memory Report:");
 		eprintln!("-------------------------");
-		eprintln!("  Dimensions: {} x {} x {}", self.len_i, self.len_j, self.len_k);
-		eprintln!("  Total Voxels: {:e}", self.total_voxels as f64); // Scientific notation
-		eprintln!("  Grid Size: {:.2} A", self.grid_size);
-		eprintln!("  Struct Overhead: {}", format_bytes(struct_overhead));
-		eprintln!("  BitVec Capacity: {}", format_bytes(bitvec_bytes));
-		eprintln!("  Total Memory Used: {}", format_bytes(total_memory));
+		eprintln!("  Dimensions: {} x {} x {}", len_i, len_j, len_k);
+		eprintln!("  Total Voxels: {:e}", report.total_voxels as f64); // Scientific notation
+		eprintln!("  Grid Size: {:.2} A", report.grid_size);
+		eprintln!("  Struct Overhead: {}", format_bytes(report.struct_bytes));
+		eprintln!("  BitVec Capacity: {}", format_bytes(report.bitvec_bytes));
+		eprintln!("  Total Memory Used: {}", format_bytes(report.total_bytes));
 		eprintln!("-------------------------");
 	}
 
@@ -62,13 +160,59 @@ impl grid::Grid3D {
 		(i, j, k)
 	}
 
+	/// Convert physical coordinates (angstroms) to fractional voxel coordinates (computed in f64)
+	#[inline]
+	pub fn physical_to_voxel(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+		let [sx, sy, sz] = self.spacing();
+		let to_voxel = |v: f32, shift: f32, s: f32| ((v as f64 - shift as f64) / s as f64) as f32;
+		(
+			to_voxel(x, self.x_shift, sx),
+			to_voxel(y, self.y_shift, sy),
+			to_voxel(z, self.z_shift, sz),
+		)
+	}
+
+	/// Convert physical coordinates to the nearest voxel coordinates (may lie outside the grid)
+	#[inline]
+	pub fn physical_to_voxel_rounded(&self, x: f32, y: f32, z: f32) -> (isize, isize, isize) {
+		let (fi, fj, fk) = self.physical_to_voxel(x, y, z);
+		(fi.round() as isize, fj.round() as isize, fk.round() as isize)
+	}
+
+	/// Convert physical coordinates to the nearest (i, j, k), or `None` if outside the grid
+	#[inline]
+	pub fn physical_to_ijk(&self, x: f32, y: f32, z: f32) -> Option<(usize, usize, usize)> {
+		let (i, j, k) = self.physical_to_voxel_rounded(x, y, z);
+		let inside = |v: isize, len: usize| v >= 0 && (v as usize) < len;
+		if inside(i, self.len_i) && inside(j, self.len_j) && inside(k, self.len_k) {
+			Some((i as usize, j as usize, k as usize))
+		} else {
+			None
+		}
+	}
+
+	/// Convert (i, j, k) to physical coordinates (angstroms).
+	/// Computed in f64 and rounded once, so far corners of large grids stay within half an f32 ulp.
+	#[inline]
+	pub fn voxel_to_physical(&self, i: usize, j: usize, k: usize) -> (f32, f32, f32) {
+		let [sx, sy, sz] = self.spacing();
+		let to_physical = |n: usize, s: f32, shift: f32| (n as f64 * s as f64 + shift as f64) as f32;
+		(
+			to_physical(i, sx, self.x_shift),
+			to_physical(j, sy, self.y_shift),
+			to_physical(k, sz, self.z_shift),
+		)
+	}
+
 	/// Get a voxel value by linear index (panics if out of bounds)
 	#[inline]
 	pub fn get_voxel_index(&self, index: usize) -> bool {
-		self.data[index]
+		self.voxels().get(index)
 	}
 
-	/// Get a voxel value using (i, j, k) coordinates
+	/// Get a voxel value using (i, j, k) coordinates. Does not check each coordinate: an out-of-range
+	/// `i` or `j` reads a voxel of a neighboring row, and a flat index past the end panics.
+	/// Use `try_get_voxel_ijk` for untrusted coordinates.
 	#[inline]
 	pub fn get_voxel_ijk(&self, i: usize, j: usize, k: usize) -> bool {
 		let index = self.ijk_to_index(i, j, k);
@@ -78,16 +222,59 @@ impl grid::Grid3D {
 	/// Set a voxel value by linear index (panics if out of bounds)
 	#[inline]
 	pub fn set_voxel_index(&mut self, index: usize, value: bool) {
-		self.data.set(index, value);
+		if self.filled_cache.is_some()
+			&& self.voxels().get(index) != value
+			&& let Some(count) = self.filled_cache.as_mut()
+		{
+			if value {
+				*count += 1;
+			} else {
+				*count -= 1;
+			}
+		}
+		self.voxels_mut().set(index, value);
 	}
 
-	/// Set a voxel value using (i, j, k) coordinates (assumes valid bounds)
+	/// Set a voxel value using (i, j, k) coordinates. Unchecked like `get_voxel_ijk`;
+	/// use `try_set_voxel_ijk` for untrusted coordinates.
 	#[inline]
 	pub fn set_voxel_ijk(&mut self, i: usize, j: usize, k: usize, value: bool) {
 		let index = self.ijk_to_index(i, j, k);
 		self.set_voxel_index(index, value);
 	}
 
+	/// Voxel value at (i, j, k), or `None` if any coordinate is outside the grid
+	#[inline]
+	pub fn try_get_voxel_ijk(&self, i: usize, j: usize, k: usize) -> Option<bool> {
+		if i < self.len_i && j < self.len_j && k < self.len_k {
+			Some(self.get_voxel_ijk(i, j, k))
+		} else {
+			None
+		}
+	}
+
+	/// Set the voxel at (i, j, k); errors with `InvalidInput` (and changes nothing) if any coordinate
+	/// is outside the grid
+	pub fn try_set_voxel_ijk(&mut self, i: usize, j: usize, k: usize, value: bool) -> io::Result<()> {
+		self.check_ijk(i, j, k)?;
+		self.set_voxel_ijk(i, j, k, value);
+		Ok(())
+	}
+
+	/// `InvalidInput` error unless (i, j, k) lies inside the grid
+	fn check_ijk(&self, i: usize, j: usize, k: usize) -> io::Result<()> {
+		if i < self.len_i && j < self.len_j && k < self.len_k {
+			return Ok(());
+		}
+		Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!(
+				"voxel ({}, {}, {}) is outside a {}x{}x{} grid",
+				i, j, k, self.len_i, self.len_j, self.len_k
+			),
+		))
+	}
+
 	/// Set a voxel to `true`
 	#[inline]
 	pub fn fill_voxel_ijk(&mut self, i: usize, j: usize, k: usize) {
@@ -100,6 +287,65 @@ impl grid::Grid3D {
 		self.set_voxel_index(index, true);
 	}
 
+	/// Set every listed linear index to `true` (e.g. a mask computed elsewhere).
+	/// Errors with `InvalidInput` on the first out-of-range index, before any voxel is changed.
+	pub fn fill_indices(&mut self, indices: &[usize]) -> io::Result<()> {
+		if let Some(&bad) = indices.iter().find(|&&idx| idx >= self.total_voxels) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("voxel index {} is outside a grid of {} voxels", bad, self.total_voxels),
+			));
+		}
+		for &idx in indices {
+			self.set_voxel_index(idx, true);
+		}
+		Ok(())
+	}
+
+	/// Set every listed (i, j, k) voxel to `true`.
+	/// Errors with `InvalidInput` on the first coordinate outside the grid, before any voxel is changed.
+	pub fn fill_coords(&mut self, coords: &[(usize, usize, usize)]) -> io::Result<()> {
+		for &(i, j, k) in coords {
+			self.check_ijk(i, j, k)?;
+		}
+		for &(i, j, k) in coords {
+			self.set_voxel_ijk(i, j, k, true);
+		}
+		Ok(())
+	}
+
+	/// One byte per voxel (1 = filled, 0 = empty) in flat index order, i fastest and k slowest:
+	/// the byte layout of `write_to_mrc_file`, and a numpy `uint8` array of shape (len_k, len_j, len_i).
+	pub fn to_u8_array(&self) -> Vec<u8> {
+		(0..self.total_voxels).map(|idx| u8::from(self.voxels().get(idx))).collect()
+	}
+
+	/// Build a grid from bytes laid out as `to_u8_array` writes them; any nonzero byte is filled.
+	/// `shifts` is the physical (x, y, z) of voxel (0, 0, 0). Errors with `InvalidInput` if `data` does not
+	/// hold exactly one byte per voxel of `dims` (I, J, K) or `grid_size` is not finite and positive.
+	pub fn from_u8_array(
+		dims: (usize, usize, usize),
+		grid_size: f32,
+		shifts: [f32; 3],
+		data: &[u8],
+	) -> io::Result<grid::Grid3D> {
+		let (len_i, len_j, len_k) = dims;
+		let mut out = grid::Grid3D::try_new(len_i, len_j, len_k, grid_size)?
+			.with_shifts(shifts[0], shifts[1], shifts[2]);
+		if data.len() != out.total_voxels {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("{} bytes given for a {}x{}x{} grid of {} voxels", data.len(), len_i, len_j, len_k, out.total_voxels),
+			));
+		}
+		for (idx, &byte) in data.iter().enumerate() {
+			if byte != 0 {
+				out.fill_voxel_index(idx);
+			}
+		}
+		Ok(out)
+	}
+
 	/// Set a voxel to `false`
 	#[inline]
 	pub fn empty_voxel_ijk(&mut self, i: usize, j: usize, k: usize) {
@@ -114,18 +360,79 @@ impl grid::Grid3D {
 
 	/// Zero out the entire grid (sets all voxels to `false`)
 	pub fn zero_grid(&mut self) {
-		self.data.fill(false);
+		self.voxels_mut().fill(false);
+		if self.filled_cache.is_some() {
+			self.filled_cache = Some(0);
+		}
 	}
 
 	/// Invert the entire grid (flip all bits)
 	pub fn invert(&mut self) {
-		for mut bit in self.data.as_mut_bitslice().iter_mut() {
-			*bit = !*bit; // Flip each bit manually
+		self.voxels_mut().invert();
+		if let Some(count) = self.filled_cache {
+			self.filled_cache = Some(self.total_voxels - count);
+		}
+	}
+
+	/// Replace the voxel storage after a bulk fill, keeping the filled-count cache in step
+	pub(crate) fn set_data(&mut self, data: BitVec, filled: usize) {
+		self.replace_bits(data);
+		self.refresh_filled_cache(filled);
+	}
+
+	/// Record a freshly counted filled total if the cache is being tracked
+	pub(crate) fn refresh_filled_cache(&mut self, filled: usize) {
+		if self.filled_cache.is_some() {
+			self.filled_cache = Some(filled);
 		}
 	}
 
 	/// Count the number of filled voxels
 	pub fn count_filled(&self) -> usize {
-		self.data.count_ones()
+		self.voxels().count_ones()
+	}
+
+	/// Flat indices of the filled voxels, in increasing order
+	pub fn iter_filled(&self) -> impl Iterator<Item = usize> + '_ {
+		self.voxels().iter_ones()
+	}
+
+	/// Filled voxel count maintained incrementally by the voxel setters, `invert`, `zero_grid`, and the rasterizers.
+	/// The first call does a full count and turns tracking on; later calls are O(1).
+	pub fn filled_count_cached(&mut self) -> usize {
+		match self.filled_cache {
+			Some(count) => count,
+			None => {
+				let count = self.count_filled();
+				self.filled_cache = Some(count);
+				count
+			}
+		}
+	}
+
+	/// Volume of the whole grid box in cubic angstroms (`total_voxels` times the voxel volume, in f64)
+	pub fn box_volume(&self) -> f64 {
+		self.total_voxels as f64 * self.voxel_volume()
+	}
+
+	/// Filled volume in cubic angstroms (`count_filled()` times the voxel volume, in f64)
+	pub fn filled_volume(&self) -> f64 {
+		self.count_filled() as f64 * self.voxel_volume()
+	}
+
+	/// Fraction of voxels that are empty, `1 - occupancy_fraction()` (0.0 for an empty-sized grid)
+	pub fn sparsity(&self) -> f64 {
+		if self.total_voxels == 0 {
+			return 0.0;
+		}
+		1.0 - self.occupancy_fraction()
+	}
+
+	/// Fraction of voxels that are filled (0.0 for an empty-sized grid)
+	pub fn occupancy_fraction(&self) -> f64 {
+		if self.total_voxels == 0 {
+			return 0.0;
+		}
+		self.count_filled() as f64 / self.total_voxels as f64
 	}
 }
@@ -0,0 +1,33 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+
+use crate::voxel_grid::grid::Grid3D;
+
+/// Voxel values per line in the scalar section.
+const VTK_VALUES_PER_LINE: usize = 20;
+
+impl Grid3D {
+	/// Save the voxel grid as a legacy ASCII VTK structured-points file (opened directly by ParaView).
+	/// Each voxel center is a point: `ORIGIN` is the shifts, `SPACING` the per-axis voxel size, and the
+	/// `occupancy` scalars are 0/1 with i fastest, as VTK expects.
+	pub fn write_to_vtk_file(&self, path: &str) -> Result<()> {
+		let mut file = BufWriter::new(File::create(path)?);
+		let [sx, sy, sz] = self.spacing();
+
+		writeln!(file, "# vtk DataFile Version 3.0")?;
+		writeln!(file, "vossvolvox voxel grid")?;
+		writeln!(file, "ASCII")?;
+		writeln!(file, "DATASET STRUCTURED_POINTS")?;
+		writeln!(file, "DIMENSIONS {} {} {}", self.len_i, self.len_j, self.len_k)?;
+		writeln!(file, "ORIGIN {} {} {}", self.x_shift, self.y_shift, self.z_shift)?;
+		writeln!(file, "SPACING {} {} {}", sx, sy, sz)?;
+		writeln!(file, "POINT_DATA {}", self.total_voxels)?;
+		writeln!(file, "SCALARS occupancy unsigned_char 1")?;
+		writeln!(file, "LOOKUP_TABLE default")?;
+		let values: Vec<&str> = self.iter_mrc_order().map(|filled| if filled { "1" } else { "0" }).collect();
+		for line in values.chunks(VTK_VALUES_PER_LINE) {
+			writeln!(file, "{}", line.join(" "))?;
+		}
+		file.flush()
+	}
+}
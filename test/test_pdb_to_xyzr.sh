@@ -121,13 +121,28 @@ for ((idx=0; idx<${#KEYS[@]}; idx++)); do
   printf "%-8s %-8s %-10s %s\n" "${KEYS[$idx]}" "${LINE_COUNTS[$idx]}" "${DURATIONS[$idx]}ms" "${MD5S[$idx]}"
 done
 
+# Rust writes coordinates as parsed values ("%8.3f"), while the other converters echo the PDB field
+# text, so MD5s can differ on non-standard inputs. Lines are compared numerically instead: same radius
+# text and coordinates within half of the last printed digit.
 diff_lines() {
   python3 - "$1" "$2" <<'PY'
 import sys
 from itertools import zip_longest
 ref = open(sys.argv[1]).read().splitlines()
 other = open(sys.argv[2]).read().splitlines()
-diff = sum(1 for a, b in zip_longest(ref, other, fillvalue=None) if a != b)
+
+def same(a, b):
+    if a is None or b is None:
+        return False
+    fa, fb = a.split(), b.split()
+    if len(fa) != 4 or len(fb) != 4 or fa[3] != fb[3]:
+        return a == b
+    try:
+        return all(abs(float(x) - float(y)) <= 0.0005 for x, y in zip(fa[:3], fb[:3]))
+    except ValueError:
+        return a == b
+
+diff = sum(1 for a, b in zip_longest(ref, other, fillvalue=None) if not same(a, b))
 print(diff)
 PY
 }
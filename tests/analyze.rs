@@ -0,0 +1,165 @@
+mod common;
+
+use std::f64::consts::PI;
+
+use voxel_sphere::voxel_grid::analyze::{interface_void, solvent_content, volume_difference};
+use voxel_sphere::voxel_grid::geometry::GridParams;
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::Atom;
+
+#[test]
+fn occupancy_fraction_and_solvent_content() {
+	let mut grid = Grid3D::new(4, 4, 4, 1.0);
+	for index in 0..16 {
+		grid.fill_voxel_index(index);
+	}
+	assert!((grid.occupancy_fraction() - 0.25).abs() < 1e-12);
+
+	let atoms = [Atom::new(20.0, 20.0, 20.0, 10.0)];
+	let solvent = solvent_content(&atoms, [40.0, 40.0, 40.0], 0.5);
+	let expected = 1.0 - (4.0 / 3.0 * PI * 1000.0) / 64000.0;
+	assert!((solvent - expected).abs() < 0.01);
+}
+
+#[test]
+fn volume_near_atoms_ignores_duplicate_references() {
+	let mut grid = Grid3D::new(30, 30, 30, 1.0);
+	grid.invert();
+	let atom = Atom::new(15.0, 15.0, 15.0, 1.5);
+	let one = grid.volume_near_atoms(&[atom], 5.0);
+	let two = grid.volume_near_atoms(&[atom, atom], 5.0);
+	assert_eq!(one, two);
+	assert!((one - 4.0 / 3.0 * PI * 125.0).abs() < 60.0);
+}
+
+#[test]
+fn per_residue_volume_credits_isolated_residue() {
+	let mut atoms = vec![
+		Atom::new(5.0, 5.0, 5.0, 1.5),
+		Atom::new(6.0, 5.0, 5.0, 1.5),
+		Atom::new(14.0, 14.0, 14.0, 1.8),
+	];
+	atoms[0].residue_id = 1;
+	atoms[1].residue_id = 1;
+	atoms[2].residue_id = 2;
+
+	let mut grid = Grid3D::new(40, 40, 40, 0.5);
+	let labels = grid.fill_accessible_labeled(&atoms, 1.0);
+	let volumes = grid.per_residue_volume(&labels);
+	let mut alone = Grid3D::new(40, 40, 40, 0.5);
+	let filled = alone.fill_accessible_parallel(&atoms[2..], 1.0);
+	assert!((volumes[&2] - filled as f64 * 0.125).abs() < 1e-9);
+
+	let mut unlabeled = Grid3D::new(40, 40, 40, 0.5);
+	unlabeled.fill_accessible_parallel(&atoms, 1.0);
+	assert_eq!(common::voxels(&unlabeled), common::voxels(&grid));
+}
+
+#[test]
+fn volume_difference_of_shared_grid() {
+	let chain = |n: usize| -> Vec<Atom> {
+		(0..n).map(|i| Atom::new(i as f32 * 1.5, 0.0, 0.0, 1.7)).collect()
+	};
+	let short = chain(3);
+	let (diff, delta) = volume_difference(&short, &short, 1.4, 0.5);
+	assert_eq!(delta, 0.0);
+	assert_eq!(diff.count_filled(), 0);
+
+	let (diff, delta) = volume_difference(&short, &chain(4), 1.4, 0.5);
+	assert!(delta > 0.0);
+	assert!(diff.count_filled() > 0);
+
+	// One ion per conformation is enough to size the shared grid.
+	let (diff, delta) = volume_difference(&chain(1), &[Atom::new(5.0, 0.0, 0.0, 1.7)], 1.4, 0.5);
+	assert!(delta.abs() < 1.0);
+	assert!(diff.count_filled() > 0);
+	let (diff, delta) = volume_difference(&[], &[], 1.4, 0.5);
+	assert_eq!((diff.count_filled(), delta), (0, 0.0));
+}
+
+#[test]
+fn keep_and_remove_largest_component_partition_the_grid() {
+	let mut grid = Grid3D::new(30, 30, 30, 1.0);
+	grid.add_sphere(10, 10, 10, 6.0);
+	grid.add_sphere(25, 25, 25, 2.0);
+	grid.add_sphere(25, 5, 25, 1.5);
+	let total = grid.count_filled();
+
+	let mut largest = grid.clone();
+	let kept = largest.keep_largest_component();
+	let mut rest = grid.clone();
+	let remaining = rest.remove_largest_component();
+	assert_eq!(kept + remaining, total);
+	assert_eq!(largest.count_filled(), kept);
+	assert_eq!(rest.count_filled(), remaining);
+	assert!(largest.get_voxel_ijk(10, 10, 10) && !largest.get_voxel_ijk(25, 25, 25));
+	assert!(!rest.get_voxel_ijk(10, 10, 10));
+	assert!(rest.get_voxel_ijk(25, 25, 25) && rest.get_voxel_ijk(25, 5, 25));
+}
+
+#[test]
+fn interface_void_measures_space_closed_by_the_partner() {
+	// An open cup of atoms on a 1 A lattice (walls and floor of 0..=8) and the lid that closes it.
+	let mut cup = Vec::new();
+	let mut lid = Vec::new();
+	for x in 0..=8 {
+		for y in 0..=8 {
+			for z in 0..=8 {
+				let wall = x == 0 || x == 8 || y == 0 || y == 8 || z == 0;
+				let atom = Atom::new(x as f32, y as f32, z as f32, 0.8);
+				if wall {
+					cup.push(atom);
+				} else if z == 8 {
+					lid.push(atom);
+				}
+			}
+		}
+	}
+	let void = interface_void(&cup, &lid, 0.0, 0.5);
+	let closed: Vec<Atom> = cup.iter().chain(lid.iter()).copied().collect();
+	let params = GridParams::from_atoms(&closed, 0.0, 0.5).unwrap();
+	let mut both = params.build_grid();
+	both.fill_excluded(&closed, 0.0);
+	let mut cup_only = params.build_grid();
+	cup_only.fill_excluded(&cup, 0.0);
+	assert_eq!(void, both.interior_cavities(1).difference(&cup_only.interior_cavities(1)).filled_volume());
+	assert!(void > 262.0);
+	assert!(interface_void(&cup, &lid, 1.4, 0.5) >= void * 0.5);
+
+	let far_lid: Vec<Atom> = lid.iter().map(|a| Atom::new(a.x, a.y, a.z + 30.0, a.radius)).collect();
+	assert_eq!(interface_void(&cup, &far_lid, 0.0, 0.5), 0.0);
+	// The closed box's own cavity is not credited to a distant partner.
+	let distant = [Atom::new(-20.0, 0.0, 0.0, 1.0), Atom::new(-20.0, 1.0, 0.0, 1.0), Atom::new(-21.0, 0.0, 0.0, 1.0)];
+	assert_eq!(interface_void(&closed, &distant, 0.0, 0.5), 0.0);
+	// A lone ion needs no minimum atom count; it encloses nothing with a distant partner or with no partner.
+	let ion = [Atom::new(30.0, 30.0, 30.0, 1.0)];
+	assert_eq!(interface_void(&ion, &ion[..0], 1.4, 0.5), 0.0);
+	assert_eq!(interface_void(&cup, &ion, 1.4, 0.5), 0.0);
+}
+
+#[test]
+fn extracted_components_reassemble_the_grid() {
+	let mut grid = Grid3D::new(20, 18, 16, 0.5).with_shifts(-3.0, 1.0, 2.0);
+	grid.add_sphere(5, 5, 5, 3.0);
+	grid.add_sphere(14, 12, 10, 2.5);
+	grid.fill_voxel_ijk(0, 0, 15);
+	let (labels, count) = grid.connected_components_parallel(true);
+	assert_eq!(count, 3);
+	let mut union = grid.empty_like();
+	for label in 1..=count as u32 {
+		let component = grid.extract_component(&labels, label);
+		union = union.union(&component);
+		let cropped = grid.extract_component_cropped(&labels, label, 2).unwrap();
+		assert_eq!(cropped.count_filled(), component.count_filled());
+		for idx in 0..cropped.total_voxels {
+			if cropped.get_voxel_index(idx) {
+				let (i, j, k) = cropped.index_to_ijk(idx);
+				let (x, y, z) = cropped.voxel_to_physical(i, j, k);
+				let (gi, gj, gk) = grid.physical_to_ijk(x, y, z).unwrap();
+				assert_eq!(labels[grid.ijk_to_index(gi, gj, gk)], label);
+			}
+		}
+	}
+	assert_eq!(common::voxels(&union), common::voxels(&grid));
+	assert!(grid.extract_component_cropped(&labels, 99, 0).is_none());
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use voxel_sphere::voxel_grid::atom_index::AtomGridIndex;
+use voxel_sphere::voxel_grid::raster::Atom;
+
+fn distance2(atom: &Atom, point: [f32; 3]) -> f32 {
+	(atom.x - point[0]).powi(2) + (atom.y - point[1]).powi(2) + (atom.z - point[2]).powi(2)
+}
+
+#[test]
+fn nearest_atom_matches_brute_force() {
+	let mut rng = common::Lcg::new(12345);
+	let atoms: Vec<Atom> = (0..500)
+		.map(|_| Atom::new(rng.next_f32() * 60.0 - 10.0, rng.next_f32() * 20.0, rng.next_f32() * 40.0, 1.5))
+		.collect();
+	for cell in [0.7f32, 3.0, 8.0, 100.0] {
+		let index = AtomGridIndex::new(&atoms, cell);
+		for _ in 0..300 {
+			// Query points reach well outside the atoms' bounding box.
+			let point = [rng.next_f32() * 100.0 - 30.0, rng.next_f32() * 60.0 - 20.0, rng.next_f32() * 80.0 - 20.0];
+			let mut best = 0usize;
+			for (i, atom) in atoms.iter().enumerate() {
+				if distance2(atom, point) < distance2(&atoms[best], point) {
+					best = i;
+				}
+			}
+			assert_eq!(index.nearest_atom(point), Some(best), "cell {cell} point {point:?}");
+			let within: Vec<usize> = (0..atoms.len()).filter(|&i| distance2(&atoms[i], point) <= 25.0).collect();
+			assert_eq!(index.atoms_within(point, 5.0), within);
+		}
+	}
+	assert_eq!(AtomGridIndex::new(&[], 2.0).nearest_atom([0.0; 3]), None);
+	assert!(AtomGridIndex::try_new(&atoms, 0.0).is_err());
+}
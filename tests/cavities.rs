@@ -0,0 +1,140 @@
+use voxel_sphere::voxel_grid::grid::Grid3D;
+
+/// Cube of filled voxels with a one-voxel empty border.
+fn solid_block(n: usize) -> Grid3D {
+	let mut grid = Grid3D::new(n, n, n, 1.0);
+	for k in 1..n - 1 {
+		for j in 1..n - 1 {
+			for i in 1..n - 1 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	grid
+}
+
+#[test]
+fn largest_cavity_keeps_only_the_biggest_void() {
+	let mut grid = solid_block(20);
+	grid.empty_voxel_ijk(4, 4, 4);
+	for k in 10..12 {
+		for j in 10..12 {
+			for i in 10..12 {
+				grid.empty_voxel_ijk(i, j, k);
+			}
+		}
+	}
+
+	let cavity = grid.largest_cavity().unwrap();
+	assert_eq!(cavity.count_filled(), 8);
+	assert!(cavity.get_voxel_ijk(10, 10, 10));
+	assert!(!cavity.get_voxel_ijk(4, 4, 4));
+	assert_eq!(grid.interior_cavities(1).count_filled(), 9);
+	assert!(solid_block(10).largest_cavity().is_none());
+}
+
+#[test]
+fn cavity_surface_area_matches_sphere() {
+	let n = 60;
+	let center = 30.0f64;
+	let radius = 10.0f64;
+	let mut grid = Grid3D::new(n, n, n, 1.0);
+	for k in 2..n - 2 {
+		for j in 2..n - 2 {
+			for i in 2..n - 2 {
+				let d = ((i as f64 - center).powi(2)
+					+ (j as f64 - center).powi(2)
+					+ (k as f64 - center).powi(2))
+				.sqrt();
+				if d > radius {
+					grid.fill_voxel_ijk(i, j, k);
+				}
+			}
+		}
+	}
+
+	let cavity = grid.largest_cavity().unwrap();
+	let area = grid.cavity_surface_area(&cavity);
+	let analytic = 4.0 * std::f64::consts::PI * radius * radius;
+	assert!((area - analytic).abs() / analytic < 0.1);
+	let (full, _) = cavity.estimate_surface_area_with_edges();
+	assert!((area - full).abs() < 1e-6);
+}
+
+#[test]
+fn cavity_sizes_land_in_their_bins() {
+	let mut grid = Grid3D::new(30, 12, 12, 0.5);
+	for k in 1..11 {
+		for j in 1..11 {
+			for i in 1..29 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	// Cavities of 1, 8, and 27 voxels: 0.125, 1.0, and 3.375 cubic angstroms.
+	grid.empty_voxel_ijk(3, 5, 5);
+	for k in 4..6 {
+		for j in 4..6 {
+			for i in 8..10 {
+				grid.empty_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	for k in 4..7 {
+		for j in 4..7 {
+			for i in 15..18 {
+				grid.empty_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	assert_eq!(grid.cavity_size_histogram(&[0.0, 0.5, 2.0, 10.0], 1).unwrap(), vec![1, 1, 1]);
+	assert_eq!(grid.cavity_size_histogram(&[0.125, 1.0], 1).unwrap(), vec![2]);
+	assert_eq!(grid.cavity_size_histogram(&[1.0, 3.0], 1).unwrap(), vec![1]);
+	assert!(grid.cavity_size_histogram(&[1.0], 1).unwrap().is_empty());
+	for bad in [&[2.0, 1.0][..], &[1.0, 1.0], &[0.0, f64::NAN, 2.0], &[0.0, f64::INFINITY]] {
+		let err = grid.cavity_size_histogram(bad, 1).unwrap_err();
+		assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{bad:?}");
+	}
+}
+
+#[test]
+fn boundary_components_are_the_ones_touching_a_face() {
+	let mut grid = Grid3D::new(16, 16, 16, 1.0);
+	for j in 4..7 {
+		for i in 0..3 {
+			grid.fill_voxel_ijk(i, j, 5);
+		}
+	}
+	grid.add_sphere(9, 9, 9, 2.0);
+	let (labels, count) = grid.connected_components(true);
+	assert_eq!(count, 2);
+	let boundary = grid.boundary_components(&labels);
+	assert_eq!(boundary.len(), 1);
+	assert!(boundary.contains(&labels[grid.ijk_to_index(0, 5, 5)]));
+	assert!(!boundary.contains(&labels[grid.ijk_to_index(9, 9, 9)]));
+}
+
+#[test]
+fn min_cavity_size_drops_single_voxel_noise() {
+	let mut grid = solid_block(20);
+	grid.set_voxel_ijk(4, 4, 4, false);
+	for k in 10..13 {
+		for j in 10..13 {
+			for i in 10..13 {
+				grid.empty_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	assert_eq!(grid.interior_cavities(1).count_filled(), 28);
+	let kept = grid.interior_cavities(2);
+	assert_eq!(kept.count_filled(), 27);
+	assert!(!kept.get_voxel_ijk(4, 4, 4));
+	assert!(kept.get_voxel_ijk(11, 11, 11));
+	assert_eq!(grid.min_cavity_voxels(0.0), 1);
+	assert_eq!(grid.min_cavity_voxels(27.0), 27);
+	assert_eq!(grid.interior_cavities(grid.min_cavity_voxels(27.0)).count_filled(), 27);
+	assert_eq!(grid.interior_cavities(grid.min_cavity_voxels(27.1)).count_filled(), 0);
+	// The histogram applies the same threshold, so it counts exactly the cavities left in the grid.
+	assert_eq!(grid.cavity_size_histogram(&[0.0, 2.0, 100.0], 1).unwrap(), vec![1, 1]);
+	assert_eq!(grid.cavity_size_histogram(&[0.0, 2.0, 100.0], 2).unwrap(), vec![0, 1]);
+}
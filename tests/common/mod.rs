@@ -0,0 +1,40 @@
+#![allow(dead_code)]
+
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::Atom;
+
+/// Small deterministic LCG so tests do not need a random-number crate.
+pub struct Lcg(u64);
+
+impl Lcg {
+	pub fn new(seed: u64) -> Self {
+		Lcg(seed)
+	}
+
+	/// Uniform value in [0, 1).
+	pub fn next_f32(&mut self) -> f32 {
+		self.0 = self
+			.0
+			.wrapping_mul(6364136223846793005)
+			.wrapping_add(1442695040888963407);
+		(self.0 >> 40) as f32 / (1u64 << 24) as f32
+	}
+}
+
+/// `count` atoms scattered through the cube [low, low + span) with radii in [1, 2).
+pub fn random_atoms(count: usize, low: f32, span: f32, seed: u64) -> Vec<Atom> {
+	let mut rng = Lcg::new(seed);
+	(0..count)
+		.map(|_| {
+			let x = low + span * rng.next_f32();
+			let y = low + span * rng.next_f32();
+			let z = low + span * rng.next_f32();
+			Atom::new(x, y, z, 1.0 + rng.next_f32())
+		})
+		.collect()
+}
+
+/// Every voxel of `grid` in flat index order, for comparing grids.
+pub fn voxels(grid: &Grid3D) -> Vec<bool> {
+	(0..grid.total_voxels).map(|idx| grid.get_voxel_index(idx)).collect()
+}
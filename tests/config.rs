@@ -0,0 +1,54 @@
+mod common;
+
+use voxel_sphere::voxel_grid::config::VolumeConfig;
+use voxel_sphere::voxel_grid::geometry::GridParams;
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::pdb::load_atoms_from_pdb_path;
+
+/// Write a three-carbon chain to a temp PDB file named `name` and return its path.
+fn write_chain_pdb(name: &str) -> String {
+	let path = std::env::temp_dir().join(name);
+	let mut text = String::new();
+	for (n, x) in [0.0f32, 1.5, 3.0].into_iter().enumerate() {
+		text += &format!(
+			"ATOM  {:>5}  C   ALA A   1    {:>8.3}   0.000   0.000  1.00  0.00           C\n",
+			n + 1,
+			x
+		);
+	}
+	std::fs::write(&path, text).unwrap();
+	path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn config_pipeline_matches_manual_steps() {
+	let path = write_chain_pdb("voxel_sphere_config.pdb");
+	let config = VolumeConfig { grid: 0.5, ..VolumeConfig::default() };
+	let grid = Grid3D::from_pdb_with_config(&path, &config).unwrap();
+
+	let atoms = load_atoms_from_pdb_path(&path, &config.pdb_options()).unwrap();
+	let mut manual = GridParams::from_atoms(&atoms, config.probe, config.grid).unwrap().build_grid();
+	manual.fill_excluded(&atoms, config.probe);
+	assert!(grid.count_filled() > 0);
+	assert_eq!(common::voxels(&grid), common::voxels(&manual));
+
+	let bad = VolumeConfig { grid: 0.0, ..config };
+	assert!(Grid3D::from_pdb_with_config(&path, &bad).is_err());
+}
+
+#[test]
+fn timings_record_each_pipeline_phase() {
+	let path = write_chain_pdb("voxel_sphere_config_timings.pdb");
+	let config = VolumeConfig::default();
+	let (grid, mut timings) = Grid3D::from_pdb_with_timings(&path, &config).unwrap();
+	for phase in ["parse", "grid_build", "rasterize", "contract"] {
+		assert!(timings.get(phase).is_some(), "{phase}");
+	}
+	timings.time("surface", || grid.estimate_surface_area_with_edges());
+	assert!(timings.get("surface").is_some());
+	assert!(timings.get("write").is_none());
+	assert_eq!(timings.phases.len(), 5);
+	assert!(timings.total() >= timings.get("rasterize").unwrap());
+	let plain = Grid3D::from_pdb_with_config(&path, &config).unwrap();
+	assert_eq!(grid.to_u8_array(), plain.to_u8_array());
+}
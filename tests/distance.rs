@@ -0,0 +1,189 @@
+mod common;
+
+use voxel_sphere::voxel_grid::distance::{
+	cavity_depth, channel_bottleneck, channel_mouth_radius, surface_hausdorff, surface_hausdorff_symmetric,
+};
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::surface_area::classify_edge_point;
+
+/// Voxel coordinates of the surface voxels of `grid`.
+fn surface_points(grid: &Grid3D) -> Vec<[f64; 3]> {
+	(0..grid.total_voxels)
+		.filter(|&idx| grid.get_voxel_index(idx) && classify_edge_point(grid, idx) != 0)
+		.map(|idx| {
+			let (i, j, k) = grid.index_to_ijk(idx);
+			[i as f64, j as f64, k as f64]
+		})
+		.collect()
+}
+
+/// Brute-force nearest-surface distances from each surface voxel of `from` to `to`, in voxel units.
+fn brute_directed(from: &Grid3D, to: &Grid3D) -> Vec<f64> {
+	let targets = surface_points(to);
+	surface_points(from)
+		.iter()
+		.map(|p| {
+			targets
+				.iter()
+				.map(|q| ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2) + (p[2] - q[2]).powi(2)).sqrt())
+				.fold(f64::INFINITY, f64::min)
+		})
+		.collect()
+}
+
+/// (max, mean) of a list of distances.
+fn max_mean(all: &[f64]) -> (f64, f64) {
+	(all.iter().copied().fold(0.0, f64::max), all.iter().sum::<f64>() / all.len() as f64)
+}
+
+#[test]
+fn hausdorff_matches_brute_force() {
+	let mut a = Grid3D::new(24, 24, 24, 0.5);
+	a.add_sphere(12, 12, 12, 6.0);
+	assert_eq!(surface_hausdorff(&a, &a), (0.0, 0.0));
+	assert_eq!(surface_hausdorff_symmetric(&a, &a), (0.0, 0.0));
+	let mut shifted = Grid3D::new(24, 24, 24, 0.5);
+	shifted.add_sphere(13, 12, 12, 6.0);
+	assert!((surface_hausdorff(&a, &shifted).0 - 0.5).abs() < 1e-4);
+	assert!((surface_hausdorff_symmetric(&a, &shifted).0 - 0.5).abs() < 1e-4);
+
+	let mut rng = common::Lcg::new(12345);
+	let mut sparse = Grid3D::new(9, 7, 5, 1.0);
+	for idx in 0..sparse.total_voxels {
+		if rng.next_f32() < 0.2 {
+			sparse.fill_voxel_index(idx);
+		}
+	}
+	let mut corners = sparse.empty_like();
+	corners.fill_voxel_ijk(0, 0, 0);
+	corners.fill_voxel_ijk(8, 6, 4);
+	let mut strided = sparse.empty_like();
+	for idx in (0..strided.total_voxels).step_by(7) {
+		strided.fill_voxel_index(idx);
+	}
+	for other in [&corners, &strided] {
+		let forward = brute_directed(&sparse, other);
+		let backward = brute_directed(other, &sparse);
+		let (max, mean) = surface_hausdorff(&sparse, other);
+		let (brute_max, brute_mean) = max_mean(&forward);
+		assert!((max as f64 - brute_max).abs() < 1e-4);
+		assert!((mean as f64 - brute_mean).abs() < 1e-4);
+
+		let (max, mean) = surface_hausdorff_symmetric(&sparse, other);
+		let (brute_max, brute_mean) = max_mean(&[forward, backward].concat());
+		assert!((max as f64 - brute_max).abs() < 1e-4);
+		assert!((mean as f64 - brute_mean).abs() < 1e-4);
+	}
+	// Two corner voxels are close to some of the sparse surface but far from most of it.
+	assert!(surface_hausdorff(&corners, &sparse).0 < surface_hausdorff(&sparse, &corners).0);
+}
+
+/// Brute-force distance (angstroms) from voxel `a` to the nearest voxel with occupancy `value`.
+fn brute_distance(grid: &Grid3D, a: usize, value: bool) -> f64 {
+	let spacing = grid.spacing().map(|v| v as f64);
+	let (i, j, k) = grid.index_to_ijk(a);
+	(0..grid.total_voxels)
+		.filter(|&b| grid.get_voxel_index(b) == value)
+		.map(|b| {
+			let (p, q, r) = grid.index_to_ijk(b);
+			let dx = (i as f64 - p as f64) * spacing[0];
+			let dy = (j as f64 - q as f64) * spacing[1];
+			let dz = (k as f64 - r as f64) * spacing[2];
+			(dx * dx + dy * dy + dz * dz).sqrt()
+		})
+		.fold(f64::INFINITY, f64::min)
+}
+
+#[test]
+fn distance_transform_is_exact() {
+	let cubic = Grid3D::new(11, 8, 6, 0.7);
+	let anisotropic = Grid3D::new_anisotropic(11, 8, 6, [0.5, 1.25, 2.0]);
+	for (seed, mut grid) in [(99, cubic), (7, anisotropic)] {
+		let mut rng = common::Lcg::new(seed);
+		for idx in 0..grid.total_voxels {
+			if rng.next_f32() < 0.06 {
+				grid.fill_voxel_index(idx);
+			}
+		}
+		for value in [true, false] {
+			let distance = grid.distance_to(value);
+			for a in 0..grid.total_voxels {
+				let expected = brute_distance(&grid, a, value);
+				assert!((distance.data[a] as f64 - expected).abs() < 1e-4, "voxel {a}: {} vs {expected}", distance.data[a]);
+			}
+		}
+	}
+	let empty = Grid3D::new(3, 3, 3, 1.0).distance_transform();
+	assert!(empty.data.iter().all(|v| v.is_infinite()));
+}
+
+#[test]
+fn channel_bottleneck_and_depth_of_a_narrowing_pore() {
+	let g = 0.25f32;
+	let n = 80usize;
+	let c = (n as f32 - 1.0) * g / 2.0;
+	// Pore along z, radius 4 A narrowing linearly to 2 A at the middle.
+	let radius_at = |z: f32| 2.0 + 2.0 * ((z - c) / 4.0).abs().min(1.0);
+	let mut wall = Grid3D::new(n, n, n, g);
+	let mut channel = wall.empty_like();
+	for k in 0..n {
+		for j in 0..n {
+			for i in 0..n {
+				let (x, y, z) = (i as f32 * g, j as f32 * g, k as f32 * g);
+				if ((x - c).powi(2) + (y - c).powi(2)).sqrt() > radius_at(z) {
+					wall.fill_voxel_ijk(i, j, k);
+				} else {
+					channel.fill_voxel_ijk(i, j, k);
+				}
+			}
+		}
+	}
+	let path: Vec<[f32; 3]> = (0..n).map(|k| [c, c, k as f32 * g]).collect();
+	assert!((channel_bottleneck(&wall, &path) - 2.0).abs() <= g);
+	assert!((cavity_depth(&channel, &wall) - 4.0).abs() <= g);
+	assert_eq!(cavity_depth(&wall.empty_like(), &wall), 0.0);
+}
+
+#[test]
+fn largest_inscribed_sphere_fills_a_spherical_cavity() {
+	let mut solid = Grid3D::new(40, 40, 40, 0.5);
+	solid.x_shift = 1.0;
+	solid.y_shift = 0.0;
+	solid.z_shift = 0.0;
+	solid.invert();
+	solid.remove_sphere(20, 20, 20, 10.0);
+	let cavity = solid.interior_cavities(1);
+	let (center, radius) = solid.largest_inscribed_sphere(&cavity);
+	assert!((radius - 5.0).abs() <= 0.5);
+	assert_eq!(center, [11.0, 10.0, 10.0]);
+	assert_eq!(solid.largest_inscribed_sphere(&solid.empty_like()).1, 0.0);
+}
+
+#[test]
+fn channel_mouth_radius_is_the_neck_constriction() {
+	// A spherical cavity joined to an exterior slab (k >= 24) by a neck of radius 3 along k.
+	let n = 30;
+	let mut cavity = Grid3D::new(n, n, n, 1.0);
+	let mut exterior = Grid3D::new(n, n, n, 1.0);
+	for k in 0..n {
+		for j in 0..n {
+			for i in 0..n {
+				let (x, y, z) = (i as f32 - 15.0, j as f32 - 15.0, k as f32);
+				let in_ball = x * x + y * y + (z - 8.0) * (z - 8.0) <= 36.0;
+				let in_neck = x * x + y * y <= 9.0 && (8.0..24.0).contains(&z);
+				if in_ball {
+					cavity.fill_voxel_ijk(i, j, k);
+				} else if k >= 24 || in_neck {
+					exterior.fill_voxel_ijk(i, j, k);
+				}
+			}
+		}
+	}
+	// Open neck voxels have x^2 + y^2 <= 9, so the nearest wall lies between sqrt(10) and 4.
+	let radius = channel_mouth_radius(&cavity, &exterior);
+	assert!((3.0..=4.0).contains(&radius), "{radius}");
+
+	let mut unreachable = Grid3D::new(n, n, n, 1.0);
+	unreachable.fill_voxel_ijk(0, 0, 29);
+	assert_eq!(channel_mouth_radius(&cavity, &unreachable), 0.0);
+}
@@ -0,0 +1,131 @@
+use std::f64::consts::PI;
+
+use voxel_sphere::voxel_grid::float_grid::{FloatGrid3D, Threshold};
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::Atom;
+
+#[test]
+fn splatted_volume_changes_smoothly_with_position() {
+	let mut previous: Option<(f64, f64)> = None;
+	let mut max_splat_step = 0.0f64;
+	let mut max_binary_step = 0.0f64;
+	let mut last_splat = 0.0;
+	for step in 0..20 {
+		let atoms = [Atom::new(10.0 + step as f32 * 0.05, 10.0, 10.0, 1.7)];
+		let mut splat = FloatGrid3D::new(40, 40, 40, 0.5);
+		let splat_volume = splat.fill_accessible_splatted(&atoms, 0.0);
+		let mut binary = Grid3D::new(40, 40, 40, 0.5);
+		let binary_volume = binary.fill_accessible_parallel(&atoms, 0.0) as f64 * 0.125;
+		if let Some((prev_splat, prev_binary)) = previous {
+			max_splat_step = max_splat_step.max((splat_volume - prev_splat).abs());
+			max_binary_step = max_binary_step.max((binary_volume - prev_binary).abs());
+		}
+		previous = Some((splat_volume, binary_volume));
+		last_splat = splat_volume;
+	}
+	assert!(max_splat_step < max_binary_step);
+	let analytic = 4.0 / 3.0 * PI * 1.7f64.powi(3);
+	assert!((last_splat - analytic).abs() / analytic < 0.05);
+}
+
+#[test]
+fn anisotropic_spacing_carries_through_float_grids() {
+	let spacing = [2.0, 1.0, 0.5];
+	let mut grid = Grid3D::new_anisotropic(10, 12, 14, spacing).with_shifts(-4.0, 3.0, 1.0);
+	grid.fill_voxel_ijk(1, 2, 3);
+	grid.fill_voxel_ijk(9, 11, 13);
+	let diff = grid.signed_difference(&grid.empty_like());
+	assert_eq!(diff.spacing, spacing);
+	assert_eq!(FloatGrid3D::like_grid(&grid).spacing, spacing);
+	assert!((diff.total_volume() - 2.0).abs() < 1e-9);
+
+	let back = diff.threshold(Threshold::default());
+	assert_eq!(back.spacing(), spacing);
+	assert_eq!((back.x_shift, back.y_shift, back.z_shift), (-4.0, 3.0, 1.0));
+	assert_eq!(back.to_u8_array(), grid.to_u8_array());
+	assert!((back.filled_volume() - diff.total_volume()).abs() < 1e-9);
+
+	let path = std::env::temp_dir().join("voxel_sphere_float_aniso.mrc");
+	diff.write_to_mrc_file(path.to_str().unwrap()).unwrap();
+	let bytes = std::fs::read(&path).unwrap();
+	let word = |n: usize| f32::from_le_bytes(bytes[n * 4..n * 4 + 4].try_into().unwrap());
+	assert_eq!((word(10), word(11), word(12)), (20.0, 12.0, 7.0));
+	assert_eq!(bytes.len(), 1024 + 4 * 10 * 12 * 14);
+
+	let atoms = [Atom::new(10.0, 10.0, 10.0, 4.0)];
+	let mut splat = FloatGrid3D::new_anisotropic(20, 40, 80, [1.0, 0.5, 0.25]);
+	let volume = splat.fill_accessible_splatted(&atoms, 0.0);
+	let analytic = 4.0 / 3.0 * PI * 64.0;
+	assert!((volume - analytic).abs() / analytic < 0.02);
+}
+
+#[test]
+fn threshold_directions_split_a_gaussian_blob() {
+	let mut blob = FloatGrid3D::new(21, 21, 21, 1.0);
+	for k in 0..21 {
+		for j in 0..21 {
+			for i in 0..21 {
+				let d2 = ((i as f32 - 10.0).powi(2) + (j as f32 - 10.0).powi(2) + (k as f32 - 10.0).powi(2)) / 18.0;
+				let idx = blob.ijk_to_index(i, j, k);
+				blob.data[idx] = (-d2).exp();
+			}
+		}
+	}
+	let above = blob.threshold(Threshold::Above(0.5));
+	let below = blob.threshold(Threshold::Below(0.5));
+	assert!(above.get_voxel_ijk(10, 10, 10) && !above.get_voxel_ijk(0, 0, 0));
+	assert!(below.get_voxel_ijk(0, 0, 0) && !below.get_voxel_ijk(10, 10, 10));
+	assert_eq!(above.count_filled() + below.count_filled(), blob.total_voxels);
+	assert_eq!(blob.threshold(Threshold::default()).count_filled(), above.count_filled());
+	let band = blob.threshold(Threshold::Between(0.2, 0.5));
+	assert!(!band.get_voxel_ijk(10, 10, 10) && !band.get_voxel_ijk(0, 0, 0));
+	assert!(band.count_filled() > 0);
+	blob.data[0] = f32::NAN;
+	assert!(!blob.threshold(Threshold::Below(0.5)).get_voxel_index(0));
+}
+
+#[test]
+fn signed_difference_writes_a_float_mrc() {
+	let mut a = Grid3D::new(10, 10, 10, 1.0);
+	let mut b = a.empty_like();
+	a.fill_voxel_ijk(1, 1, 1);
+	a.fill_voxel_ijk(2, 2, 2);
+	b.fill_voxel_ijk(2, 2, 2);
+	b.fill_voxel_ijk(3, 3, 3);
+	let diff = a.signed_difference(&b);
+	assert_eq!(diff.get_ijk(1, 1, 1), 1.0);
+	assert_eq!(diff.get_ijk(3, 3, 3), -1.0);
+	assert_eq!(diff.get_ijk(2, 2, 2), 0.0);
+	assert_eq!(diff.data.iter().filter(|&&value| value != 0.0).count(), 2);
+
+	let path = std::env::temp_dir().join("voxel_sphere_signed_difference.mrc");
+	diff.write_to_mrc_file(path.to_str().unwrap()).unwrap();
+	let bytes = std::fs::read(&path).unwrap();
+	let word = |n: usize| -> [u8; 4] { bytes[n * 4..n * 4 + 4].try_into().unwrap() };
+	assert_eq!(bytes.len(), 1024 + 4 * 1000);
+	assert_eq!(i32::from_le_bytes(word(3)), 2);
+	assert_eq!((f32::from_le_bytes(word(19)), f32::from_le_bytes(word(20))), (-1.0, 1.0));
+	assert_eq!(f32::from_le_bytes(word(256 + diff.ijk_to_index(3, 3, 3))), -1.0);
+}
+
+#[test]
+fn gaussian_blur_is_identical_on_any_thread_count() {
+	let mut grid = FloatGrid3D::new(37, 29, 23, 0.5);
+	for (n, value) in grid.data.iter_mut().enumerate() {
+		*value = ((n * 7919) % 101) as f32 / 100.0;
+	}
+	let serial = grid.gaussian_blur_with_threads(0.8, 1);
+	for threads in [2, 3, 8, 64] {
+		assert_eq!(grid.gaussian_blur_with_threads(0.8, threads).data, serial.data, "threads {threads}");
+	}
+
+	// A delta in the middle keeps its mass.
+	let mut delta = FloatGrid3D::new(31, 31, 31, 1.0);
+	let center = delta.ijk_to_index(15, 15, 15);
+	delta.data[center] = 1.0;
+	let blurred = delta.gaussian_blur(1.5);
+	let mass: f32 = blurred.data.iter().sum();
+	assert!((mass - 1.0).abs() < 1e-4);
+	assert!(blurred.data[center] > 0.0 && blurred.data[center] < 1.0);
+	assert_eq!(delta.gaussian_blur(0.0).data, delta.data);
+}
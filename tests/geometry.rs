@@ -0,0 +1,110 @@
+use voxel_sphere::voxel_grid::geometry::{AtomSummary, GridOptions, GridParams, atom_summary};
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::Atom;
+use voxel_sphere::voxel_grid::utils::estimate_grid_memory;
+
+#[test]
+fn from_box_sizes_and_aligns() {
+	let plain = GridParams::from_box([0.0, 0.0, 0.0], [10.0, 5.0, 3.0], 1.0, false).unwrap();
+	assert_eq!((plain.len_i, plain.len_j, plain.len_k), (10, 5, 3));
+
+	let aligned = GridParams::from_box([-3.0, 0.5, 0.0], [10.0, 5.0, 3.0], 1.0, true).unwrap();
+	assert_eq!(aligned.xmin, -4.0);
+	assert_eq!(aligned.len_i % 4, 0);
+	let first = aligned.build_grid();
+	let second = aligned.build_grid();
+	assert_eq!((first.len_i, first.x_shift), (second.len_i, second.x_shift));
+
+	for grid in [0.0, -1.0, f32::NAN] {
+		let err = GridParams::from_box([0.0; 3], [10.0; 3], grid, true).unwrap_err();
+		assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+	}
+}
+
+#[test]
+fn per_axis_padding_overrides_only_that_axis() {
+	let atoms = [
+		Atom::new(0.0, 0.0, 0.0, 1.7),
+		Atom::new(100.0, 100.0, 2.0, 1.7),
+		Atom::new(50.0, 50.0, 1.0, 1.7),
+	];
+	let default = GridParams::from_atoms(&atoms, 1.4, 1.0).unwrap();
+	let options = GridOptions { pad_z: Some(2.0), ..Default::default() };
+	let padded = GridParams::from_atoms_with_options(&atoms, 1.4, 1.0, &options).unwrap();
+	assert_eq!((default.len_i, default.len_j), (padded.len_i, padded.len_j));
+	assert_eq!(padded.zmin, -2.0);
+	assert_eq!(padded.len_k, 12);
+	assert!(padded.len_k < default.len_k);
+}
+
+#[test]
+fn memory_estimate_covers_bit_storage() {
+	let params = GridParams::from_box([0.0, 0.0, 0.0], [50.0, 40.0, 30.0], 0.5, false).unwrap();
+	let voxels = params.len_i * params.len_j * params.len_k;
+	let estimate = params.estimated_memory();
+	assert_eq!(estimate, estimate_grid_memory(params.len_i, params.len_j, params.len_k));
+	assert!(estimate >= voxels / 8);
+	assert!(estimate < voxels / 8 + 4096);
+}
+
+#[test]
+fn enclosure_check_finds_clipped_atoms() {
+	let atoms = vec![Atom::new(0.0, 0.0, 0.0, 1.6), Atom::new(5.0, 2.0, 1.0, 1.6), Atom::new(3.0, 4.0, -2.0, 1.5)];
+	let params = GridParams::from_atoms(&atoms, 1.4, 1.0).unwrap();
+	assert!(params.encloses(&atoms, 1.4));
+	let mut with_big = atoms.clone();
+	with_big.push(Atom::new(3.0, 3.0, 3.0, 50.0));
+	assert_eq!(params.first_atom_outside(&with_big, 1.4), Some(3));
+
+	assert!(!Grid3D::new(10, 10, 10, 1.0).encloses(&atoms, 1.4));
+	let mut grid = Grid3D::new(10, 10, 10, 1.0);
+	grid.x_shift = -5.0;
+	grid.y_shift = -5.0;
+	grid.z_shift = -5.0;
+	assert!(grid.encloses(&[Atom::new(0.0, 0.0, 0.0, 2.0)], 1.4));
+	assert_eq!(grid.first_atom_outside(&[Atom::new(0.0, 0.0, 0.0, 2.0), Atom::new(3.0, 0.0, 0.0, 1.0)], 1.4), Some(1));
+}
+
+#[test]
+fn minimal_dimensions_skip_multiple_of_four_rounding() {
+	let atoms = [Atom::new(0.0, 0.0, 0.0, 1.5), Atom::new(6.0, 0.0, 0.0, 1.5), Atom::new(0.0, 1.0, 1.0, 1.5)];
+	let options = GridOptions { pad_x: Some(2.0), pad_y: Some(2.0), pad_z: Some(2.0), minimal_dimensions: true };
+	let minimal = GridParams::from_atoms_with_options(&atoms, 0.0, 1.0, &options).unwrap();
+	assert_eq!(minimal.len_i, 10);
+	let aligned = GridOptions { minimal_dimensions: false, ..options };
+	let aligned = GridParams::from_atoms_with_options(&atoms, 0.0, 1.0, &aligned).unwrap();
+	assert!(aligned.len_i.is_multiple_of(4) && aligned.len_i > 10);
+	assert!(GridParams::from_atoms(&atoms, 1.4, 0.5).unwrap().len_i.is_multiple_of(4));
+}
+
+#[test]
+fn atom_summary_flags_suspect_inputs() {
+	let atoms = [
+		Atom::new(1.0, -2.0, 3.0, 1.5),
+		Atom::new(-4.0, 5.0, 0.5, 2.0),
+		Atom::new(0.0, 0.0, 0.0, 0.01),
+		Atom::new(f32::NAN, 0.0, 0.0, 1.0),
+	];
+	let summary = atom_summary(&atoms);
+	assert_eq!((summary.count, summary.non_finite, summary.small_radius, summary.at_origin), (4, 1, 1, 1));
+	assert_eq!((summary.min_xyz, summary.max_xyz), ([-4.0, -2.0, 0.0], [1.0, 5.0, 3.0]));
+	assert_eq!((summary.min_radius, summary.max_radius), (0.01, 2.0));
+	assert!((summary.mean_radius - (1.5 + 2.0 + 0.01f32 as f64) / 3.0).abs() < 1e-6);
+	assert!(!summary.mostly_small_radii());
+
+	let table_misses = [Atom::new(1.0, 1.0, 1.0, 0.01), Atom::new(2.0, 1.0, 1.0, 0.0), Atom::new(3.0, 1.0, 1.0, 1.7)];
+	assert!(atom_summary(&table_misses).mostly_small_radii());
+	assert_eq!(atom_summary(&[]), AtomSummary::default());
+}
+
+#[test]
+fn fit_atoms_builds_an_empty_enclosing_grid() {
+	let atoms = [Atom::new(10.0, -3.0, 7.0, 1.9), Atom::new(15.0, 2.0, 9.0, 1.5), Atom::new(12.0, 0.0, -4.0, 1.7)];
+	let mut grid = Grid3D::fit_atoms(&atoms, 1.4, 0.5).unwrap();
+	assert_eq!(grid.count_filled(), 0);
+	assert!(grid.encloses(&atoms, 1.4));
+	let params = GridParams::from_atoms(&atoms, 1.4, 0.5).unwrap();
+	assert_eq!((grid.len_i, grid.len_j, grid.len_k), (params.len_i, params.len_j, params.len_k));
+	assert!(grid.fill_accessible_parallel(&atoms, 1.4) > 0);
+	assert!(Grid3D::fit_atoms(&atoms[..2], 1.4, 0.5).is_none());
+}
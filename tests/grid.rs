@@ -0,0 +1,364 @@
+mod common;
+
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::{Atom, Downsample};
+
+#[test]
+fn physical_and_voxel_coordinates_round_trip() {
+	let mut grid = Grid3D::new(10, 10, 10, 0.7);
+	grid.x_shift = -3.1;
+	grid.y_shift = 2.0;
+	grid.z_shift = 11.3;
+	for (i, j, k) in [(0, 0, 0), (3, 4, 5), (9, 9, 9)] {
+		let (x, y, z) = grid.voxel_to_physical(i, j, k);
+		let (fi, fj, fk) = grid.physical_to_voxel(x, y, z);
+		assert!((fi - i as f32).abs() < 1e-4);
+		assert!((fj - j as f32).abs() < 1e-4);
+		assert!((fk - k as f32).abs() < 1e-4);
+		assert_eq!(grid.physical_to_ijk(x, y, z), Some((i, j, k)));
+	}
+	assert_eq!(grid.physical_to_ijk(-100.0, 0.0, 0.0), None);
+}
+
+#[test]
+fn clip_plane_keeps_one_half_space() {
+	let full = || {
+		let mut grid = Grid3D::new(10, 10, 10, 1.0);
+		grid.invert();
+		grid
+	};
+	let mut grid = full();
+	grid.clip_plane([1.0, 0.0, 0.0], -4.5);
+	assert_eq!(grid.count_filled(), 500);
+	assert!(grid.get_voxel_ijk(4, 0, 0));
+	assert!(!grid.get_voxel_ijk(5, 0, 0));
+
+	let mut grid = full();
+	grid.clip_plane([1.0, 0.0, 0.0], 100.0);
+	assert_eq!(grid.count_filled(), 0);
+	let mut grid = full();
+	grid.clip_plane([1.0, 0.0, 0.0], -100.0);
+	assert_eq!(grid.count_filled(), 1000);
+}
+
+#[test]
+fn mask_sphere_matches_sphere_volume() {
+	let mut grid = Grid3D::new(60, 60, 60, 0.5);
+	grid.x_shift = -15.0;
+	grid.y_shift = -15.0;
+	grid.z_shift = -15.0;
+	grid.invert();
+	let masked = grid.mask_sphere([0.0, 0.0, 0.0], 5.0);
+	let volume = masked.count_filled() as f64 * 0.125;
+	let analytic = 4.0 / 3.0 * std::f64::consts::PI * 125.0;
+	assert!((volume - analytic).abs() / analytic < 0.05);
+	// On a full grid the mask is exactly the rasterized sphere.
+	let mut sphere = grid.empty_like();
+	sphere.fill_accessible_parallel(&[Atom::new(0.0, 0.0, 0.0, 5.0)], 0.0);
+	assert_eq!(masked.to_u8_array(), sphere.to_u8_array());
+	assert_eq!(grid.mask_sphere([100.0, 0.0, 0.0], 2.0).count_filled(), 0);
+	assert_eq!(grid.mask_sphere([0.0, 0.0, 0.0], -1.0).count_filled(), 0);
+}
+
+#[test]
+fn memory_report_adds_up() {
+	let grid = Grid3D::new(40, 30, 20, 0.5);
+	let report = grid.memory_report();
+	assert_eq!(report.dimensions, (40, 30, 20));
+	assert_eq!(report.total_voxels, 24000);
+	assert_eq!(report.grid_size, 0.5);
+	assert!(report.bitvec_bytes >= 24000 / 8);
+	assert_eq!(report.total_bytes, report.struct_bytes + report.bitvec_bytes);
+}
+
+#[test]
+fn filled_count_cache_tracks_every_mutation() {
+	let mut grid = Grid3D::new(16, 16, 16, 1.0);
+	assert_eq!(grid.filled_count_cached(), 0);
+	let atoms = common::random_atoms(6, 4.0, 8.0, 99);
+	let mut rng = common::Lcg::new(7);
+	let mut pick = |n: usize| (rng.next_f32() * n as f32) as usize;
+
+	for _ in 0..60 {
+		match pick(12) {
+			0 => {
+				for _ in 0..200 {
+					let index = pick(grid.total_voxels);
+					grid.set_voxel_index(index, pick(2) == 1);
+				}
+			}
+			1 => grid.invert(),
+			2 => grid.zero_grid(),
+			3 => {
+				let indices: Vec<usize> = (0..50).map(|_| pick(grid.total_voxels)).collect();
+				grid.fill_indices(&indices).unwrap();
+			}
+			4 => {
+				let coords: Vec<(usize, usize, usize)> = (0..50).map(|_| (pick(16), pick(16), pick(16))).collect();
+				grid.fill_coords(&coords).unwrap();
+			}
+			5 => grid.add_sphere(pick(16), pick(16), pick(16), 3.0),
+			6 => grid.remove_sphere(pick(16), pick(16), pick(16), 3.0),
+			7 => {
+				grid.fill_accessible_parallel(&atoms, 1.4);
+			}
+			8 => {
+				grid.fill_accessible_into(&atoms, 1.4);
+			}
+			9 => {
+				grid.fill_accessible_or(&atoms[..2], 1.4);
+			}
+			10 => {
+				grid.fill_accessible_labeled(&atoms, 1.4);
+			}
+			_ => {
+				grid.contract_exclusion_parallel(1.4);
+			}
+		}
+		assert_eq!(grid.filled_count_cached(), grid.count_filled());
+	}
+	for mode in [Downsample::Majority, Downsample::VolumePreserving] {
+		grid.fill_accessible_supersampled(&atoms, 1.4, 2, mode);
+		assert_eq!(grid.filled_count_cached(), grid.count_filled());
+	}
+	grid.zero_grid();
+	assert_eq!(grid.filled_count_cached(), 0);
+}
+
+#[test]
+fn try_new_rejects_bad_grid_size() {
+	for size in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+		assert!(Grid3D::try_new(2, 2, 2, size).is_err());
+	}
+	assert!(Grid3D::try_new(2, 2, 2, 0.5).is_ok());
+}
+
+#[test]
+#[should_panic]
+fn new_panics_on_zero_grid_size() {
+	Grid3D::new(2, 2, 2, 0.0);
+}
+
+#[test]
+fn fill_indices_and_coords_set_exact_voxels() {
+	let mut grid = Grid3D::new(4, 5, 6, 1.0);
+	let indices = [0usize, 7, 7, 33, 119];
+	grid.fill_indices(&indices).unwrap();
+	assert_eq!(grid.count_filled(), 4);
+	for &idx in &indices {
+		assert!(grid.get_voxel_index(idx));
+	}
+	assert!(grid.fill_indices(&[1, 120]).is_err());
+	assert!(!grid.get_voxel_index(1));
+
+	grid.fill_coords(&[(3, 4, 5), (1, 1, 1)]).unwrap();
+	assert_eq!(grid.count_filled(), 5);
+	assert!(grid.fill_coords(&[(4, 0, 0)]).is_err());
+	assert_eq!(grid.filled_count_cached(), grid.count_filled());
+}
+
+#[test]
+fn box_and_filled_volumes_use_f64_cubes() {
+	let mut grid = Grid3D::new(4, 5, 6, 0.5);
+	assert_eq!(grid.box_volume(), 15.0);
+	grid.fill_indices(&[1, 2, 3]).unwrap();
+	assert_eq!(grid.filled_volume(), 0.375);
+	assert_eq!(Grid3D::new_anisotropic(2, 2, 2, [1.0, 2.0, 0.5]).box_volume(), 8.0);
+}
+
+#[test]
+fn with_shifts_sets_all_three_and_survives_empty_like() {
+	let grid = Grid3D::new(4, 4, 4, 0.5).with_shifts(1.0, -2.0, 3.5);
+	assert_eq!((grid.x_shift, grid.y_shift, grid.z_shift), (1.0, -2.0, 3.5));
+	assert_eq!(grid.voxel_to_physical(2, 0, 1), (2.0, -2.0, 4.0));
+	let empty = grid.empty_like();
+	assert_eq!((empty.x_shift, empty.y_shift, empty.z_shift), (1.0, -2.0, 3.5));
+}
+
+#[test]
+fn debug_and_display_summarize_without_bits() {
+	let mut grid = Grid3D::new(4, 4, 4, 0.5);
+	grid.fill_indices(&[0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+	assert_eq!(format!("{}", grid), "4x4x4 grid, 0.500 A voxels, 8 of 64 filled (12.50%)");
+	let debug = format!("{:?}", grid);
+	assert!(debug.starts_with("Grid3D { len_i: 4, len_j: 4, len_k: 4, grid_size: 0.5, x_shift: 0.0"));
+	assert!(debug.ends_with("total_voxels: 64, filled: 8 }"));
+	assert!(!debug.contains("spacing"));
+
+	let anisotropic = Grid3D::new_anisotropic(2, 3, 4, [0.5, 0.5, 1.0]);
+	assert!(format!("{:?}", anisotropic).contains("spacing: [0.5, 0.5, 1.0]"));
+	assert!(format!("{}", anisotropic).starts_with("2x3x4 grid, 0.500x0.500x1.000 A voxels, 0 of 24"));
+
+	let mut big = Grid3D::new(200, 200, 200, 0.5);
+	big.add_sphere(100, 100, 100, 10.0);
+	assert!(format!("{:?}", big).len() < 300);
+	assert!(format!("{}", big).len() < 120);
+}
+
+#[test]
+fn u8_array_round_trips() {
+	let mut grid = Grid3D::new(7, 5, 3, 0.5).with_shifts(1.0, 2.0, 3.0);
+	grid.add_sphere(3, 2, 1, 1.5);
+	grid.fill_voxel_ijk(6, 0, 0);
+	let bytes = grid.to_u8_array();
+	assert_eq!(bytes.len(), 105);
+	assert_eq!(bytes[6], 1);
+	let back = Grid3D::from_u8_array((7, 5, 3), 0.5, [1.0, 2.0, 3.0], &bytes).unwrap();
+	assert_eq!(back.to_u8_array(), bytes);
+	assert_eq!(back.count_filled(), grid.count_filled());
+	assert_eq!(back.voxel_to_physical(1, 1, 1), grid.voxel_to_physical(1, 1, 1));
+	assert!(Grid3D::from_u8_array((7, 5, 2), 0.5, [0.0; 3], &bytes).is_err());
+	assert!(Grid3D::from_u8_array((7, 5, 3), 0.0, [0.0; 3], &bytes).is_err());
+}
+
+#[test]
+fn sparsity_and_crop_to_content() {
+	let mut grid = Grid3D::new(10, 10, 10, 1.0).with_shifts(1.0, 2.0, 3.0);
+	assert_eq!(grid.sparsity(), 1.0);
+	assert!(grid.crop_to_content(1).is_none());
+	grid.fill_voxel_ijk(4, 5, 6);
+	assert!((grid.sparsity() - 0.999).abs() < 1e-12);
+	let cropped = grid.crop_to_content(1).unwrap();
+	assert_eq!((cropped.len_i, cropped.len_j, cropped.len_k), (3, 3, 3));
+	assert!(cropped.get_voxel_ijk(1, 1, 1));
+	assert_eq!(cropped.voxel_to_physical(1, 1, 1), grid.voxel_to_physical(4, 5, 6));
+	grid.invert();
+	grid.fill_voxel_ijk(4, 5, 6);
+	assert_eq!(grid.sparsity(), 0.0);
+}
+
+#[test]
+fn far_corner_coordinates_keep_f64_precision() {
+	let grid = Grid3D::new(2000, 1, 1, 0.3).with_shifts(-123.456, 0.0, 0.0);
+	let (x, _, _) = grid.voxel_to_physical(1999, 0, 0);
+	let exact = 1999.0 * 0.3f32 as f64 + (-123.456f32) as f64;
+	assert!((x as f64 - exact).abs() < 1e-4);
+	let (fi, _, _) = grid.physical_to_voxel(x, 0.0, 0.0);
+	assert!((fi - 1999.0).abs() < 1e-3);
+}
+
+#[test]
+fn checked_voxel_access_rejects_out_of_range() {
+	let mut grid = Grid3D::new(4, 3, 2, 1.0);
+	assert_eq!(grid.try_get_voxel_ijk(4, 0, 0), None);
+	assert_eq!(grid.try_get_voxel_ijk(0, 0, 2), None);
+	assert_eq!(grid.try_get_voxel_ijk(3, 2, 1), Some(false));
+	assert!(grid.try_set_voxel_ijk(4, 0, 0, true).is_err());
+	assert_eq!(grid.count_filled(), 0);
+	grid.try_set_voxel_ijk(3, 2, 1, true).unwrap();
+	assert_eq!(grid.try_get_voxel_ijk(3, 2, 1), Some(true));
+	// A bad coordinate anywhere in the list leaves the grid unchanged.
+	assert!(grid.fill_coords(&[(0, 0, 0), (0, 3, 0)]).is_err());
+	assert_eq!(grid.count_filled(), 1);
+}
+
+#[test]
+#[should_panic]
+fn unchecked_index_access_panics_out_of_range() {
+	let grid = Grid3D::new(4, 3, 2, 1.0);
+	grid.get_voxel_index(24);
+}
+
+#[test]
+fn export_iterators_visit_voxels_in_format_order() {
+	let (len_i, len_j, len_k) = (2, 3, 4);
+	for k in 0..len_k {
+		for j in 0..len_j {
+			for i in 0..len_i {
+				let mut grid = Grid3D::new(len_i, len_j, len_k, 1.0);
+				grid.fill_voxel_ijk(i, j, k);
+				let position = |order: Vec<bool>| order.iter().position(|&filled| filled).unwrap();
+				// MRC runs i fastest; DX and cube files run k fastest.
+				assert_eq!(position(grid.iter_mrc_order().collect()), i + len_i * j + len_i * len_j * k);
+				assert_eq!(position(grid.iter_dx_order().collect()), k + len_k * j + len_k * len_j * i);
+				assert_eq!(position(grid.iter_cube_order().collect()), position(grid.iter_dx_order().collect()));
+				assert_eq!(grid.iter_mrc_order().len(), 24);
+				assert_eq!(grid.iter_dx_order().count(), 24);
+			}
+		}
+	}
+}
+
+#[test]
+fn content_hash_tracks_bits_dimensions_and_shifts() {
+	let mut grid = Grid3D::new(8, 9, 10, 0.5).with_shifts(1.0, 2.0, 3.0);
+	grid.fill_voxel_ijk(1, 2, 3);
+	grid.fill_voxel_ijk(7, 8, 9);
+	assert_eq!(grid.content_hash(), grid.clone().content_hash());
+	let mut more = grid.clone();
+	more.fill_voxel_ijk(0, 0, 0);
+	assert_ne!(grid.content_hash(), more.content_hash());
+	assert_ne!(grid.content_hash(), grid.clone().with_shifts(1.0, 2.0, 3.5).content_hash());
+	assert_ne!(Grid3D::new(2, 3, 4, 1.0).content_hash(), Grid3D::new(3, 2, 4, 1.0).content_hash());
+	let rebuilt = Grid3D::from_u8_array((8, 9, 10), 0.5, [1.0, 2.0, 3.0], &grid.to_u8_array()).unwrap();
+	assert_eq!(rebuilt.content_hash(), grid.content_hash());
+}
+
+#[test]
+fn voxel_count_overflow_is_an_error() {
+	let big = usize::MAX / 2;
+	assert!(Grid3D::try_new(big, 3, 1, 1.0).is_err());
+	assert!(Grid3D::try_new_anisotropic(big, 1, 3, [1.0, 1.0, 2.0]).is_err());
+	assert!(std::panic::catch_unwind(|| Grid3D::new(big, 1, 4, 1.0)).is_err());
+	assert_eq!(Grid3D::try_new(4, 5, 6, 1.0).unwrap().total_voxels, 120);
+}
+
+#[test]
+fn tiled_grid_matches_monolithic_grid() {
+	let atoms = common::random_atoms(150, 4.0, 22.0, 692);
+	// 1000-voxel tiles do not line up with the 30x30 k-slabs, so fills and neighbors cross tile edges.
+	let mut whole = Grid3D::new(30, 30, 30, 1.0);
+	let mut tiled = Grid3D::new_tiled(30, 30, 30, 1.0, 1000);
+	assert_eq!((whole.tile_count(), tiled.tile_count()), (1, 27));
+	assert_eq!(tiled.tile_voxels(), 1000);
+
+	assert_eq!(whole.fill_accessible_parallel(&atoms, 1.4), tiled.fill_accessible_parallel(&atoms, 1.4));
+	assert_eq!(whole.to_u8_array(), tiled.to_u8_array());
+	assert_eq!(whole.contract_exclusion_parallel(1.4), tiled.contract_exclusion_parallel(1.4));
+	assert_eq!(whole.to_u8_array(), tiled.to_u8_array());
+	assert_eq!(whole.content_hash(), tiled.content_hash());
+	let mut threaded = Grid3D::new_tiled(30, 30, 30, 1.0, 1000);
+	threaded.fill_accessible_with_threads(&atoms, 1.4, 3);
+	threaded.contract_exclusion_with_threads(1.4, 5);
+	assert_eq!(threaded.to_u8_array(), whole.to_u8_array());
+	assert_eq!(whole.connected_components(false), tiled.connected_components(false));
+	assert_eq!(whole.interior_cavities(1).to_u8_array(), tiled.interior_cavities(1).to_u8_array());
+	assert_eq!(whole.estimate_surface_area_with_edges(), tiled.estimate_surface_area_with_edges());
+	assert_eq!(tiled.empty_like().tile_count(), 27);
+
+	assert_eq!(whole.fill_accessible_into(&atoms[..50], 0.0), tiled.fill_accessible_into(&atoms[..50], 0.0));
+	assert_eq!(whole.fill_accessible_or(&atoms[50..], 0.0), tiled.fill_accessible_or(&atoms[50..], 0.0));
+	assert_eq!(whole.to_u8_array(), tiled.to_u8_array());
+	whole.invert();
+	tiled.invert();
+	assert_eq!(whole.count_filled(), tiled.count_filled());
+	assert!(whole.iter_filled().eq(tiled.iter_filled()));
+
+	// Retiling keeps the voxels, and tile size 0 goes back to one allocation.
+	let retiled = whole.clone().with_tiles(4096);
+	assert_eq!(retiled.to_u8_array(), whole.to_u8_array());
+	assert_eq!(retiled.with_tiles(0).tile_count(), 1);
+}
+
+#[test]
+#[should_panic]
+fn tiled_grid_panics_past_the_last_voxel() {
+	Grid3D::new_tiled(4, 4, 4, 1.0, 10).get_voxel_index(64);
+}
+
+#[test]
+fn public_data_field_and_bits_cover_untiled_grids() {
+	let mut grid = Grid3D::new(4, 4, 4, 1.0);
+	grid.set_voxel_index(5, true);
+	assert!(grid.data[5]);
+	assert_eq!(grid.data.count_ones(), 1);
+	assert_eq!(grid.filled_count_cached(), 1);
+	assert_eq!(grid.bits().map(|bits| bits.count_ones()), Some(1));
+	grid.bits_mut().unwrap().set(9, true);
+	// Writes through `bits_mut` bypass the setters, so the cached count is recomputed.
+	assert_eq!(grid.filled_count_cached(), 2);
+	let tiled = grid.with_tiles(16);
+	assert!(tiled.bits().is_none());
+	assert!(tiled.data.is_empty());
+	assert_eq!(tiled.count_filled(), 2);
+}
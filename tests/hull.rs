@@ -0,0 +1,50 @@
+mod common;
+
+use std::f64::consts::PI;
+
+use voxel_sphere::voxel_grid::hull::convex_hull_volume;
+use voxel_sphere::voxel_grid::raster::Atom;
+
+fn at(x: f32, y: f32, z: f32) -> Atom {
+	Atom::new(x, y, z, 1.5)
+}
+
+#[test]
+fn hull_volume_of_simple_solids() {
+	let tetrahedron = [at(0.0, 0.0, 0.0), at(1.0, 0.0, 0.0), at(0.0, 1.0, 0.0), at(0.0, 0.0, 1.0)];
+	assert!((convex_hull_volume(&tetrahedron) - 1.0 / 6.0).abs() < 1e-9);
+	assert_eq!(convex_hull_volume(&tetrahedron[..3]), 0.0);
+	assert_eq!(convex_hull_volume(&[at(0.0, 0.0, 0.0), at(1.0, 0.0, 0.0), at(0.0, 1.0, 0.0), at(1.0, 1.0, 0.0)]), 0.0);
+
+	let cube: Vec<Atom> = (0..8)
+		.map(|c| at((c & 1) as f32 * 2.0 + 5.0, ((c >> 1) & 1) as f32 * 2.0, ((c >> 2) & 1) as f32 * 2.0 - 3.0))
+		.collect();
+	assert!((convex_hull_volume(&cube) - 8.0).abs() < 1e-9);
+	// Interior, face, and duplicate points do not change the hull.
+	let mut cluttered = cube.clone();
+	for i in 0..8 {
+		for j in 0..8 {
+			cluttered.push(at(5.0 + i as f32 * 0.25, j as f32 * 0.25, -3.0 + (i * j % 9) as f32 * 0.25));
+		}
+	}
+	cluttered.push(at(6.0, 1.0, -2.0));
+	cluttered.push(cube[3]);
+	assert!((convex_hull_volume(&cluttered) - 8.0).abs() < 1e-6);
+}
+
+#[test]
+fn hull_of_sphere_points_approaches_sphere_volume() {
+	let mut rng = common::Lcg::new(7);
+	let mut points = Vec::new();
+	while points.len() < 3000 {
+		let [x, y, z] = [0; 3].map(|_| rng.next_f32() * 2.0 - 1.0);
+		let norm = (x * x + y * y + z * z).sqrt();
+		if norm > 0.1 && norm <= 1.0 {
+			points.push(at(x / norm * 10.0, y / norm * 10.0, z / norm * 10.0));
+			points.push(at(x * 5.0, y * 5.0, z * 5.0));
+		}
+	}
+	let volume = convex_hull_volume(&points);
+	let analytic = 4.0 / 3.0 * PI * 1000.0;
+	assert!(volume < analytic && volume > 0.98 * analytic);
+}
@@ -0,0 +1,16 @@
+use voxel_sphere::voxel_grid::info::{citation_text, compile_info_text, is_verbose, print_citation, set_verbose};
+
+#[test]
+fn info_text_is_returned_and_printing_can_be_silenced() {
+	let citation = citation_text();
+	assert_eq!(citation.lines().count(), 3);
+	assert!(citation.lines().nth(1).unwrap().starts_with("DOI: http"));
+	assert!(citation.ends_with(">\n"));
+	assert!(compile_info_text().contains("Rust version"));
+
+	set_verbose(false);
+	assert!(!is_verbose());
+	print_citation();
+	set_verbose(true);
+	assert!(is_verbose());
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use voxel_sphere::voxel_grid::geometry::GridParams;
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::mesh::{
+	Triangle, enclosed_volume, molecular_surface_mesh, surface_area, vertex_normals, write_obj_with_normals, write_ply_mesh,
+};
+use voxel_sphere::voxel_grid::raster::Atom;
+
+/// Every directed edge is matched by its reverse in another triangle.
+fn is_closed(triangles: &[Triangle]) -> bool {
+	let mut edges: HashMap<([u32; 3], [u32; 3]), i32> = HashMap::new();
+	for tri in triangles {
+		for e in 0..3 {
+			let a = tri.vertices[e].map(f32::to_bits);
+			let b = tri.vertices[(e + 1) % 3].map(f32::to_bits);
+			*edges.entry((a, b)).or_insert(0) += 1;
+			*edges.entry((b, a)).or_insert(0) -= 1;
+		}
+	}
+	edges.values().all(|&count| count == 0)
+}
+
+#[test]
+fn single_atom_surface_is_closed_sphere() {
+	let atoms = [Atom::new(1.0, -2.0, 3.0, 3.0)];
+	let triangles = molecular_surface_mesh(&atoms, 1.4, 0.25).unwrap();
+	assert!(!triangles.is_empty());
+	assert!(is_closed(&triangles));
+	let volume = enclosed_volume(&triangles);
+	let analytic = 4.0 / 3.0 * PI * 27.0;
+	assert!((volume - analytic).abs() / analytic < 0.1);
+	let mut excluded = GridParams::from_atom_bounds(&atoms, 1.4, 0.25).unwrap().build_grid();
+	excluded.fill_excluded(&atoms, 1.4);
+	let voxel_volume = excluded.filled_volume();
+	assert!((volume - voxel_volume).abs() / voxel_volume < 0.05);
+	assert!(molecular_surface_mesh(&[], 1.4, 0.25).is_none());
+
+	let mut voxel = Grid3D::new(3, 3, 3, 1.0);
+	voxel.fill_voxel_ijk(1, 1, 1);
+	let triangles = voxel.isosurface_mesh();
+	assert!(is_closed(&triangles));
+	assert!(enclosed_volume(&triangles) > 0.0);
+}
+
+#[test]
+fn sphere_vertex_normals_point_outward_and_are_exported() {
+	let mut grid = Grid3D::new(40, 40, 40, 0.5);
+	grid.fill_accessible_parallel(&[Atom::new(10.0, 10.0, 10.0, 6.0)], 0.0);
+	let triangles = grid.isosurface_mesh();
+	let normals = vertex_normals(&triangles);
+	let mut sum = 0.0f64;
+	let mut min = 1.0f32;
+	let mut count = 0usize;
+	for (triangle, corner_normals) in triangles.iter().zip(normals.iter()) {
+		for (vertex, normal) in triangle.vertices.iter().zip(corner_normals.iter()) {
+			let radial = [vertex[0] - 10.0, vertex[1] - 10.0, vertex[2] - 10.0];
+			let length = (radial[0] * radial[0] + radial[1] * radial[1] + radial[2] * radial[2]).sqrt();
+			let cosine = (radial[0] * normal[0] + radial[1] * normal[1] + radial[2] * normal[2]) / length;
+			sum += cosine as f64;
+			min = min.min(cosine);
+			count += 1;
+		}
+	}
+	assert!(sum / count as f64 > 0.98);
+	assert!(min > 0.8);
+
+	let dir = std::env::temp_dir();
+	let obj_path = dir.join("voxel_sphere_normals.obj");
+	let ply_path = dir.join("voxel_sphere_normals.ply");
+	write_obj_with_normals(&triangles, obj_path.to_str().unwrap()).unwrap();
+	write_ply_mesh(&triangles, ply_path.to_str().unwrap(), true).unwrap();
+	let ply = std::fs::read_to_string(&ply_path).unwrap();
+	let header_count = |prefix: &str| -> usize {
+		ply.lines().find_map(|line| line.strip_prefix(prefix)).unwrap().trim().parse().unwrap()
+	};
+	let vertices = header_count("element vertex");
+	let faces = header_count("element face");
+	assert_eq!(faces, triangles.len());
+	// Euler characteristic of a closed sphere: V - E + F = 2 with E = 3F / 2.
+	assert_eq!(vertices as i64 - 3 * faces as i64 / 2 + faces as i64, 2);
+	let obj = std::fs::read_to_string(&obj_path).unwrap();
+	assert_eq!(obj.lines().filter(|line| line.starts_with("vn ")).count(), vertices);
+}
+
+/// Unit cube with corners `offset + {0, 1}^3` as 12 triangles wound counter-clockwise seen from outside.
+fn unit_cube(offset: [f32; 3]) -> Vec<Triangle> {
+	let corner = |c: usize| [(c & 1) as f32 + offset[0], ((c >> 1) & 1) as f32 + offset[1], ((c >> 2) & 1) as f32 + offset[2]];
+	let quads = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+	let mut triangles = Vec::new();
+	for q in quads {
+		triangles.push(Triangle { vertices: [corner(q[0]), corner(q[1]), corner(q[2])] });
+		triangles.push(Triangle { vertices: [corner(q[0]), corner(q[2]), corner(q[3])] });
+	}
+	triangles
+}
+
+/// Icosahedron of circumradius `radius` with each face split into 4 `levels` times, every new vertex
+/// pushed out onto the sphere; triangles wound counter-clockwise seen from outside.
+fn icosphere(radius: f64, levels: usize) -> Vec<Triangle> {
+	let t = (1.0 + 5.0f64.sqrt()) / 2.0;
+	let on_sphere = |v: [f64; 3]| -> [f64; 3] {
+		let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+		v.map(|x| x * radius / norm)
+	};
+	let corners: Vec<[f64; 3]> = [
+		[-1.0, t, 0.0], [1.0, t, 0.0], [-1.0, -t, 0.0], [1.0, -t, 0.0],
+		[0.0, -1.0, t], [0.0, 1.0, t], [0.0, -1.0, -t], [0.0, 1.0, -t],
+		[t, 0.0, -1.0], [t, 0.0, 1.0], [-t, 0.0, -1.0], [-t, 0.0, 1.0],
+	]
+	.into_iter()
+	.map(on_sphere)
+	.collect();
+	let faces = [
+		[0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+		[1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+		[3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+		[4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+	];
+	let mut tris: Vec<[[f64; 3]; 3]> = faces.iter().map(|f| f.map(|c| corners[c])).collect();
+	for _ in 0..levels {
+		let mid = |a: [f64; 3], b: [f64; 3]| on_sphere([a[0] + b[0], a[1] + b[1], a[2] + b[2]]);
+		tris = tris
+			.iter()
+			.flat_map(|&[a, b, c]| {
+				let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+				[[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+			})
+			.collect();
+	}
+	tris.iter().map(|tri| Triangle { vertices: tri.map(|v| v.map(|x| x as f32)) }).collect()
+}
+
+#[test]
+fn mesh_area_of_cube_and_refined_sphere() {
+	assert!((surface_area(&unit_cube([0.0; 3])) - 6.0).abs() < 1e-9);
+	let radius = 5.0;
+	let analytic = 4.0 * PI * radius * radius;
+	let mut previous_error = f64::INFINITY;
+	for levels in 0..6 {
+		let mesh = icosphere(radius, levels);
+		assert!(enclosed_volume(&mesh) > 0.0, "icosphere must be wound outward");
+		let error = (surface_area(&mesh) - analytic).abs() / analytic;
+		assert!(error < previous_error, "level {levels}: relative error {error} did not shrink");
+		previous_error = error;
+	}
+	assert!(previous_error < 1e-3, "relative error {previous_error}");
+}
+
+#[test]
+fn enclosed_volume_of_cube_and_voxel_sphere() {
+	let cube = unit_cube([3.0, -2.0, 7.0]);
+	assert!((enclosed_volume(&cube) - 1.0).abs() < 1e-9);
+	let mut grid = Grid3D::new(30, 30, 30, 0.5).with_shifts(-3.0, 2.0, 1.0);
+	grid.fill_accessible_parallel(&[Atom::new(4.5, 9.5, 8.5, 5.0)], 0.0);
+	let mesh_volume = enclosed_volume(&grid.isosurface_mesh());
+	let voxel_volume = grid.filled_volume();
+	assert!((mesh_volume - voxel_volume).abs() / voxel_volume < 0.03);
+}
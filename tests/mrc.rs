@@ -0,0 +1,137 @@
+mod common;
+
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::mrc_output::{MrcOrigin, write_multi_mrc};
+
+/// Little-endian i32 at 4-byte word `word` of an MRC header.
+fn header_i32(bytes: &[u8], word: usize) -> i32 {
+	i32::from_le_bytes(bytes[word * 4..word * 4 + 4].try_into().unwrap())
+}
+
+/// `xorigin`, `yorigin`, `zorigin` (words 49-51) of an MRC header.
+fn header_origin(bytes: &[u8]) -> (f32, f32, f32) {
+	let word = |n: usize| f32::from_le_bytes(bytes[n * 4..n * 4 + 4].try_into().unwrap());
+	(word(49), word(50), word(51))
+}
+
+#[test]
+fn start_indices_follow_grid_shifts() {
+	let mut grid = Grid3D::new(4, 4, 4, 0.5);
+	grid.x_shift = -10.0;
+	grid.y_shift = 2.0;
+	grid.z_shift = 0.24;
+	let path = std::env::temp_dir().join("voxel_sphere_mrc_start.mrc");
+	grid.write_to_mrc_file(path.to_str().unwrap());
+	let bytes = std::fs::read(&path).unwrap();
+	assert_eq!(bytes.len(), 1024 + 64);
+	let start = (header_i32(&bytes, 4), header_i32(&bytes, 5), header_i32(&bytes, 6));
+	assert_eq!(start, (-20, 4, 0));
+}
+
+#[test]
+fn axis_order_permutes_header_and_voxel_order() {
+	let mut grid = Grid3D::new(4, 3, 2, 1.0);
+	grid.fill_voxel_ijk(3, 0, 0);
+	grid.fill_voxel_ijk(0, 2, 1);
+	grid.fill_voxel_ijk(1, 1, 0);
+	let dir = std::env::temp_dir();
+	let default_path = dir.join("voxel_sphere_mrc_axes_default.mrc");
+	let permuted_path = dir.join("voxel_sphere_mrc_axes_permuted.mrc");
+	grid.write_to_mrc_file(default_path.to_str().unwrap());
+	grid.write_to_mrc_file_with_axes(permuted_path.to_str().unwrap(), [3, 1, 2]).unwrap();
+	let default_bytes = std::fs::read(&default_path).unwrap();
+	let permuted_bytes = std::fs::read(&permuted_path).unwrap();
+
+	let counts = (header_i32(&permuted_bytes, 0), header_i32(&permuted_bytes, 1), header_i32(&permuted_bytes, 2));
+	assert_eq!(counts, (2, 4, 3));
+	let axes = (header_i32(&permuted_bytes, 16), header_i32(&permuted_bytes, 17), header_i32(&permuted_bytes, 18));
+	assert_eq!(axes, (3, 1, 2));
+
+	let default_data = &default_bytes[1024..];
+	let permuted_data = &permuted_bytes[1024..];
+	for k in 0..2 {
+		for j in 0..3 {
+			for i in 0..4 {
+				let value = grid.get_voxel_ijk(i, j, k) as u8;
+				assert_eq!(default_data[i + 4 * j + 12 * k], value);
+				// columns run along k, rows along i, sections along j
+				assert_eq!(permuted_data[k + 2 * i + 8 * j], value);
+			}
+		}
+	}
+	assert!(grid.write_to_mrc_file_with_axes(permuted_path.to_str().unwrap(), [1, 1, 2]).is_err());
+}
+
+#[test]
+fn anisotropic_grid_round_trips_with_cell_lengths() {
+	let mut grid = Grid3D::new_anisotropic(10, 12, 14, [2.0, 1.0, 1.0]);
+	grid.x_shift = -4.0;
+	grid.y_shift = 3.0;
+	grid.z_shift = 1.0;
+	grid.fill_voxel_ijk(1, 2, 3);
+	grid.fill_voxel_ijk(9, 11, 13);
+	let path = std::env::temp_dir().join("voxel_sphere_mrc_aniso.mrc");
+	grid.write_to_mrc_file_with_axes(path.to_str().unwrap(), [3, 1, 2]).unwrap();
+	let back = Grid3D::read_mrc_file(path.to_str().unwrap()).unwrap();
+	assert_eq!(back.spacing(), [2.0, 1.0, 1.0]);
+	assert!(back.is_anisotropic());
+	assert_eq!((back.len_i, back.len_j, back.len_k), (10, 12, 14));
+	assert_eq!((back.x_shift, back.y_shift, back.z_shift), (-4.0, 3.0, 1.0));
+	assert_eq!(common::voxels(&back), common::voxels(&grid));
+
+	grid.write_to_mrc_file(path.to_str().unwrap());
+	let bytes = std::fs::read(&path).unwrap();
+	let word = |n: usize| f32::from_le_bytes(bytes[n * 4..n * 4 + 4].try_into().unwrap());
+	assert_eq!((word(10), word(11), word(12)), (20.0, 12.0, 14.0));
+}
+
+#[test]
+fn origin_conventions_set_origin_and_start_words() {
+	let mut grid = Grid3D::new(4, 6, 8, 2.0);
+	grid.x_shift = 10.0;
+	grid.y_shift = -4.0;
+	grid.z_shift = 3.0;
+	let dir = std::env::temp_dir();
+	let center = dir.join("voxel_sphere_mrc_origin_center.mrc");
+	let corner = dir.join("voxel_sphere_mrc_origin_corner.mrc");
+	let start_only = dir.join("voxel_sphere_mrc_origin_start.mrc");
+
+	grid.write_to_mrc_file_with_origin(center.to_str().unwrap(), [1, 2, 3], MrcOrigin::Center).unwrap();
+	let bytes = std::fs::read(&center).unwrap();
+	assert_eq!(header_origin(&bytes), (10.0, -4.0, 3.0));
+	assert_eq!((header_i32(&bytes, 4), header_i32(&bytes, 5), header_i32(&bytes, 6)), (5, -2, 2));
+
+	grid.write_to_mrc_file_with_origin(corner.to_str().unwrap(), [1, 2, 3], MrcOrigin::Corner).unwrap();
+	let bytes = std::fs::read(&corner).unwrap();
+	assert_eq!(header_origin(&bytes), (9.0, -5.0, 2.0));
+	assert_eq!((header_i32(&bytes, 4), header_i32(&bytes, 5), header_i32(&bytes, 6)), (5, -2, 2));
+
+	// Start indices follow the column/row/section order; z = 3.0 rounds to start 2 (4.0 A).
+	grid.write_to_mrc_file_with_origin(start_only.to_str().unwrap(), [3, 1, 2], MrcOrigin::StartOnly).unwrap();
+	let bytes = std::fs::read(&start_only).unwrap();
+	assert_eq!(header_origin(&bytes), (0.0, 0.0, 0.0));
+	assert_eq!((header_i32(&bytes, 4), header_i32(&bytes, 5), header_i32(&bytes, 6)), (2, 5, -2));
+
+	let back = Grid3D::read_mrc_file(center.to_str().unwrap()).unwrap();
+	assert_eq!((back.x_shift, back.y_shift, back.z_shift), (10.0, -4.0, 3.0));
+	let back = Grid3D::read_mrc_file(start_only.to_str().unwrap()).unwrap();
+	assert_eq!((back.x_shift, back.y_shift, back.z_shift), (10.0, -4.0, 4.0));
+	assert_eq!((back.len_i, back.len_j, back.len_k), (4, 6, 8));
+}
+
+#[test]
+fn multi_mrc_writes_one_numbered_map_per_grid() {
+	let mut a = Grid3D::new(4, 5, 6, 1.0);
+	a.fill_voxel_ijk(1, 2, 3);
+	let b = Grid3D::new(7, 3, 2, 0.5);
+	let c = Grid3D::new(2, 2, 9, 2.0);
+	let dir = std::env::temp_dir().join("voxel_sphere_multi_mrc");
+	let paths = write_multi_mrc(&[&a, &b, &c], dir.to_str().unwrap()).unwrap();
+	assert_eq!(paths.len(), 3);
+	assert!(paths[1].ends_with("volume_001.mrc"));
+	for (path, grid) in paths.iter().zip([&a, &b, &c]) {
+		let back = Grid3D::read_mrc_file(path).unwrap();
+		assert_eq!((back.len_i, back.len_j, back.len_k), (grid.len_i, grid.len_j, grid.len_k));
+		assert_eq!(back.to_u8_array(), grid.to_u8_array());
+	}
+}
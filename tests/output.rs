@@ -0,0 +1,62 @@
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::ply_output::write_surface_ply;
+use voxel_sphere::voxel_grid::surface_area::classify_edge_point;
+
+#[test]
+fn ply_writes_one_vertex_per_surface_voxel() {
+	let mut grid = Grid3D::new(20, 20, 20, 1.0);
+	grid.add_sphere(10, 10, 10, 5.0);
+	let surface = (0..grid.total_voxels)
+		.filter(|&idx| grid.get_voxel_index(idx) && classify_edge_point(&grid, idx) != 0)
+		.count();
+
+	let path = std::env::temp_dir().join("voxel_sphere_surface.ply");
+	write_surface_ply(&grid, path.to_str().unwrap(), true).unwrap();
+	let text = std::fs::read_to_string(&path).unwrap();
+	let header_count: usize = text
+		.lines()
+		.find_map(|line| line.strip_prefix("element vertex "))
+		.unwrap()
+		.parse()
+		.unwrap();
+	let body = text.lines().skip_while(|line| *line != "end_header").skip(1).count();
+	assert_eq!(header_count, surface);
+	assert_eq!(body, surface);
+	assert!(grid.surface_normal(15, 10, 10)[0] > 0.9);
+}
+
+#[test]
+fn ezd_header_and_body_match_the_grid() {
+	let mut grid = Grid3D::new(5, 4, 3, 0.5).with_shifts(-2.0, 1.0, 0.5);
+	grid.fill_voxel_ijk(1, 2, 1);
+	let path = std::env::temp_dir().join("voxel_sphere_grid.ezd");
+	grid.write_to_ezd_file(path.to_str().unwrap()).unwrap();
+	let text = std::fs::read_to_string(&path).unwrap();
+	assert!(text.contains("EXTENT 5 4 3\n"));
+	assert!(text.contains("GRID 5 4 3\n"));
+	assert!(text.contains("ORIGIN -4 2 1\n"));
+	assert!(text.contains("CELL 2.500 2.000 1.500 "));
+	let body: Vec<&str> = text.split("\nMAP\n").nth(1).unwrap().split("END").next().unwrap().split_whitespace().collect();
+	assert_eq!(body.len(), grid.total_voxels);
+	assert_eq!(body[grid.ijk_to_index(1, 2, 1)], "1");
+	assert_eq!(body.iter().filter(|&&value| value == "1").count(), 1);
+	assert!(text.ends_with("END\n"));
+}
+
+#[test]
+fn vtk_structured_points_header_and_body() {
+	let mut grid = Grid3D::new_anisotropic(5, 4, 3, [0.5, 0.5, 1.0]).with_shifts(-2.0, 1.25, 0.5);
+	grid.fill_voxel_ijk(1, 2, 1);
+	let path = std::env::temp_dir().join("voxel_sphere_grid.vtk");
+	grid.write_to_vtk_file(path.to_str().unwrap()).unwrap();
+	let text = std::fs::read_to_string(&path).unwrap();
+	assert!(text.contains("\nDATASET STRUCTURED_POINTS\n"));
+	assert!(text.contains("\nDIMENSIONS 5 4 3\n"));
+	assert!(text.contains("\nSPACING 0.5 0.5 1\n"));
+	assert!(text.contains("\nORIGIN -2 1.25 0.5\n"));
+	assert!(text.contains("\nPOINT_DATA 60\n"));
+	let body: Vec<&str> = text.split("LOOKUP_TABLE default\n").nth(1).unwrap().split_whitespace().collect();
+	assert_eq!(body.len(), grid.total_voxels);
+	assert_eq!(body[grid.ijk_to_index(1, 2, 1)], "1");
+	assert_eq!(body.iter().filter(|&&value| value == "1").count(), 1);
+}
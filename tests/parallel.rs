@@ -0,0 +1,171 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use voxel_sphere::voxel_grid::float_grid::FloatGrid3D;
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::parallel::{set_thread_limit, worker_threads};
+use voxel_sphere::voxel_grid::raster::Atom;
+
+#[test]
+fn thread_limit_overrides_worker_count() {
+	let atoms = [Atom::new(4.0, 4.0, 4.0, 1.7), Atom::new(6.0, 5.0, 4.5, 1.5)];
+
+	set_thread_limit(1);
+	assert_eq!(worker_threads(), 1);
+	let mut serial = Grid3D::new(24, 24, 24, 0.5);
+	let serial_count = serial.fill_accessible_parallel(&atoms, 1.4);
+
+	set_thread_limit(4);
+	assert_eq!(worker_threads(), 4);
+	let mut threaded = Grid3D::new(24, 24, 24, 0.5);
+	let threaded_count = threaded.fill_accessible_parallel(&atoms, 1.4);
+
+	set_thread_limit(0);
+	assert!(worker_threads() >= 1);
+	assert_eq!(serial_count, threaded_count);
+	assert_eq!(common::voxels(&serial), common::voxels(&threaded));
+}
+
+#[test]
+fn explicit_thread_counts_give_identical_grids() {
+	let atoms = common::random_atoms(200, 5.0, 20.0, 12345);
+	let mut baseline: Option<(usize, Vec<bool>, usize, Vec<bool>)> = None;
+	for threads in [1, 2, 8] {
+		let mut grid = Grid3D::new(32, 32, 32, 1.0);
+		let accessible = grid.fill_accessible_with_threads(&atoms, 1.4, threads);
+		let accessible_data = common::voxels(&grid);
+		let excluded = grid.contract_exclusion_with_threads(1.4, threads);
+		let excluded_data = common::voxels(&grid);
+		assert!(accessible > excluded && excluded > 0);
+		match &baseline {
+			Some(b) => {
+				assert_eq!(b.0, accessible);
+				assert_eq!(b.1, accessible_data);
+				assert_eq!(b.2, excluded);
+				assert_eq!(b.3, excluded_data);
+			}
+			None => baseline = Some((accessible, accessible_data, excluded, excluded_data)),
+		}
+	}
+}
+
+#[test]
+fn block_parallel_labels_match_serial_labels() {
+	let mut rng = common::Lcg::new(7);
+	for (n, density) in [(17usize, 0.3f32), (23, 0.5), (9, 0.6), (31, 0.45)] {
+		let mut grid = Grid3D::new(n, n + 3, n - 2, 1.0);
+		for idx in 0..grid.total_voxels {
+			if rng.next_f32() < density {
+				grid.fill_voxel_index(idx);
+			}
+		}
+		for value in [true, false] {
+			let serial = grid.connected_components(value);
+			for threads in [1, 2, 3, 4, 7, 64] {
+				assert_eq!(grid.connected_components_with_threads(value, threads), serial, "n {n} threads {threads}");
+			}
+		}
+	}
+}
+
+/// True if two label arrays group the voxels identically: label 0 matches only label 0 and
+/// every other label maps one-to-one onto a label of the other array.
+fn same_partition(a: &[u32], b: &[u32]) -> bool {
+	let mut forward: HashMap<u32, u32> = HashMap::new();
+	let mut backward: HashMap<u32, u32> = HashMap::new();
+	a.len() == b.len()
+		&& a.iter().zip(b).all(|(&x, &y)| {
+			(x == 0) == (y == 0) && *forward.entry(x).or_insert(y) == y && *backward.entry(y).or_insert(x) == x
+		})
+}
+
+#[test]
+fn parallel_labels_partition_like_serial_labels() {
+	// Spheres, a ring, and single voxels spread over several k-slabs so components cross block boundaries.
+	let mut grid = Grid3D::new(40, 36, 48, 0.5);
+	grid.add_sphere(8, 8, 8, 5.0);
+	grid.add_sphere(28, 20, 30, 7.0);
+	grid.add_sphere(30, 8, 42, 3.0);
+	for k in 0..48 {
+		grid.fill_voxel_ijk(36, 30, k);
+	}
+	for j in 5..31 {
+		grid.fill_voxel_ijk(2, j, 24);
+	}
+	grid.fill_voxel_ijk(0, 0, 47);
+	grid.fill_voxel_ijk(39, 35, 0);
+
+	for value in [true, false] {
+		let (serial, serial_count) = grid.connected_components(value);
+		if value {
+			assert_eq!(serial_count, 7);
+		}
+		for threads in [1, 2, 5, 16] {
+			let (labels, count) = grid.connected_components_with_threads(value, threads);
+			assert_eq!(count, serial_count, "threads {threads}");
+			assert!(same_partition(&labels, &serial), "threads {threads}");
+		}
+	}
+	let mut relabeled = grid.connected_components(true).0;
+	for label in relabeled.iter_mut().filter(|label| **label > 0) {
+		*label = 8 - *label;
+	}
+	assert!(same_partition(&relabeled, &grid.connected_components(true).0));
+	relabeled[grid.ijk_to_index(0, 0, 47)] = relabeled[grid.ijk_to_index(39, 35, 0)];
+	assert!(!same_partition(&relabeled, &grid.connected_components(true).0));
+}
+
+/// Serial vs parallel flood-fill timing on a 256^3 grid; run with
+/// `cargo test --release --test parallel -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn benchmark_serial_vs_parallel_components() {
+	let n = 256;
+	let mut grid = Grid3D::new(n, n, n, 1.0);
+	// Overlapping random spheres give a mix of large and small components of both filled and empty voxels.
+	let mut rng = common::Lcg::new(636);
+	for _ in 0..4000 {
+		let at = |rng: &mut common::Lcg| 4 + (rng.next_f32() * (n - 8) as f32) as usize;
+		let (i, j, k) = (at(&mut rng), at(&mut rng), at(&mut rng));
+		grid.add_sphere(i, j, k, 2.0 + 3.0 * rng.next_f32() as f64);
+	}
+	for value in [true, false] {
+		let start = Instant::now();
+		let serial = grid.connected_components(value);
+		let serial_secs = start.elapsed().as_secs_f64();
+		println!("value {value}: serial BFS {serial_secs:.3} s, {} regions", serial.1);
+		for threads in [1, 2, 4, 8, worker_threads()] {
+			let start = Instant::now();
+			let parallel = grid.connected_components_with_threads(value, threads);
+			let secs = start.elapsed().as_secs_f64();
+			println!("value {value}: {threads} threads {secs:.3} s ({:.2}x)", serial_secs / secs);
+			assert_eq!(parallel, serial, "threads {threads}");
+		}
+	}
+}
+
+/// Serial vs threaded Gaussian blur timing on a 256^3 float grid; run with
+/// `cargo test --release --test parallel -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn benchmark_serial_vs_threaded_blur() {
+	let n = 256;
+	let mut grid = FloatGrid3D::new(n, n, n, 1.0);
+	let mut rng = common::Lcg::new(690);
+	for value in grid.data.iter_mut() {
+		*value = rng.next_f32();
+	}
+	let start = Instant::now();
+	let serial = grid.gaussian_blur_with_threads(1.5, 1);
+	let serial_secs = start.elapsed().as_secs_f64();
+	println!("blur sigma 1.5: 1 thread {serial_secs:.3} s");
+	for threads in [2, 4, 8, worker_threads()] {
+		let start = Instant::now();
+		let threaded = grid.gaussian_blur_with_threads(1.5, threads);
+		let secs = start.elapsed().as_secs_f64();
+		println!("blur sigma 1.5: {threads} threads {secs:.3} s ({:.2}x)", serial_secs / secs);
+		assert_eq!(threaded.data, serial.data, "threads {threads}");
+	}
+}
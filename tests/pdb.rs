@@ -0,0 +1,325 @@
+use voxel_sphere::voxel_grid::analyze::volume_trajectory;
+use voxel_sphere::voxel_grid::pdb::*;
+
+/// Fixed-column ATOM record for a carbon in alanine `serial` of chain A.
+fn atom_line(serial: usize, x: f32, y: f32, z: f32) -> String {
+	format!(
+		"ATOM  {:>5}  C   ALA A{:>4}    {:>8.3}{:>8.3}{:>8.3}  1.00  0.00           C\n",
+		serial, serial, x, y, z
+	)
+}
+
+#[test]
+fn models_load_separately_and_give_a_volume_trajectory() {
+	let mut text = String::from("MODEL        1\n");
+	for (n, x) in [0.0f32, 1.0, 2.0].into_iter().enumerate() {
+		text += &atom_line(n + 1, x, 0.0, 0.0);
+	}
+	text += "ENDMDL\nMODEL        2\n";
+	for (n, x) in [0.0f32, 10.0, 20.0, 30.0].into_iter().enumerate() {
+		text += &atom_line(n + 1, x, 0.0, 0.0);
+	}
+	text += "ENDMDL\nEND\n";
+
+	let options = PdbOptions::default();
+	let models = load_models_from_reader(text.as_bytes(), &options).unwrap();
+	assert_eq!(models.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 4]);
+	assert_eq!(load_atoms_from_reader(text.as_bytes(), &options).unwrap().len(), 7);
+
+	let path = std::env::temp_dir().join("voxel_sphere_trajectory.pdb");
+	std::fs::write(&path, &text).unwrap();
+	let volumes = volume_trajectory(path.to_str().unwrap(), &options, 0.0, 0.5).unwrap();
+	let radius = models[0][0].radius as f64;
+	let four_spheres = 4.0 * 4.0 / 3.0 * std::f64::consts::PI * radius.powi(3);
+	assert_eq!(volumes.len(), 2);
+	assert!((volumes[1] - four_spheres).abs() / four_spheres < 0.1);
+	assert!(volumes[1] > volumes[0]);
+}
+
+#[test]
+fn radii_can_come_from_bfactor_or_occupancy() {
+	let text = "\
+ATOM      1  C   ALA A   1       0.000   0.000   0.000  1.00  2.50           C
+ATOM      2  C   ALA A   1       1.000   0.000   0.000  0.80  0.00           C
+ATOM      3  C   ALA A   1       2.000   0.000   0.000  1.00                 C
+";
+	let radii = |source: RadiusSource| -> Vec<f32> {
+		let options = PdbOptions { radius_source: source, ..PdbOptions::default() };
+		load_atoms_from_reader(text.as_bytes(), &options).unwrap().iter().map(|a| a.radius).collect()
+	};
+	let table = radii(RadiusSource::Table)[0];
+	assert_eq!(radii(RadiusSource::BFactorColumn), vec![2.5, table, table]);
+	assert_eq!(radii(RadiusSource::OccupancyColumn), vec![1.0, 0.8, 1.0]);
+
+	let xyzr_radii = |source: RadiusSource| -> Vec<String> {
+		let options = PdbOptions { radius_source: source, ..PdbOptions::default() };
+		let mut xyzr = Vec::new();
+		write_xyzr_from_reader(text.as_bytes(), &options, &mut xyzr).unwrap();
+		let xyzr = String::from_utf8(xyzr).unwrap();
+		xyzr.lines().map(|line| line.split_whitespace().last().unwrap().to_string()).collect()
+	};
+	let table_text = xyzr_radii(RadiusSource::Table)[0].clone();
+	assert_eq!(xyzr_radii(RadiusSource::BFactorColumn), vec!["2.50".to_string(), table_text.clone(), table_text]);
+}
+
+#[test]
+fn bundled_radius_table_compiles() {
+	check_radius_table().unwrap();
+	assert!(radius_table_bad_patterns().is_empty());
+	let options = PdbOptions { strict_radius_table: true, ..PdbOptions::default() };
+	let line = "ATOM      1  C   ALA A   1       0.000   0.000   0.000  1.00  0.00           C\n";
+	assert_eq!(load_atoms_from_reader(line.as_bytes(), &options).unwrap().len(), 1);
+}
+
+#[test]
+fn hg_hydrogens_are_not_mercury() {
+	let record = |name: &str, residue: &str, element: &str| {
+		format!(
+			"ATOM      1 {:<4} {:>3} A   1       0.000   0.000   0.000  1.00  0.00          {:>2}\n",
+			name, residue, element
+		)
+	};
+	let options = PdbOptions { use_united: false, ..PdbOptions::default() };
+	let radius = |line: &str| load_atoms_from_reader(line.as_bytes(), &options).unwrap()[0].radius;
+
+	for (name, residue, element) in [
+		("HG21", "VAL", "H"),
+		("HG21", "VAL", ""),
+		(" HG ", "SER", "H"),
+		("1HG ", "LEU", ""),
+		(" HG1", "THR", "H"),
+	] {
+		let hydrogen = radius(&record(" H  ", residue, "H"));
+		assert_eq!(radius(&record(name, residue, element)), hydrogen, "{name}");
+	}
+	let mercury = radius(&record("HG  ", " HG", "HG"));
+	assert_ne!(mercury, radius(&record(" H  ", "SER", "H")));
+	assert_eq!(radius(&record("HG  ", " HG", "")), mercury);
+
+	// The element column wins over the digit heuristic: HG1 with element HG is mercury.
+	let hetatm = "HETATM    1 HG1   HG A   1       0.000   0.000   0.000  1.00  0.00          HG\n";
+	assert_ne!(radius(hetatm), radius(&record(" H  ", "SER", "H")));
+	assert_eq!(radius(hetatm), mercury);
+}
+
+#[test]
+fn two_letter_metals_are_guessed_from_atom_names() {
+	let options = PdbOptions {
+		filters: Filters { exclude_ions: true, ..Filters::default() },
+		..PdbOptions::default()
+	};
+	let count = |line: &str| load_atoms_from_reader(line.as_bytes(), &options).unwrap().len();
+	// Blank element columns: zinc and calcium left-justified in column 13 are ions, an alpha carbon is not.
+	assert_eq!(count("HETATM    1 ZN   XYZ A   1       0.000   0.000   0.000  1.00  0.00            \n"), 0);
+	assert_eq!(count("HETATM    1 CA   XYZ A   1       0.000   0.000   0.000  1.00  0.00            \n"), 0);
+	assert_eq!(count("HETATM    1  CA  XYZ A   1       0.000   0.000   0.000  1.00  0.00            \n"), 1);
+}
+
+#[test]
+fn streaming_loader_yields_non_empty_models() {
+	let text = format!(
+		"MODEL 1\n{}{}ENDMDL\nMODEL 2\n{}ENDMDL\nMODEL 3\nENDMDL\nMODEL 4\n{}{}{}ENDMDL\n",
+		atom_line(1, 0.0, 0.0, 0.0),
+		atom_line(2, 1.0, 0.0, 0.0),
+		atom_line(1, 2.0, 0.0, 0.0),
+		atom_line(1, 3.0, 0.0, 0.0),
+		atom_line(2, 4.0, 0.0, 0.0),
+		atom_line(3, 5.0, 0.0, 0.0),
+	);
+	let model_sizes = |text: &str| -> Vec<usize> {
+		load_atoms_streaming(text.as_bytes(), &PdbOptions::default())
+			.unwrap()
+			.map(|model| model.unwrap().len())
+			.collect()
+	};
+	assert_eq!(model_sizes(&text), vec![2, 1, 3]);
+	assert_eq!(model_sizes(&atom_line(1, 0.0, 0.0, 0.0)), vec![1]);
+}
+
+#[test]
+fn residue_classification_is_reported() {
+	let text = "\
+ATOM      1  C   ALA A   1       0.000   0.000   0.000  1.00  0.00           C
+HETATM    2  O   HOH A   2       1.000   0.000   0.000  1.00  0.00           O
+HETATM    3 ZN    ZN A   3       2.000   0.000   0.000  1.00  0.00          ZN
+HETATM    4  C1  LIG A   4       3.000   0.000   0.000  1.00  0.00           C
+HETATM    5  C2  LIG A   4       3.000   0.000   0.000  1.00  0.00           C
+";
+	let options = PdbOptions {
+		filters: Filters { exclude_hetatm: true, ..Filters::default() },
+		..PdbOptions::default()
+	};
+	let residues = classify_pdb_reader(text.as_bytes(), &options).unwrap();
+	assert_eq!(residues.len(), 4);
+	assert!(residues[0].is_amino && residues[0].kept);
+	assert!(residues[1].is_water && !residues[1].kept);
+	assert!(residues[2].is_ion);
+	assert!(residues[3].is_ligand && residues[3].hetatm_only && !residues[3].kept);
+}
+
+#[test]
+fn exclude_hetatm_keeps_polymer_mse() {
+	let text = "\
+ATOM      1  C   ALA A   1       0.000   0.000   0.000  1.00  0.00           C
+HETATM    2  C   MSE A   2       1.000   0.000   0.000  1.00  0.00           C
+HETATM    3  N   MSE A   2       1.500   0.000   0.000  1.00  0.00           N
+ATOM      4  C   ALA A   3       2.000   0.000   0.000  1.00  0.00           C
+HETATM    5  C1  LIG A   4       3.000   0.000   0.000  1.00  0.00           C
+";
+	let options = PdbOptions {
+		filters: Filters { exclude_hetatm: true, ..Filters::default() },
+		..PdbOptions::default()
+	};
+	assert_eq!(load_atoms_from_reader(text.as_bytes(), &options).unwrap().len(), 4);
+}
+
+#[test]
+fn xyzr_columns_stay_aligned_for_loose_coordinates() {
+	// Left-justified, over-precise, and touching coordinate fields, each in its 8-column slot.
+	let record = |fields: &str| format!("ATOM      1  C   ALA A   1    {fields}  1.00  0.00           C\n");
+	let text = record("-12.5   3.0000121e2     ") + &record("1234.567-999.999   0.1  ");
+	let mut out = Vec::new();
+	assert_eq!(write_xyzr_from_reader(text.as_bytes(), &PdbOptions::default(), &mut out).unwrap(), 2);
+	let out = String::from_utf8(out).unwrap();
+	let lines: Vec<&str> = out.lines().collect();
+	let radius = lines[0].rsplit(' ').next().unwrap();
+	assert_eq!(lines[0], format!(" -12.500    3.000  100.000 {radius}"));
+	assert_eq!(lines[1], format!("1234.567 -999.999    0.100 {radius}"));
+
+	let bad = format!("REMARK\n{}", record("   1.0x0   2.000   3.000"));
+	let err = write_xyzr_from_reader(bad.as_bytes(), &PdbOptions::default(), Vec::new()).unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+	assert!(err.to_string().starts_with("line 2:"));
+	// The atom loaders reject the same field with the same error instead of reading it as 0.0.
+	let load_err = load_atoms_from_reader(bad.as_bytes(), &PdbOptions::default()).unwrap_err();
+	assert_eq!((load_err.kind(), load_err.to_string()), (err.kind(), err.to_string()));
+	let models_err = load_models_from_reader(bad.as_bytes(), &PdbOptions::default()).unwrap_err();
+	assert_eq!(models_err.to_string(), err.to_string());
+}
+
+#[test]
+fn pqr_radii_are_read_from_whitespace_or_columns() {
+	let text = "REMARK   1 PQR file\n\
+ATOM      1  N   MET     1     -12.345  23.456   7.890 -0.3000 1.8240\n\
+ATOM      2  CA  MET A   1      1.000   2.000   3.000  0.2100 1.9080\n\
+ATOM      3  C   GLY     2      4.5 5.5 6.5 0.5 1.7\n\
+HETATM    4  O   HOH     3      -1.000-122.000-133.000 -0.8340 1.5000\n\
+TER\nEND\n";
+	let atoms = load_atoms_from_pqr_reader(text.as_bytes()).unwrap();
+	assert_eq!(atoms.len(), 4);
+	assert_eq!((atoms[0].x, atoms[0].radius), (-12.345, 1.824));
+	assert_eq!((atoms[1].z, atoms[1].radius), (3.0, 1.908));
+	assert_eq!(atoms[2].radius, 1.7);
+	assert_eq!((atoms[3].x, atoms[3].y, atoms[3].z, atoms[3].radius), (-1.0, -122.0, -133.0, 1.5));
+	assert_eq!(atoms.iter().map(|a| a.residue_id).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+	assert!(load_atoms_from_pqr_reader("ATOM 1 N MET 1 x y z q r\n".as_bytes()).is_err());
+	assert!(load_atoms_from_pqr_reader("ATOM 1 N MET 1 1 2 3 0 -1\n".as_bytes()).is_err());
+}
+
+#[test]
+fn forced_classes_override_residue_classification() {
+	let text = "\
+HETATM    1  O1  MYL A   1       1.000   2.000   3.000  1.00  0.00           O\n\
+HETATM    2  C1  MYL A   1       2.000   2.000   3.000  1.00  0.00           C\n\
+ATOM      3  CA  ALA A   2       3.000   2.000   3.000  1.00  0.00           C\n";
+	let load = |filters: Filters| {
+		let options = PdbOptions { filters, ..PdbOptions::default() };
+		load_atoms_from_reader(text.as_bytes(), &options).unwrap().len()
+	};
+	assert_eq!(load(Filters { exclude_water: true, ..Filters::default() }), 3);
+	// Names match case-insensitively.
+	assert_eq!(load(Filters { exclude_water: true, force_water: vec!["myl".into()], ..Filters::default() }), 1);
+	assert_eq!(load(Filters { exclude_hetatm: true, ..Filters::default() }), 1);
+	let polymer = Filters { exclude_hetatm: true, force_polymer: vec!["MYL".into()], ..Filters::default() };
+	assert_eq!(load(polymer.clone()), 3);
+	let options = PdbOptions { filters: polymer, ..PdbOptions::default() };
+	let classes = classify_pdb_reader(text.as_bytes(), &options).unwrap();
+	assert!(!classes[0].is_ligand);
+}
+
+#[test]
+fn highest_occupancy_alt_loc_wins_with_alphabetical_ties() {
+	let text = "\
+ATOM      1  N   SER A   1       0.000   0.000   0.000  1.00  0.00           C\n\
+ATOM      2  CA ASER A   1       1.000   0.000   0.000  0.60  0.00           C\n\
+ATOM      3  CA BSER A   1      11.000   0.000   0.000  0.40  0.00           C\n\
+ATOM      4  OG ASER A   1       2.000   0.000   0.000  0.60  0.00           C\n\
+ATOM      5  OG BSER A   1      12.000   0.000   0.000  0.40  0.00           C\n\
+ATOM      6  CB BTHR A   2      23.000   0.000   0.000  0.50  0.00           C\n\
+ATOM      7  CB ATHR A   2       3.000   0.000   0.000  0.50  0.00           C\n";
+	let all = load_atoms_from_reader(text.as_bytes(), &PdbOptions::default()).unwrap();
+	assert_eq!(all.len(), 7);
+	let options = PdbOptions { alt_loc: AltLocPolicy::HighestOccupancy, ..PdbOptions::default() };
+	let best = load_atoms_from_reader(text.as_bytes(), &options).unwrap();
+	let xs: Vec<f32> = best.iter().map(|atom| atom.x).collect();
+	assert_eq!(xs, vec![0.0, 1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn alt_loc_sites_keep_hydrogen_names_and_insertion_codes_apart() {
+	let text = "\
+ATOM      1  HB2ASER A  52       1.000   0.000   0.000  0.70  0.00           H\n\
+ATOM      2  HB2BSER A  52      11.000   0.000   0.000  0.30  0.00           H\n\
+ATOM      3  HB3ASER A  52       2.000   0.000   0.000  0.70  0.00           H\n\
+ATOM      4  HB3BSER A  52      12.000   0.000   0.000  0.30  0.00           H\n\
+ATOM      5  CA ASER A  53       3.000   0.000   0.000  0.60  0.00           C\n\
+ATOM      6  CA BSER A  53      13.000   0.000   0.000  0.40  0.00           C\n\
+ATOM      7  CA AGLY A  53A      4.000   0.000   0.000  0.55  0.00           C\n\
+ATOM      8  CA BGLY A  53A     14.000   0.000   0.000  0.45  0.00           C\n";
+	let options = PdbOptions { alt_loc: AltLocPolicy::HighestOccupancy, ..PdbOptions::default() };
+	let best = load_atoms_from_reader(text.as_bytes(), &options).unwrap();
+	let xs: Vec<f32> = best.iter().map(|atom| atom.x).collect();
+	assert_eq!(xs, vec![1.0, 2.0, 3.0, 4.0]);
+}
+
+#[test]
+fn public_radius_lookup_agrees_with_its_text_form() {
+	for (residue, atom) in [("ALA", "C"), ("GLY", "N"), ("SER", "O"), ("SER", "H"), ("ALA", "CA")] {
+		for use_united in [true, false] {
+			let radius = lookup_radius(residue, atom, use_united);
+			assert!(radius > 0.0);
+			assert_eq!(lookup_radius_text(residue, atom, use_united).parse::<f32>().unwrap(), radius);
+		}
+	}
+	assert!(lookup_radius("ALA", "C", true) > 1.0);
+	assert_eq!(lookup_radius("ALA", "XX9", true), 0.01);
+	assert_eq!(lookup_radius_text("ALA", "XX9", false), "0.01");
+}
+
+#[test]
+fn per_residue_united_override_changes_only_that_residue() {
+	let text = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N\n\
+ATOM      2  H   ALA A   1       1.000   0.000   0.000  1.00  0.00           H\n\
+HETATM    3  C1  LIG A   2       5.000   0.000   0.000  1.00  0.00           C\n\
+HETATM    4  H1  LIG A   2       6.000   0.000   0.000  1.00  0.00           H\n";
+	let united = load_atoms_from_reader(text.as_bytes(), &PdbOptions::default()).unwrap();
+	let options = PdbOptions {
+		united_overrides: [("lig".to_string(), false)].into_iter().collect(),
+		..PdbOptions::default()
+	};
+	let mixed = load_atoms_from_reader(text.as_bytes(), &options).unwrap();
+	assert_eq!(united.len(), mixed.len());
+	assert_eq!((united[0].radius, united[1].radius), (mixed[0].radius, mixed[1].radius));
+	assert_eq!(united[3].radius, lookup_radius("LIG", "H", true));
+	assert_eq!(mixed[3].radius, lookup_radius("LIG", "H", false));
+	assert_ne!(united[3].radius, mixed[3].radius);
+	assert!(options.use_united_for("ALA"));
+	assert!(!options.use_united_for(" LIG"));
+}
+
+#[test]
+fn segment_ids_stand_in_for_blank_chains() {
+	let text = "\
+ATOM      1  N   ALA     1       0.000   0.000   0.000  1.00  0.00      PROA\n\
+ATOM      2  C   ALA     1       1.000   0.000   0.000  1.00  0.00      PROA\n\
+ATOM      3  N   ALA     1       5.000   0.000   0.000  1.00  0.00      PROB\n\
+ATOM      4  C   ALA     1       6.000   0.000   0.000  1.00  0.00      PROB\n";
+	let residues = classify_pdb_reader(text.as_bytes(), &PdbOptions::default()).unwrap();
+	assert_eq!(residues.len(), 2);
+	assert_eq!(residues[0].chain, "PROA");
+	assert_eq!(residues[1].chain, "PROB");
+	// Same residue number and atom names in two segments are distinct atoms, not alternates.
+	let options = PdbOptions { alt_loc: AltLocPolicy::HighestOccupancy, ..PdbOptions::default() };
+	assert_eq!(load_atoms_from_reader(text.as_bytes(), &options).unwrap().len(), 4);
+}
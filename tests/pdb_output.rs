@@ -0,0 +1,144 @@
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::pdb_output::{
+	SURFACE_POINTS_PER_BLOCK, write_surface_pdb, write_surface_pdb_annotated, write_surface_pdb_blocked, write_surface_pdb_filtered,
+};
+
+/// Write `grid` with `write_surface_pdb` to a temp file named `name` and return the text.
+fn surface_pdb_text(grid: &Grid3D, name: &str) -> String {
+	let path = std::env::temp_dir().join(name);
+	write_surface_pdb(grid, path.to_str().unwrap()).unwrap();
+	std::fs::read_to_string(&path).unwrap()
+}
+
+#[test]
+fn surface_blocks_end_with_ter_and_serials_wrap() {
+	// Checkerboard: every filled voxel is a surface point, enough of them to wrap the serial.
+	let n = 60;
+	let mut grid = Grid3D::new(n, n, n, 1.0);
+	for k in 0..n {
+		for j in 0..n {
+			for i in 0..n {
+				if (i + j + k) % 2 == 0 {
+					grid.fill_voxel_ijk(i, j, k);
+				}
+			}
+		}
+	}
+	let points = grid.count_filled();
+	assert!(points + points / SURFACE_POINTS_PER_BLOCK > 99999);
+
+	let path = std::env::temp_dir().join("voxel_sphere_surface_blocks.pdb");
+	write_surface_pdb_blocked(&grid, path.to_str().unwrap()).unwrap();
+	let text = std::fs::read_to_string(&path).unwrap();
+	let chains = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+	let mut serial = 1usize;
+	let mut written = 0usize;
+	let mut ters = 0usize;
+	let mut wrapped = false;
+	let lines: Vec<&str> = text.lines().collect();
+	assert_eq!(lines.last(), Some(&"END"));
+	for (n, line) in lines[..lines.len() - 1].iter().enumerate() {
+		assert_eq!(line[6..11].trim().parse::<usize>().unwrap(), serial, "{line}");
+		if line.starts_with("TER") {
+			assert!(written.is_multiple_of(SURFACE_POINTS_PER_BLOCK) || written == points);
+			let block = (written - 1) / SURFACE_POINTS_PER_BLOCK;
+			assert_eq!(line.as_bytes()[21], chains[block % chains.len()], "{line}");
+			ters += 1;
+		} else {
+			assert!(line.starts_with("ATOM"));
+			let block = written / SURFACE_POINTS_PER_BLOCK;
+			assert_eq!(line.as_bytes()[21], chains[block % chains.len()], "{line}");
+			let resnum: usize = line[22..26].trim().parse().unwrap();
+			assert_eq!(resnum, written % SURFACE_POINTS_PER_BLOCK + 1);
+			written += 1;
+			if written.is_multiple_of(SURFACE_POINTS_PER_BLOCK) || written == points {
+				assert!(lines[n + 1].starts_with("TER"));
+			}
+		}
+		serial = if serial == 99999 { 1 } else { serial + 1 };
+		wrapped |= serial == 1;
+	}
+	assert!(wrapped);
+	assert_eq!(written, points);
+	assert_eq!(ters, points.div_ceil(SURFACE_POINTS_PER_BLOCK));
+}
+
+#[test]
+fn filtered_surface_keeps_only_listed_edge_types() {
+	let mut grid = Grid3D::new(12, 12, 12, 1.0);
+	for k in 2..8 {
+		for j in 3..9 {
+			for i in 1..6 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	grid.fill_voxel_ijk(9, 9, 9);
+	let all = surface_pdb_text(&grid, "voxel_sphere_surface_all.pdb");
+	let path = std::env::temp_dir().join("voxel_sphere_surface_filtered.pdb");
+	write_surface_pdb_filtered(&grid, path.to_str().unwrap(), &[1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+	assert_eq!(std::fs::read_to_string(&path).unwrap(), all);
+
+	write_surface_pdb_filtered(&grid, path.to_str().unwrap(), &[9]).unwrap();
+	let isolated = std::fs::read_to_string(&path).unwrap();
+	assert_eq!(isolated.lines().filter(|line| line.starts_with("ATOM")).count(), 1);
+	assert!(all.lines().filter(|line| line.starts_with("ATOM")).count() > 1);
+}
+
+#[test]
+fn surface_records_follow_voxel_index_order() {
+	let mut grid = Grid3D::new(4, 4, 4, 1.0).with_shifts(-1.0, -2.0, -3.0);
+	for k in 1..3 {
+		for j in 1..3 {
+			for i in 1..3 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	let text = surface_pdb_text(&grid, "voxel_sphere_surface_order.pdb");
+	let lines: Vec<&str> = text.lines().take(3).collect();
+	assert_eq!(lines, vec![
+		"ATOM      1  C   RES A   1       0.000   -1.000   -2.000",
+		"ATOM      2  C   RES A   1       1.000   -1.000   -2.000",
+		"ATOM      3  C   RES A   1       0.000    0.000   -2.000",
+	]);
+	assert_eq!(text.lines().filter(|line| line.starts_with("ATOM")).count(), 8);
+	assert_eq!(text.lines().last(), Some("END"));
+	assert!(!text.contains("TER"));
+}
+
+#[test]
+fn annotated_surface_points_carry_edge_type_and_exposed_faces() {
+	// 3x3x3 block (corners, edges, and face centers are surface; the center is buried) plus a lone voxel.
+	let mut grid = Grid3D::new(7, 7, 7, 1.0);
+	for k in 2..5 {
+		for j in 2..5 {
+			for i in 2..5 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	grid.fill_voxel_ijk(0, 6, 6);
+	let path = std::env::temp_dir().join("voxel_sphere_surface_annotated.pdb");
+	write_surface_pdb_annotated(&grid, path.to_str().unwrap()).unwrap();
+	let text = std::fs::read_to_string(&path).unwrap();
+	let atoms: Vec<&str> = text.lines().filter(|line| line.starts_with("ATOM")).collect();
+	assert_eq!(atoms.len(), 27);
+
+	// (occupancy, B-factor) of the point written for voxel (i, j, k).
+	let annotation = |i: usize, j: usize, k: usize| -> (f32, f32) {
+		let (x, y, z) = grid.voxel_to_physical(i, j, k);
+		let line = atoms
+			.iter()
+			.find(|line| {
+				let coord = |col: usize| line[col..col + 8].trim().parse::<f32>().unwrap();
+				(coord(30) - x).abs() < 1e-3 && (coord(38) - y).abs() < 1e-3 && (coord(46) - z).abs() < 1e-3
+			})
+			.unwrap();
+		(line[54..60].trim().parse().unwrap(), line[60..66].trim().parse().unwrap())
+	};
+	assert_eq!(annotation(2, 2, 2), (3.0, 3.0));
+	assert_eq!(annotation(3, 2, 2), (2.0, 2.0));
+	assert_eq!(annotation(3, 3, 2), (1.0, 1.0));
+	assert_eq!(annotation(0, 6, 6), (6.0, 9.0));
+}
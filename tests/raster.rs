@@ -0,0 +1,245 @@
+mod common;
+
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::{Atom, Downsample};
+
+#[test]
+fn fill_into_matches_parallel_fill_and_reuses_storage() {
+	let mut grid = Grid3D::new(33, 30, 29, 0.7);
+	for round in 0..4 {
+		let atoms = common::random_atoms(150, 0.0, 19.0, 99 + round);
+		let before = grid.memory_report().bitvec_bytes;
+		let filled = grid.fill_accessible_into(&atoms, 1.2);
+		if round > 0 {
+			assert_eq!(before, grid.memory_report().bitvec_bytes);
+		}
+		let mut fresh = Grid3D::new(33, 30, 29, 0.7);
+		assert_eq!(filled, fresh.fill_accessible_parallel(&atoms, 1.2));
+		assert_eq!(common::voxels(&grid), common::voxels(&fresh));
+	}
+}
+
+#[test]
+fn uniform_per_atom_probes_match_single_probe() {
+	let atoms: Vec<Atom> = (0..20).map(|i| Atom::new(3.0 + i as f32 * 0.7, 8.0, 9.1, 1.6)).collect();
+	let mut single = Grid3D::new(30, 20, 20, 0.9);
+	let filled = single.fill_accessible_parallel(&atoms, 1.4);
+	let mut variable = Grid3D::new(30, 20, 20, 0.9);
+	assert_eq!(filled, variable.fill_accessible_variable(&atoms, &[1.4; 20]).unwrap());
+	assert_eq!(common::voxels(&single), common::voxels(&variable));
+	assert!(variable.fill_accessible_variable(&atoms, &[1.0]).is_err());
+
+	let mut wider = Grid3D::new(30, 20, 20, 0.9);
+	let mut probes = [1.4; 20];
+	probes[0] = 3.0;
+	assert!(wider.fill_accessible_variable(&atoms, &probes).unwrap() > filled);
+}
+
+#[test]
+fn contraction_with_other_probe_still_uses_that_probe() {
+	let atoms = common::random_atoms(30, 4.0, 10.0, 5);
+	let mut filled = Grid3D::new(24, 24, 24, 0.75);
+	filled.fill_accessible_parallel(&atoms, 1.4);
+
+	// A mismatched probe only warns; the contraction still uses the probe it was given.
+	let mut same = filled.clone();
+	let mut mismatched = filled.clone();
+	same.contract_exclusion_parallel(1.4);
+	mismatched.contract_exclusion_parallel(1.0);
+	assert!(mismatched.count_filled() > same.count_filled());
+	assert!(mismatched.count_filled() < filled.count_filled());
+}
+
+#[test]
+fn atom_new_takes_defaults_and_copies() {
+	let atom = Atom::new(1.0, 2.0, 3.0, 1.7);
+	assert_eq!(atom, Atom { x: 1.0, y: 2.0, z: 3.0, radius: 1.7, ..Atom::default() });
+	assert_eq!(atom.residue_id, 0);
+	let mut copy = atom;
+	copy.residue_id = 7;
+	assert_eq!((atom.residue_id, copy.residue_id), (0, 7));
+	assert_eq!([atom; 2][1], atom);
+}
+
+/// Sub-voxel centers inside the accessible spheres for each voxel of `grid`, from a `factor`-times finer fill.
+fn sub_voxel_coverage(grid: &Grid3D, atoms: &[Atom], probe: f32, factor: usize) -> Vec<usize> {
+	let step = grid.grid_size / factor as f32;
+	let offset = 0.5 * step - 0.5 * grid.grid_size;
+	let mut fine = Grid3D::new(grid.len_i * factor, grid.len_j * factor, grid.len_k * factor, step);
+	fine.x_shift = grid.x_shift + offset;
+	fine.y_shift = grid.y_shift + offset;
+	fine.z_shift = grid.z_shift + offset;
+	fine.fill_accessible_parallel(atoms, probe);
+	let mut coverage = vec![0usize; grid.total_voxels];
+	for k in 0..fine.len_k {
+		for j in 0..fine.len_j {
+			for i in 0..fine.len_i {
+				if fine.get_voxel_ijk(i, j, k) {
+					coverage[grid.ijk_to_index(i / factor, j / factor, k / factor)] += 1;
+				}
+			}
+		}
+	}
+	coverage
+}
+
+#[test]
+fn supersampled_modes_follow_sub_voxel_coverage() {
+	let atoms = common::random_atoms(12, 3.0, 8.0, 21);
+	let blank = Grid3D::new(18, 18, 18, 0.8);
+	let coverage = sub_voxel_coverage(&blank, &atoms, 1.0, 2);
+
+	let mut majority = blank.clone();
+	let filled = majority.fill_accessible_supersampled(&atoms, 1.0, 2, Downsample::Majority);
+	let expected: Vec<u8> = coverage.iter().map(|&inside| (2 * inside > 8) as u8).collect();
+	assert_eq!(majority.to_u8_array(), expected);
+	assert_eq!(filled, majority.count_filled());
+
+	let mut preserving = blank.clone();
+	let filled = preserving.fill_accessible_supersampled(&atoms, 1.0, 2, Downsample::VolumePreserving);
+	let inside_total: usize = coverage.iter().sum();
+	assert_eq!(filled, (inside_total + 4) / 8);
+	for (idx, &inside) in coverage.iter().enumerate() {
+		if inside == 0 {
+			assert!(!preserving.get_voxel_index(idx));
+		}
+		if inside == 8 {
+			assert!(preserving.get_voxel_index(idx));
+		}
+	}
+
+	let mut plain = blank.clone();
+	plain.fill_accessible_parallel(&atoms, 1.0);
+	for mode in [Downsample::Majority, Downsample::VolumePreserving] {
+		let mut single = blank.clone();
+		single.fill_accessible_supersampled(&atoms, 1.0, 1, mode);
+		assert_eq!(single.to_u8_array(), plain.to_u8_array());
+	}
+}
+
+#[test]
+fn volume_preserving_supersampling_tracks_sphere_volume() {
+	let mut plain_error = 0.0;
+	let mut supersampled_error = 0.0;
+	for step in 0..8 {
+		let radius = 1.3 + 0.17 * step as f32;
+		let atom = Atom::new(5.03 + 0.071 * step as f32, 4.97, 5.11, radius);
+		let analytic = 4.0 / 3.0 * std::f64::consts::PI * radius.powi(3) as f64;
+		let mut plain = Grid3D::new(20, 20, 20, 0.5);
+		plain.fill_accessible_parallel(&[atom], 0.0);
+		plain_error += (plain.filled_volume() - analytic).abs();
+		let mut fine = Grid3D::new(20, 20, 20, 0.5);
+		fine.fill_accessible_supersampled(&[atom], 0.0, 4, Downsample::VolumePreserving);
+		supersampled_error += (fine.filled_volume() - analytic).abs();
+	}
+	assert!(supersampled_error < plain_error);
+}
+
+#[test]
+fn default_factor_two_supersampling_beats_a_plain_fill() {
+	assert_eq!(Downsample::default(), Downsample::VolumePreserving);
+	let mut plain_error = 0.0;
+	let mut supersampled_error = 0.0;
+	for step in 0..8 {
+		let radius = 1.3 + 0.17 * step as f32;
+		let atom = Atom::new(5.03 + 0.071 * step as f32, 4.97, 5.11, radius);
+		let analytic = 4.0 / 3.0 * std::f64::consts::PI * radius.powi(3) as f64;
+		let mut errors = [0.0; 2];
+		for (error, factor) in errors.iter_mut().zip([1, 2]) {
+			let mut grid = Grid3D::new(20, 20, 20, 0.5);
+			grid.fill_accessible_supersampled(&[atom], 0.0, factor, Downsample::default());
+			*error = (grid.filled_volume() - analytic).abs();
+		}
+		plain_error += errors[0];
+		supersampled_error += errors[1];
+	}
+	assert!(supersampled_error < plain_error, "factor 2 {supersampled_error} vs factor 1 {plain_error}");
+}
+
+#[test]
+fn contraction_radius_is_independent_of_fill_probe() {
+	let atoms = [Atom::new(12.0, 12.0, 12.0, 3.0), Atom::new(16.3, 13.1, 11.2, 2.5), Atom::new(9.0, 15.5, 14.0, 2.0)];
+	for (probe, radius) in [(3.0f32, 1.5f32), (1.4, 3.7), (2.0, 2.0), (0.5, 4.6)] {
+		let mut grid = Grid3D::new(32, 32, 32, 0.5);
+		grid.fill_accessible_parallel(&atoms, probe);
+		let accessible = grid.clone();
+		let depth = accessible.distance_to(false);
+		let filled = grid.contract_by_radius(radius);
+		let mut expected = 0;
+		for idx in 0..grid.total_voxels {
+			// A voxel survives when no empty voxel lies closer than the contraction radius.
+			let keep = accessible.get_voxel_index(idx) && depth.data[idx] * depth.data[idx] >= radius * radius - 1e-3;
+			assert_eq!(grid.get_voxel_index(idx), keep, "probe {probe} radius {radius} idx {idx}");
+			expected += keep as usize;
+		}
+		assert_eq!(filled, expected);
+		assert!(filled < accessible.count_filled());
+	}
+}
+
+#[test]
+fn or_fill_accumulates_like_one_combined_fill() {
+	let a = [Atom::new(3.0, 3.0, 3.0, 1.7), Atom::new(4.5, 3.2, 3.0, 1.5)];
+	let b = [Atom::new(6.0, 5.0, 4.0, 1.8), Atom::new(7.0, 6.0, 5.0, 1.2)];
+	let all: Vec<Atom> = a.iter().chain(b.iter()).copied().collect();
+	let mut combined = Grid3D::new(24, 24, 24, 0.5);
+	let filled = combined.fill_accessible_parallel(&all, 1.4);
+
+	let mut merged = combined.empty_like();
+	merged.fill_accessible_parallel(&a, 1.4);
+	assert_eq!(merged.fill_accessible_or(&b, 1.4), filled);
+	assert_eq!(merged.to_u8_array(), combined.to_u8_array());
+	let mut contracted = combined.clone();
+	contracted.contract_exclusion_parallel(1.4);
+	merged.contract_exclusion_parallel(1.4);
+	assert_eq!(merged.to_u8_array(), contracted.to_u8_array());
+
+	let mut from_empty = combined.empty_like();
+	from_empty.fill_accessible_or(&a, 1.4);
+	from_empty.fill_accessible_or(&b, 1.4);
+	assert_eq!(from_empty.to_u8_array(), combined.to_u8_array());
+	assert_eq!(from_empty.filled_count_cached(), filled);
+}
+
+#[test]
+fn raster_margin_never_misses_a_voxel_inside_the_sphere() {
+	for grid_size in [0.25f32, 0.5, 1.0, 1.7] {
+		for step in (1..20).step_by(3) {
+			let radius = step as f32 * 0.37;
+			for offset_step in 0..5 {
+				let offset = offset_step as f32 * 0.23 * grid_size;
+				let n = ((2.0 * (radius + 1.4) / grid_size) as usize) + 8;
+				let mut grid = Grid3D::new(n, n, n, grid_size).with_shifts(-1.3, 2.1, 0.7);
+				let (cx, cy, cz) = grid.voxel_to_physical(n / 2, n / 2, n / 2);
+				let atom = Atom::new(cx + offset, cy - offset * 0.5, cz + offset * 0.3, radius);
+				grid.fill_accessible_parallel(&[atom], 1.4);
+				let cutoff = (radius + 1.4) as f64 - 1e-3 * grid_size as f64;
+				for k in 0..n {
+					for j in 0..n {
+						for i in 0..n {
+							let (x, y, z) = grid.voxel_to_physical(i, j, k);
+							let d = (((x - atom.x) as f64).powi(2) + ((y - atom.y) as f64).powi(2) + ((z - atom.z) as f64).powi(2)).sqrt();
+							if d < cutoff {
+								assert!(grid.get_voxel_ijk(i, j, k), "missed ({i}, {j}, {k}) grid {grid_size} radius {radius}");
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn measure_matches_the_separate_steps() {
+	let atoms = [Atom::new(5.0, 5.0, 5.0, 2.0), Atom::new(7.0, 5.5, 5.0, 1.7)];
+	let mut measured = Grid3D::new(30, 30, 30, 0.5);
+	let measurement = measured.measure(&atoms, 1.0);
+	let mut manual = Grid3D::new(30, 30, 30, 0.5);
+	let filled = manual.fill_accessible_parallel(&atoms, 1.0);
+	assert_eq!(measurement.filled_voxels, filled);
+	assert_eq!(measurement.volume_a3, manual.filled_volume());
+	assert_eq!(measurement.surface_a2, manual.estimate_surface_area_with_edges().0);
+	assert!(measurement.surface_a2 > 0.0);
+	assert_eq!(measured.to_u8_array(), manual.to_u8_array());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use voxel_sphere::voxel_grid::grid::Grid3D;
+
+#[test]
+fn raycast_hits_first_filled_voxel() {
+	let mut grid = Grid3D::new(20, 20, 20, 0.5).with_shifts(-5.0, -5.0, -5.0);
+	for k in 8..12 {
+		for j in 8..12 {
+			for i in 8..12 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	let (x, y, z) = grid.voxel_to_physical(9, 10, 11);
+	assert_eq!(grid.raycast([-100.0, y, z], [1.0, 0.0, 0.0]), Some((8, 10, 11)));
+	assert_eq!(grid.raycast([100.0, y, z], [-1.0, 0.0, 0.0]), Some((11, 10, 11)));
+	assert_eq!(grid.raycast([x, -100.0, z], [0.0, 3.0, 0.0]), Some((9, 8, 11)));
+	assert_eq!(grid.raycast([x, y, 100.0], [0.0, 0.0, -1.0]), Some((9, 10, 11)));
+	assert_eq!(grid.raycast([x, y, z], [0.0, 0.0, 1.0]), Some((9, 10, 11)));
+	assert_eq!(grid.raycast([-100.0, y, z], [-1.0, 0.0, 0.0]), None);
+	assert_eq!(grid.raycast([-100.0, 100.0, z], [1.0, 0.0, 0.0]), None);
+	assert_eq!(grid.raycast([x, y, z], [0.0, 0.0, 0.0]), None);
+	let (x0, y0, z0) = grid.voxel_to_physical(0, 0, 0);
+	assert_eq!(grid.raycast([x0 - 1.0, y0 - 1.0, z0 - 1.0], [1.0, 1.0, 1.0]), Some((8, 8, 8)));
+
+	let mut anisotropic = Grid3D::new_anisotropic(10, 10, 10, [0.5, 1.0, 2.0]);
+	anisotropic.fill_voxel_ijk(3, 4, 5);
+	let (x, y, _) = anisotropic.voxel_to_physical(3, 4, 5);
+	assert_eq!(anisotropic.raycast([x, y, -50.0], [0.0, 0.0, 1.0]), Some((3, 4, 5)));
+}
+
+#[test]
+fn raycast_matches_fine_ray_marching() {
+	let mut rng = common::Lcg::new(12345);
+	let mut random = || rng.next_f32() * 2.0 - 1.0;
+	let mut grid = Grid3D::new(12, 12, 12, 1.0);
+	for idx in 0..grid.total_voxels {
+		if random() > 0.7 {
+			grid.fill_voxel_index(idx);
+		}
+	}
+	for _ in 0..500 {
+		let origin = [random() * 20.0 + 5.0, random() * 20.0 + 5.0, random() * 20.0 + 5.0];
+		let direction = [random(), random(), random()];
+		let norm = (direction[0] * direction[0] + direction[1] * direction[1] + direction[2] * direction[2]).sqrt();
+		let mut marched = None;
+		let mut t = 0.0f32;
+		while t < 60.0 {
+			let cell = [0, 1, 2].map(|a| (origin[a] + direction[a] / norm * t + 0.5).floor());
+			if cell.iter().all(|&c| (0.0..12.0).contains(&c)) && grid.get_voxel_ijk(cell[0] as usize, cell[1] as usize, cell[2] as usize) {
+				marched = Some((cell[0] as usize, cell[1] as usize, cell[2] as usize));
+				break;
+			}
+			t += 0.001;
+		}
+		assert_eq!(grid.raycast(origin, direction), marched, "origin {origin:?} direction {direction:?}");
+	}
+}
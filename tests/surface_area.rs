@@ -0,0 +1,104 @@
+use std::f64::consts::PI;
+
+use voxel_sphere::voxel_grid::grid::Grid3D;
+use voxel_sphere::voxel_grid::raster::Atom;
+use voxel_sphere::voxel_grid::surface_area::Neighborhood;
+
+#[test]
+fn neighborhood_modes_approach_sphere_area() {
+	for radius in [5.0f64, 10.0, 20.0] {
+		let n = (2.0 * radius) as usize + 8;
+		let center = n as f64 / 2.0 + 0.3;
+		let mut grid = Grid3D::new(n, n, n, 1.0);
+		for k in 0..n {
+			for j in 0..n {
+				for i in 0..n {
+					let d2 = (i as f64 - center).powi(2)
+						+ (j as f64 - center + 0.1).powi(2)
+						+ (k as f64 - center - 0.2).powi(2);
+					if d2 < radius * radius {
+						grid.fill_voxel_ijk(i, j, k);
+					}
+				}
+			}
+		}
+		let analytic = 4.0 * PI * radius * radius;
+		let face = grid.estimate_surface_area_mode(Neighborhood::Face6) / analytic;
+		let edge = grid.estimate_surface_area_mode(Neighborhood::Edge18) / analytic;
+		let vertex = grid.estimate_surface_area_mode(Neighborhood::Vertex26) / analytic;
+		assert!((face - 1.0).abs() < 0.07);
+		assert!((edge - 1.0).abs() < 0.02);
+		assert!((vertex - 1.0).abs() < 0.02);
+	}
+}
+
+#[test]
+fn surface_voxels_of_a_block_are_its_outer_shell() {
+	let mut grid = Grid3D::new(12, 12, 12, 1.0);
+	for k in 2..8 {
+		for j in 3..9 {
+			for i in 1..6 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	assert_eq!(grid.count_surface_voxels(), 5 * 6 * 6 - 3 * 4 * 4);
+	grid.fill_voxel_ijk(10, 10, 10);
+	assert_eq!(grid.count_surface_voxels(), 5 * 6 * 6 - 3 * 4 * 4 + 1);
+	assert_eq!(Grid3D::new(4, 4, 4, 1.0).count_surface_voxels(), 0);
+}
+
+#[test]
+fn cavity_walls_count_as_internal_surface() {
+	let mut ball = Grid3D::new(30, 30, 30, 0.5);
+	ball.add_sphere(15, 15, 15, 10.0);
+	let (external, internal) = ball.external_internal_surface_area();
+	assert_eq!(internal, 0.0);
+	assert!((external - ball.estimate_surface_area_with_edges().0).abs() < 1e-9);
+
+	let mut shell = ball.clone();
+	shell.remove_sphere(15, 15, 15, 6.0);
+	let (shell_external, shell_internal) = shell.external_internal_surface_area();
+	assert!((shell_external - external).abs() < 1e-9);
+	assert!(shell_internal > 0.2 * shell_external);
+	assert!((shell_external + shell_internal - shell.estimate_surface_area_with_edges().0).abs() < 1e-6);
+}
+
+#[test]
+fn surface_patches_separate_disjoint_blocks() {
+	let mut grid = Grid3D::new(20, 12, 12, 1.0);
+	for k in 3..8 {
+		for j in 3..8 {
+			for i in 2..7 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	for k in 4..7 {
+		for j in 4..7 {
+			for i in 12..16 {
+				grid.fill_voxel_ijk(i, j, k);
+			}
+		}
+	}
+	let (labels, count) = grid.surface_patches();
+	assert_eq!(count, 2);
+	assert_eq!(labels[grid.ijk_to_index(5, 5, 5)], 0);
+	assert_eq!(labels[grid.ijk_to_index(0, 0, 0)], 0);
+	assert_ne!(labels[grid.ijk_to_index(2, 3, 3)], 0);
+	assert_ne!(labels[grid.ijk_to_index(2, 3, 3)], labels[grid.ijk_to_index(12, 4, 4)]);
+	assert_eq!(labels.iter().filter(|&&label| label != 0).count(), grid.count_surface_voxels());
+}
+
+#[test]
+fn lone_atom_sasa_and_sesa_match_sphere_areas() {
+	let atom = [Atom::new(1.0, 2.0, 3.0, 3.0)];
+	let sas = Grid3D::solvent_accessible_surface_area(&atom, 1.4, 0.25).unwrap();
+	let ses = Grid3D::solvent_excluded_surface_area(&atom, 1.4, 0.25).unwrap();
+	let sas_analytic = 4.0 * PI * 4.4f64.powi(2);
+	let ses_analytic = 4.0 * PI * 9.0;
+	assert!((sas / sas_analytic - 1.0).abs() < 0.05);
+	assert!((ses / ses_analytic - 1.0).abs() < 0.05);
+	assert!(Grid3D::solvent_excluded_surface_area(&[], 1.4, 0.25).is_none());
+	assert!(Grid3D::solvent_accessible_surface_area(&atom, 1.4, 0.0).is_none());
+}